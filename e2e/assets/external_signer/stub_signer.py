@@ -0,0 +1,44 @@
+#!/usr/bin/env python3
+"""Reference implementation of the dfx external signer protocol, used by the e2e tests.
+
+Usage: stub_signer.py <path to an Ed25519 private key in PEM format>
+
+Every invocation reads one JSON request from stdin and writes one JSON response to stdout.
+The signing itself is done by the openssl command line tool.
+"""
+import json
+import subprocess
+import sys
+
+
+def openssl(args, data=None):
+    return subprocess.run(
+        ["openssl"] + args, input=data, check=True, capture_output=True
+    ).stdout
+
+
+def main():
+    if len(sys.argv) != 2:
+        print(json.dumps({"error": "usage: stub_signer.py <key.pem>"}))
+        return
+
+    key = sys.argv[1]
+    request = json.loads(sys.stdin.readline())
+    if request.get("version") != 1:
+        print(json.dumps({"error": "unsupported protocol version"}))
+        return
+
+    public_key = openssl(["pkey", "-in", key, "-pubout", "-outform", "DER"]).hex()
+    operation = request.get("operation")
+    if operation == "public_key":
+        print(json.dumps({"public_key": public_key}))
+    elif operation == "sign":
+        message = bytes.fromhex(request["message"])
+        signature = openssl(["pkeyutl", "-sign", "-rawin", "-inkey", key], message)
+        print(json.dumps({"public_key": public_key, "signature": signature.hex()}))
+    else:
+        print(json.dumps({"error": "unknown operation {}".format(operation)}))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1 @@
+dfx config canisters/hello/main fuzz.mo
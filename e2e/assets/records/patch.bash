@@ -0,0 +1 @@
+dfx config canisters/hello/main records.mo
@@ -0,0 +1,52 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    standard_setup
+
+    dfx_new hello
+}
+
+teardown() {
+    dfx_stop
+
+    standard_teardown
+}
+
+@test "canister bench reports a JSON summary of query calls" {
+    install_asset greet
+    dfx_start
+    dfx deploy
+
+    assert_command dfx --output json canister bench hello greet '("Alice")' --concurrency 2 --duration 2s
+    echo "$stdout" > summary.json
+    assert_command jq -r '.kind, .concurrency, .errors, (.successes > 0), (.latency_ms.p50 > 0)' summary.json
+    assert_eq "query
+2
+0
+true
+true"
+}
+
+@test "canister bench reports the cycles consumed by update calls" {
+    install_asset greet
+    dfx_start
+    dfx deploy
+
+    assert_command dfx --output json canister bench hello greet '("Alice")' --update --concurrency 2 --duration 2s
+    echo "$stdout" > summary.json
+    assert_command jq -r '.kind, (.successes > 0), (.cycles_consumed != null)' summary.json
+    assert_eq "update
+true
+true"
+}
+
+@test "canister bench fails for unknown methods" {
+    install_asset greet
+    dfx_start
+    dfx deploy
+
+    assert_command_fail dfx canister bench hello unknown --duration 1s
+    assert_match "hello has no method 'unknown'"
+}
@@ -59,3 +59,45 @@ teardown() {
     dfx canister install hello
     assert_command dfx canister call hello recurse 100
 }
+
+@test "call with JSON arguments and output" {
+    install_asset records
+    dfx_start
+    dfx deploy
+
+    assert_command dfx canister call hello describe '[{"name": "Alice", "age": 30}, {"rectangle": {"width": 3, "height": 4}}]' --type json --output json
+    assert_command jq -r '.[0].greeting, .[0].area, .[0].shape.rectangle.width' <<<"$stdout"
+    assert_eq "Hello, Alice!
+12
+3"
+
+    assert_command dfx canister call hello describe '[{"name": "Alice", "age": 30, "nickname": "Al"}, "point"]' --type json --output json
+    assert_command jq -c '.[0]' <<<"$stdout"
+    assert_eq '{"area":"0","greeting":"Hello, Al!","shape":{"point":null}}'
+
+    assert_command dfx --output json canister call hello describe '[{"name": "Bob", "age": 40}, {"square": 5}]' --type json --output json
+    assert_command jq -r '.reply[0].area' <<<"$stdout"
+    assert_eq "25"
+
+    assert_command_fail dfx canister call hello describe '[{"name": "Bob", "age": 300}, "point"]' --type json
+    assert_match "Invalid JSON arguments"
+}
+
+@test "call records results and compares them with --expect" {
+    install_asset records
+    dfx_start
+    dfx deploy
+
+    assert_command dfx canister call hello describe '(record { name = "Alice"; age = 30 }, variant { square = 3 })' --record golden.json
+    assert_command jq -r '.[0].area' golden.json
+    assert_eq "9"
+
+    assert_command dfx canister call hello describe '(record { name = "Alice"; age = 30 }, variant { square = 3 })' --expect golden.json
+
+    assert_command_fail dfx canister call hello describe '(record { name = "Bob"; age = 30 }, variant { square = 3 })' --expect golden.json
+    assert_match "The result differs from golden.json"
+    assert_match '/0/greeting: expected "Hello, Alice!", found "Hello, Bob!"'
+
+    echo '{ "ignore": ["/0/greeting"] }' > expect.json
+    assert_command dfx canister call hello describe '(record { name = "Bob"; age = 30 }, variant { square = 3 })' --expect golden.json --expect-config expect.json
+}
@@ -0,0 +1,28 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    standard_setup
+}
+
+teardown() {
+    dfx_stop
+
+    standard_teardown
+}
+
+@test "can call a canister using an identity backed by an external signer" {
+    dfx_new # This installs replica and other binaries
+    install_asset external_signer
+    openssl genpkey -algorithm ed25519 -out signer.pem
+    assert_command dfx identity new --external-signer "$(pwd)/stub_signer.py" --external-signer-arg "$(pwd)/signer.pem" signer
+    dfx identity use signer
+    install_asset whoami
+    dfx_start
+    dfx canister --no-wallet create whoami
+    dfx build
+    dfx canister --no-wallet install whoami
+    assert_command dfx canister --no-wallet call whoami whoami
+    assert_match "$(dfx identity get-principal)"
+}
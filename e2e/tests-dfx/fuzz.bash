@@ -0,0 +1,54 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    standard_setup
+
+    dfx_new hello
+}
+
+teardown() {
+    dfx_stop
+
+    standard_teardown
+}
+
+@test "canister fuzz succeeds when no call fails" {
+    install_asset fuzz
+    dfx_start
+    dfx deploy
+
+    assert_command dfx canister fuzz hello --methods echo --iterations 10 --seed 7
+    assert_command jq -r '.calls, (.failures | length), .seed' fuzz-report.json
+    assert_eq "10
+0
+7"
+}
+
+@test "canister fuzz reports traps with their seeds" {
+    install_asset fuzz
+    dfx_start
+    dfx deploy
+
+    assert_command_fail dfx canister fuzz hello --methods half --iterations 20 --report report.json
+    assert_match "calls failed"
+    assert_command jq -r '.failures[0].kind' report.json
+    assert_eq "trap"
+
+    SEED=$(jq -r '.failures[0].seed' report.json)
+    ARGUMENT=$(jq -r '.failures[0].argument' report.json)
+    assert_command_fail dfx canister fuzz hello --methods half --iterations 1 --seed "$SEED" --report again.json
+    assert_command jq -r '.failures[0].argument' again.json
+    assert_eq "$ARGUMENT"
+}
+
+@test "canister fuzz refuses a config for a method that is not fuzzed" {
+    install_asset fuzz
+    dfx_start
+    dfx deploy
+
+    echo '{=}' > half.dhall
+    assert_command_fail dfx canister fuzz hello --methods echo --config half=half.dhall
+    assert_match "not one of the fuzzed methods"
+}
@@ -164,3 +164,64 @@ teardown() {
     assert_command dfx canister --no-wallet call --output idl e2e_project_assets retrieve '("B")'
     assert_eq '(blob "hello")'
 }
+
+@test "identity delegate: calls signed with the session key come from the identity" {
+    install_asset identity
+    dfx_start
+    assert_command dfx identity new jose
+
+    PRINCPAL_ID=$(dfx --identity jose identity get-principal)
+
+    dfx --identity jose canister create e2e_project
+    dfx --identity jose build e2e_project
+    dfx --identity jose canister install e2e_project
+
+    assert_command dfx --identity jose identity delegate --expire-after 10m
+    assert_command dfx --identity jose canister call e2e_project fromCall
+    assert_eq "(principal \"$PRINCPAL_ID\")"
+}
+
+@test "dfx.json can pin the identity used on a network" {
+    install_asset identity
+    dfx_start
+    assert_command dfx identity new alice
+    ALICE_PRINCIPAL=$(dfx --identity alice identity get-principal)
+    cat <<<"$(jq '.networks.local.identity="alice"' dfx.json)" >dfx.json
+
+    dfx canister create e2e_project
+    dfx build e2e_project
+    dfx canister install e2e_project
+
+    assert_command dfx canister call e2e_project fromCall
+    assert_match "$ALICE_PRINCIPAL"
+    assert_command dfx identity whoami
+    assert_eq "alice"
+    assert_command dfx identity get-principal
+    assert_eq "$ALICE_PRINCIPAL"
+
+    # --identity still takes precedence over the pinned identity.
+    assert_command dfx --identity anonymous canister call e2e_project fromCall
+    assert_match "2vxsx-fae"
+}
+
+@test "dfx.json can pin the principal used on a network" {
+    dfx_start
+    assert_command dfx identity new alice
+    ALICE_PRINCIPAL=$(dfx --identity alice identity get-principal)
+    DEFAULT_PRINCIPAL=$(dfx identity get-principal)
+    cat <<<"$(jq '.networks.local.principal="'"$ALICE_PRINCIPAL"'"' dfx.json)" >dfx.json
+
+    assert_command_fail dfx canister create --all
+    assert_match "Identity 'default' has principal $DEFAULT_PRINCIPAL, but dfx.json expects $ALICE_PRINCIPAL on the 'local' network."
+
+    assert_command dfx --identity alice canister create --all
+    assert_command dfx --allow-identity-mismatch canister create --all
+}
+
+@test "dfx.json can pin the wallet used on a network" {
+    dfx_start
+    cat <<<"$(jq '.networks.local.wallet="rwlgt-iiaaa-aaaaa-aaaaa-cai"' dfx.json)" >dfx.json
+
+    assert_command dfx identity get-wallet
+    assert_eq "rwlgt-iiaaa-aaaaa-aaaaa-cai"
+}
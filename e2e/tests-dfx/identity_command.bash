@@ -89,6 +89,53 @@ teardown() {
     assert_eq "abcd4321"
 }
 
+@test "identity new: create an identity backed by an external signer" {
+    install_asset external_signer
+    openssl genpkey -algorithm ed25519 -out signer.pem
+    assert_command dfx identity new --external-signer "$(pwd)/stub_signer.py" --external-signer-arg "$(pwd)/signer.pem" bob
+    assert_command jq -r .external.command "$DFX_CONFIG_ROOT/.config/dfx/identity/bob/identity.json"
+    assert_eq "$(pwd)/stub_signer.py"
+    assert_command jq -r '.external.args[0]' "$DFX_CONFIG_ROOT/.config/dfx/identity/bob/identity.json"
+    assert_eq "$(pwd)/signer.pem"
+
+    assert_command dfx --identity bob identity get-principal
+    BOB_PRINCIPAL="$stdout"
+    assert_command dfx --identity bob identity get-principal
+    assert_eq "$BOB_PRINCIPAL"
+}
+
+@test "identity new: refuses an external signer that does not work" {
+    assert_command_fail dfx identity new --external-signer false bob
+    assert_match "External signer 'false' failed"
+    assert_command_fail dfx identity use bob
+}
+
+@test "identity new: generate a key on a SoftHSM token" {
+    SOFTHSM2_MODULE=${SOFTHSM2_MODULE:-/usr/lib/softhsm/libsofthsm2.so}
+    [ -f "$SOFTHSM2_MODULE" ] || skip "SoftHSM is not installed"
+    export SOFTHSM2_CONF="$(pwd)/softhsm2.conf"
+    mkdir tokens
+    echo "directories.tokendir = $(pwd)/tokens" > "$SOFTHSM2_CONF"
+    softhsm2-util --init-token --free --label dfx-e2e --so-pin 5678 --pin 1234
+    export DFX_HSM_PIN=1234
+
+    assert_command dfx identity hsm list-slots --pkcs11-lib-path "$SOFTHSM2_MODULE"
+    assert_match '"dfx-e2e"'
+    SLOT=$(dfx identity hsm list-slots --pkcs11-lib-path "$SOFTHSM2_MODULE" | grep '"dfx-e2e"' | cut -d: -f1)
+
+    assert_command dfx identity new --hsm-pkcs11-lib-path "$SOFTHSM2_MODULE" --hsm-slot "$SLOT" --hsm-key-id abcd01 --hsm-generate-key bob
+    assert_command jq -r .hsm.slot "$DFX_CONFIG_ROOT/.config/dfx/identity/bob/identity.json"
+    assert_eq "$SLOT"
+
+    assert_command dfx identity hsm list-keys --pkcs11-lib-path "$SOFTHSM2_MODULE" --slot "$SLOT"
+    assert_match 'abcd01 "bob"'
+
+    assert_command_fail dfx identity new --hsm-pkcs11-lib-path "$SOFTHSM2_MODULE" --hsm-slot "$SLOT" --hsm-key-id abcd01 --hsm-generate-key alice
+    assert_match "There already is a key with id abcd01"
+
+    assert_command dfx --identity bob identity get-principal
+}
+
 @test "identity new: key_id must be hex digits" {
     assert_command_fail dfx identity new --hsm-pkcs11-lib-path xxx --hsm-key-id abcx bob
     assert_match "Key id must contain only hex digits"
@@ -233,6 +280,36 @@ teardown() {
     assert_eq "abcd4321"
 }
 
+##
+## dfx identity delegate
+##
+
+@test "identity delegate: creates and revokes a session key" {
+    assert_command dfx identity new alice
+    assert_command dfx --identity alice identity delegate --expire-after 30m
+    assert_match 'Created a session key for identity "alice"'
+    assert_command test -f "$DFX_CONFIG_ROOT/.config/dfx/identity/alice/session.pem"
+    assert_command jq -r .expiration "$DFX_CONFIG_ROOT/.config/dfx/identity/alice/delegation.json"
+
+    assert_command dfx --identity alice identity revoke-delegation
+    assert_match 'Deleted the session key of identity "alice"'
+    assert_command_fail test -f "$DFX_CONFIG_ROOT/.config/dfx/identity/alice/session.pem"
+    assert_command_fail test -f "$DFX_CONFIG_ROOT/.config/dfx/identity/alice/delegation.json"
+}
+
+@test "identity delegate: keeps the principal of the identity" {
+    assert_command dfx identity new alice
+    PRINCIPAL=$(dfx --identity alice identity get-principal)
+    assert_command dfx --identity alice identity delegate
+    assert_command dfx --identity alice identity get-principal
+    assert_eq "$PRINCIPAL"
+}
+
+@test "identity delegate: the anonymous identity cannot delegate" {
+    assert_command_fail dfx --identity anonymous identity delegate
+    assert_match "The anonymous identity cannot delegate."
+}
+
 ##
 ## dfx identity use
 ##
@@ -0,0 +1,152 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    standard_setup
+
+    dfx_new
+}
+
+teardown() {
+    dfx_stop
+
+    standard_teardown
+}
+
+@test "ledger account-id can print the account of a subaccount or another principal" {
+    DEFAULT_ACCOUNT=$(dfx ledger account-id)
+    PRINCIPAL=$(dfx identity get-principal)
+
+    assert_command dfx ledger account-id --of-principal "$PRINCIPAL"
+    assert_eq "$DEFAULT_ACCOUNT"
+
+    assert_command dfx ledger account-id --subaccount 0
+    assert_eq "$DEFAULT_ACCOUNT"
+
+    SUBACCOUNT_ONE=$(dfx ledger account-id --subaccount 1)
+    assert_command dfx ledger account-id --subaccount 0000000000000000000000000000000000000000000000000000000000000001
+    assert_eq "$SUBACCOUNT_ONE"
+    assert_neq "$DEFAULT_ACCOUNT" "$SUBACCOUNT_ONE"
+
+    # 64 digits are hex even if they are all decimal digits: ...0010 is byte 0x10, not index 10.
+    SUBACCOUNT_SIXTEEN=$(dfx ledger account-id --subaccount 16)
+    assert_command dfx ledger account-id --subaccount 0000000000000000000000000000000000000000000000000000000000000010
+    assert_eq "$SUBACCOUNT_SIXTEEN"
+    assert_neq "$(dfx ledger account-id --subaccount 10)" "$SUBACCOUNT_SIXTEEN"
+
+    assert_command_fail dfx ledger account-id --subaccount 0x1
+    assert_match "Must be a subaccount index or 64 hex digits."
+}
+
+@test "ledger pending lists the journaled operations of the network" {
+    assert_command dfx ledger pending
+    assert_match "No pending ledger operations."
+
+    JOURNAL="$DFX_CONFIG_ROOT/.config/dfx/identity/default/ledger_journal.json"
+    cat >"$JOURNAL" <<JSON
+{
+  "operations": [
+    {
+      "kind": "top-up",
+      "network": "local",
+      "memo": 1347768404,
+      "amount": { "e8s": 100000000 },
+      "fee": { "e8s": 10000 },
+      "to": "$(dfx ledger account-id)",
+      "notify": {
+        "to_canister": "rkp4c-7iaaa-aaaaa-aaaca-cai",
+        "max_fee": { "e8s": 10000 }
+      },
+      "created_at_time": 1630000000000000000,
+      "block_height": 42
+    }
+  ]
+}
+JSON
+
+    assert_command dfx ledger pending
+    assert_match "1630000000000000000 top-up of 1.00000000 ICP to .*: sent, not notified at BlockHeight 42"
+
+    assert_command dfx ledger --network ic pending
+    assert_match "No pending ledger operations."
+
+    assert_command_fail dfx ledger resume 1
+    assert_match "There is no pending ledger operation with id 1."
+
+    assert_command_fail dfx ledger pending --discard 1
+    assert_match "There is no pending ledger operation with id 1."
+
+    assert_command dfx ledger pending --discard 1630000000000000000
+    assert_match "Discarded top-up 1630000000000000000"
+    assert_command dfx ledger pending
+    assert_match "No pending ledger operations."
+}
+
+@test "ledger transfer-batch validates every row and skips completed rows" {
+    ACCOUNT=$(dfx ledger account-id)
+    cat >payouts.csv <<CSV
+account,amount,memo
+$ACCOUNT,1.5,1
+not-an-account,lots,2
+$ACCOUNT,1.5,1
+CSV
+
+    assert_command_fail dfx ledger transfer-batch payouts.csv
+    assert_match "line 3: invalid account id"
+    assert_match "line 3: invalid amount"
+    assert_match "line 4: duplicates line 2, use a different memo"
+
+    cat >payouts.csv <<CSV
+# monthly payouts
+$ACCOUNT,1.5,1
+CSV
+    cat >payouts.csv.results.json <<JSON
+{
+  "completed": [
+    { "line": 2, "to": "$ACCOUNT", "amount": { "e8s": 150000000 }, "memo": 1, "block_height": 7 }
+  ]
+}
+JSON
+
+    assert_command dfx ledger transfer-batch payouts.csv
+    assert_match "Skipping 1 rows that were transferred already"
+    assert_match "Nothing to transfer."
+
+    cat >payouts.csv <<CSV
+# monthly payouts
+$ACCOUNT,2.5,1
+CSV
+    assert_command_fail dfx ledger transfer-batch payouts.csv
+    assert_match "Line 2 of 'payouts.csv' changed since its row was transferred at BlockHeight 7."
+}
+
+@test "ledger install-local refuses networks that are not local" {
+    assert_command_fail dfx ledger --network ic install-local --ledger-wasm ledger.wasm --cmc-wasm cmc.wasm
+    assert_match "The network 'ic' is not a local network."
+}
+
+@test "ledger install-local installs a ledger the ledger commands use" {
+    [ -z "$LEDGER_WASM" ] || [ -z "$CMC_WASM" ] && skip "set LEDGER_WASM and CMC_WASM to run"
+    dfx_start
+
+    assert_command dfx ledger install-local --ledger-wasm "$LEDGER_WASM" --cmc-wasm "$CMC_WASM" --initial-balance default=100
+    assert_match "Recorded the ledger canister"
+    assert_command jq -r .networks.local.ledger_canister_id dfx.json
+    assert_neq "null"
+
+    assert_command dfx ledger balance
+    assert_eq "100.00000000 ICP"
+
+    dfx identity new alice
+    ALICE_ACCOUNT=$(dfx --identity alice ledger account-id)
+    assert_command dfx ledger transfer "$ALICE_ACCOUNT" --amount 1.5 --memo 7
+    assert_match "Transfer sent at BlockHeight: 1"
+
+    assert_command dfx ledger block 1
+    assert_match "Memo: 7"
+    assert_match "Transfer 1.50000000 ICP from $(dfx ledger account-id) to $ALICE_ACCOUNT"
+
+    assert_command dfx ledger transactions --account "$ALICE_ACCOUNT" --output json
+    assert_match '"height": 1'
+}
@@ -0,0 +1,64 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    standard_setup
+
+    dfx_new hello
+}
+
+teardown() {
+    dfx_stop
+
+    standard_teardown
+}
+
+@test "--output json prints identity results as json" {
+    assert_command dfx --output json identity whoami
+    assert_command jq -r .identity <<<"$stdout"
+    assert_eq "default"
+
+    PRINCIPAL=$(dfx identity get-principal)
+    assert_command dfx --output json identity get-principal
+    assert_command jq -r .principal <<<"$stdout"
+    assert_eq "$PRINCIPAL"
+
+    assert_command dfx --output json identity list
+    assert_command jq -r '.selected, .identities[]' <<<"$stdout"
+    assert_match "default"
+}
+
+@test "--output json prints the results of deploy, canister and wallet commands" {
+    dfx_start
+
+    assert_command dfx --output json deploy
+    # The logs of the deployment go to stderr, so stdout is a single JSON document.
+    assert_command jq -r '.canisters[] | select(.canister == "hello") | .id' <<<"$stdout"
+    assert_eq "$(dfx canister id hello)"
+
+    assert_command dfx --output json canister id hello
+    assert_command jq -r .id <<<"$stdout"
+    assert_eq "$(dfx canister id hello)"
+
+    assert_command dfx --output json canister call hello greet '("Alice")'
+    assert_command jq -r .reply <<<"$stdout"
+    assert_eq '("Hello, Alice!")'
+
+    assert_command dfx --output json canister call --async hello greet '("Bob")'
+    assert_command jq -r .request_id <<<"$stdout"
+    assert_match "^0x"
+
+    assert_command dfx --output json wallet balance
+    assert_command jq '.cycles > 0' <<<"$stdout"
+    assert_eq "true"
+}
+
+@test "--output json prints the outputs of build" {
+    dfx_start
+    dfx canister create --all
+
+    assert_command dfx --output json build hello
+    assert_command jq -r '.[] | select(.canister == "hello") | .wasm' <<<"$stdout"
+    assert_match "hello.wasm$"
+}
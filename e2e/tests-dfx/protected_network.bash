@@ -0,0 +1,75 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    standard_setup
+
+    dfx_new hello
+}
+
+teardown() {
+    dfx_stop
+
+    standard_teardown
+}
+
+protect_local_network() {
+    cat <<<"$(jq '.networks.local.protected=true' dfx.json)" >dfx.json
+}
+
+@test "destructive operations on a protected network require typing the canister name" {
+    dfx_start
+    dfx deploy hello
+    protect_local_network
+
+    assert_command_fail dfx canister uninstall-code hello </dev/null
+    assert_match "Uninstall code and delete all state of canister on protected network 'local'"
+    assert_match "Aborted"
+
+    echo world | assert_command_fail dfx canister install hello --mode reinstall
+    assert_match "Aborted"
+
+    echo hello | assert_command dfx canister install hello --mode reinstall
+    assert_command dfx canister call hello greet '("dfx")'
+}
+
+@test "--yes skips the confirmation on a protected network" {
+    dfx_start
+    dfx deploy hello
+    protect_local_network
+
+    assert_command dfx canister uninstall-code hello --yes </dev/null
+    assert_command dfx canister stop hello
+    assert_command dfx canister delete hello --yes </dev/null
+}
+
+@test "non-destructive operations on a protected network need no confirmation" {
+    dfx_start
+    dfx deploy hello
+    protect_local_network
+
+    assert_command dfx canister install hello --mode upgrade </dev/null
+    assert_command dfx canister update-settings hello --compute-allocation 1 </dev/null
+}
+
+@test "update-settings asks for confirmation when the caller drops itself as controller" {
+    dfx_start
+    dfx deploy --no-wallet hello
+    protect_local_network
+    dfx identity new alice
+    ALICE_PRINCIPAL=$(dfx --identity alice identity get-principal)
+
+    assert_command_fail dfx canister --no-wallet update-settings hello --controller "$ALICE_PRINCIPAL" --force </dev/null
+    assert_match "Remove yourself from the controllers of canister"
+}
+
+@test "wallet send on a protected network requires confirmation" {
+    dfx_start
+    protect_local_network
+    WALLET=$(dfx identity get-wallet)
+
+    assert_command_fail dfx wallet send "$WALLET" 1000 </dev/null
+    assert_match "Cycles: 1000"
+    assert_match "Aborted"
+}
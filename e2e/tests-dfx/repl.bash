@@ -0,0 +1,51 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    standard_setup
+
+    dfx_new hello
+}
+
+teardown() {
+    dfx_stop
+
+    standard_teardown
+}
+
+@test "canister repl calls methods and keeps results in variables" {
+    install_asset greet
+    dfx_start
+    dfx deploy
+
+    assert_command dfx canister repl hello <<EOF
+:methods
+let greeting = greet "Alice"
+greet \$greeting
+greet \$nobody
+unknown_method
+:quit
+EOF
+    assert_match "greet : \(text\) -> \(text\) query"
+    assert_match '"Hello, Alice!"'
+    assert_match '"Hello, Hello, Alice!!"'
+    assert_match "Unknown variable"
+    assert_match "hello has no method 'unknown_method'"
+}
+
+@test "canister repl switches identities" {
+    install_asset whoami
+    dfx_start
+    dfx deploy
+    dfx identity new alice
+    ALICE=$(dfx --identity alice identity get-principal)
+
+    assert_command dfx canister repl whoami <<EOF
+:identity alice
+whoami
+:identity
+EOF
+    assert_match "$ALICE"
+    assert_match "alice"
+}
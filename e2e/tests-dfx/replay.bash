@@ -0,0 +1,69 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    standard_setup
+
+    dfx_new hello
+}
+
+teardown() {
+    dfx_stop
+
+    standard_teardown
+}
+
+@test "replay-replica answers recorded calls without a replica" {
+    install_asset counter
+    dfx_start
+    dfx deploy
+
+    assert_command dfx --record-replica update.json canister call hello inc_read
+    assert_eq "(1 : nat)"
+    assert_command dfx --record-replica query.json canister call hello read
+    assert_eq "(1 : nat)"
+    assert_command jq .ingress_expiry update.json
+    assert_neq "0"
+    dfx stop
+
+    assert_command dfx --replay-replica update.json canister call hello inc_read
+    assert_eq "(1 : nat)"
+    assert_command dfx --replay-replica query.json canister call hello read
+    assert_eq "(1 : nat)"
+
+    assert_command_fail dfx --replay-replica update.json canister call hello write '(5)'
+    assert_match "has no response to the call of write"
+}
+
+@test "replay-replica replays identical requests in order" {
+    install_asset counter
+    dfx_start
+    dfx deploy
+
+    cat > script.txt <<'EOF'
+inc_read
+inc_read
+EOF
+    assert_command dfx --record-replica fixture.json canister run-script script.txt --canister hello
+    assert_match "\(1 : nat\)"
+    assert_match "\(2 : nat\)"
+    dfx stop
+
+    assert_command dfx --replay-replica fixture.json canister run-script script.txt --canister hello
+    assert_match "\(1 : nat\)"
+    assert_match "\(2 : nat\)"
+}
+
+@test "replay-replica replays wallet and management canister calls" {
+    install_asset counter
+    dfx_start
+    dfx deploy
+
+    assert_command dfx --record-replica wallet.json wallet set-name Alice
+    assert_command dfx --record-replica settings.json canister update-settings hello --freezing-threshold 100
+    dfx stop
+
+    assert_command dfx --replay-replica wallet.json wallet set-name Alice
+    assert_command dfx --replay-replica settings.json canister update-settings hello --freezing-threshold 100
+}
@@ -0,0 +1,73 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    standard_setup
+
+    dfx_new hello
+}
+
+teardown() {
+    dfx_stop
+
+    standard_teardown
+}
+
+@test "canister run-script runs calls with variables and assertions" {
+    install_asset greet
+    dfx_start
+    dfx deploy
+
+    cat > script.txt <<'EOF'
+# Greet Alice, then greet the greeting.
+let greeting = greet "Alice"
+assert $greeting == "Hello, Alice!"
+assert greet "Bob" == "Hello, Bob!"
+assert hello.greet $greeting == "Hello, Hello, Alice!!"
+EOF
+    assert_command dfx canister run-script script.txt --canister hello
+    assert_match '"Hello, Hello, Alice!!"'
+}
+
+@test "canister run-script compares values using the Candid types" {
+    install_asset records
+    dfx_start
+    dfx deploy
+
+    cat > script.txt <<'EOF'
+:canister hello
+assert describe (record { name = "Alice"; age = 30 }, variant { square = 2 }) == record { greeting = "Hello, Alice!"; area = 4; shape = variant { square = 2 } }
+EOF
+    assert_command dfx canister run-script script.txt
+}
+
+@test "canister run-script stops at the first failed assertion" {
+    install_asset greet
+    dfx_start
+    dfx deploy
+
+    cat > script.txt <<'EOF'
+greet "Alice"
+assert greet "Bob" == "Hi, Bob!"
+greet "Carol"
+EOF
+    assert_command_fail dfx canister run-script script.txt --canister hello
+    assert_match "Failed at script.txt:2"
+    assert_match 'Assertion failed: expected "Hi, Bob!" but found "Hello, Bob!".'
+    assert_not_match "Carol"
+}
+
+@test "canister run-script switches identities" {
+    install_asset whoami
+    dfx_start
+    dfx deploy
+    dfx identity new alice
+    ALICE=$(dfx --identity alice identity get-principal)
+
+    cat > script.txt <<EOF
+:identity alice
+assert whoami == principal "$ALICE"
+EOF
+    assert_command dfx canister run-script script.txt --canister whoami
+}
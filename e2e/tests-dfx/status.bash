@@ -0,0 +1,51 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    standard_setup
+
+    dfx_new hello
+}
+
+teardown() {
+    dfx_stop
+
+    standard_teardown
+}
+
+@test "canister status prints all canisters as json or as a table" {
+    dfx_start
+    dfx deploy
+
+    assert_command dfx canister status --all --output json
+    assert_command jq -r '.[].canister' <<<"$stdout"
+    assert_match "hello"
+    assert_match "hello_assets"
+
+    assert_command dfx canister status --all --output table
+    assert_match "CANISTER .* CYCLES .* BURN/DAY"
+    assert_match "hello .* Running"
+
+    assert_command_fail dfx --output json canister status --all --output table
+    assert_match "output table. conflicts with the global .--output json."
+    assert_command dfx --output json canister status --all --output json
+    assert_command jq -r '.[].canister' <<<"$stdout"
+    assert_match "hello"
+}
+
+@test "canister status estimates the burn rate from previous readings" {
+    dfx_start
+    dfx deploy hello
+
+    ID=$(dfx canister id hello)
+    CYCLES=$(dfx canister status hello --output json | jq '.[0].cycles')
+    DAY_AGO=$(($(date +%s) - 86400))
+    cat >".dfx/local/status/$ID.json" <<JSON
+[ { "timestamp_seconds": $DAY_AGO, "cycles": $((CYCLES + 1000000)) } ]
+JSON
+
+    assert_command dfx canister status hello --output json
+    assert_command jq '.[0].cycles_burned_per_day > 0 and .[0].days_until_frozen > 0' <<<"$stdout"
+    assert_eq "true"
+}
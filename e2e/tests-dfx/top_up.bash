@@ -0,0 +1,50 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    standard_setup
+
+    dfx_new hello
+}
+
+teardown() {
+    dfx_stop
+
+    standard_teardown
+}
+
+set_cycles_policy() {
+    cat <<<"$(jq '.canisters.hello.cycles={"minimum":'"$1"',"target":'"$2"'}' dfx.json)" >dfx.json
+}
+
+@test "canister top-up deposits the shortfall of the cycles policy" {
+    dfx_start
+    dfx deploy hello
+
+    BALANCE=$(dfx canister status hello 2>&1 | grep "Balance: " | sed 's/[^0-9]//g')
+
+    set_cycles_policy 1000 2000
+    assert_command dfx canister top-up --all
+    assert_match "Canister hello has [0-9]+ cycles, at least its minimum of 1000."
+    assert_match "No canister needs a top-up."
+
+    set_cycles_policy $((BALANCE + 1000000000)) $((BALANCE + 2000000000))
+    assert_command dfx canister top-up --all --dry-run
+    assert_match "Would deposit [0-9]+ cycles onto hello"
+
+    assert_command dfx canister top-up hello
+    assert_match "Depositing [0-9]+ cycles onto hello"
+
+    assert_command dfx deploy --ensure-cycles
+    assert_match "at least its minimum"
+}
+
+@test "canister top-up rejects a target below the minimum" {
+    dfx_start
+    dfx canister create --all
+
+    set_cycles_policy 2000 1000
+    assert_command_fail dfx canister top-up --all
+    assert_match "The cycles target of canister hello is below its minimum."
+}
@@ -0,0 +1,38 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    standard_setup
+
+    dfx_new hello
+}
+
+teardown() {
+    dfx_stop
+
+    standard_teardown
+}
+
+@test "trace-http writes requests and responses as JSON lines" {
+    install_asset greet
+    dfx_start
+    dfx deploy
+
+    assert_command dfx --trace-http trace.jsonl canister call hello greet '("Alice")'
+    assert_command jq -r 'select(.endpoint | endswith("/query")) | .request.content.method_name, .request.content.arg, .response.status, .error' trace.jsonl
+    assert_eq "greet
+(\"Alice\")
+replied
+null"
+
+    assert_command dfx --trace-http trace.jsonl canister call --update hello greet '("Bob")'
+    assert_command jq -r 'select(.endpoint | endswith("/call")) | .request.content.arg, (.request_id | startswith("0x"))' trace.jsonl
+    assert_eq "(\"Bob\")
+true"
+
+    # Signatures are redacted.
+    assert_command jq -r 'select(.request.sender_sig != null) | .request.sender_sig' trace.jsonl
+    assert_match "<redacted>"
+    assert_not_match "[0-9a-f]{64}"
+}
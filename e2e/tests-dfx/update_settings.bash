@@ -31,7 +31,7 @@ teardown() {
     ID=$(dfx canister id hello)
 
     # Set controller using canister name and identity name
-    assert_command dfx canister update-settings hello --controller "${BOB_WALLET}"
+    assert_command dfx canister update-settings hello --controller "${BOB_WALLET}" --force
     assert_match "Set controller of \"hello\" to: ${BOB_WALLET}"
 
     # Juana is controller, Jose cannot reinstall
@@ -42,15 +42,15 @@ teardown() {
 
     assert_command dfx identity use bob
     # Set controller using canister id and principal
-    assert_command dfx canister update-settings "${ID}" --controller "${ALICE_WALLET}"
+    assert_command dfx canister update-settings "${ID}" --controller "${ALICE_WALLET}" --force
     assert_match "Set controller of \"${ID}\" to: ${ALICE_WALLET}"
     assert_command_fail dfx canister install hello -m reinstall
 
     # Set controller using combination of name/id and identity/principal
-    assert_command dfx --identity alice canister update-settings hello --controller "${BOB_WALLET}"
+    assert_command dfx --identity alice canister update-settings hello --controller "${BOB_WALLET}" --force
     assert_match "Set controller of \"hello\" to: ${BOB_WALLET}"
 
-    assert_command dfx --identity bob canister update-settings "${ID}" --controller alice
+    assert_command dfx --identity bob canister update-settings "${ID}" --controller alice --force
     assert_match "Set controller of \"${ID}\" to: alice"
 
     # Set controller using invalid principal/identity fails
@@ -81,7 +81,7 @@ teardown() {
     ID=$(dfx canister id hello)
 
     # Set controller using canister name and identity name
-    assert_command dfx canister update-settings hello --controller "${BOB_WALLET}"
+    assert_command dfx canister update-settings hello --controller "${BOB_WALLET}" --force
     assert_match "Set controller of \"hello\" to: ${BOB_WALLET}"
 
     # Juana is controller, Jose cannot reinstall
@@ -92,15 +92,15 @@ teardown() {
 
     assert_command dfx identity use bob
     # Set controller using canister id and principal
-    assert_command dfx canister update-settings "${ID}" --controller "${ALICE_WALLET}"
+    assert_command dfx canister update-settings "${ID}" --controller "${ALICE_WALLET}" --force
     assert_match "Set controller of \"${ID}\" to: ${ALICE_WALLET}"
     assert_command_fail dfx canister install hello -m reinstall
 
     # Set controller using combination of name/id and identity/principal
-    assert_command dfx --identity alice canister update-settings hello --controller "${BOB_WALLET}"
+    assert_command dfx --identity alice canister update-settings hello --controller "${BOB_WALLET}" --force
     assert_match "Set controller of \"hello\" to: ${BOB_WALLET}"
 
-    assert_command dfx --identity bob canister update-settings "${ID}" --controller alice
+    assert_command dfx --identity bob canister update-settings "${ID}" --controller alice --force
     assert_match "Set controller of \"${ID}\" to: alice"
 
     # Set controller using invalid principal/identity fails
@@ -173,3 +173,90 @@ teardown() {
     assert_command dfx canister info hello
     assert_match "Controllers: ${WALLETS_SORTED}"
 }
+
+@test "add and remove controllers" {
+    assert_command dfx identity new alice
+    assert_command dfx identity new bob
+
+    assert_command dfx identity use alice
+
+    dfx_start
+    ALICE_WALLET=$(dfx --identity alice identity get-wallet)
+    BOB_WALLET=$(dfx --identity bob identity get-wallet)
+
+    dfx canister create hello
+    dfx build hello
+    dfx canister install hello
+
+    assert_command dfx canister update-settings hello --add-controller "${BOB_WALLET}"
+    assert_match "\+ ${BOB_WALLET}"
+    assert_command dfx --identity bob canister install hello -m reinstall
+
+    assert_command dfx canister update-settings hello --remove-controller "${BOB_WALLET}"
+    assert_match "- ${BOB_WALLET}"
+    assert_command_fail dfx --identity bob canister install hello -m reinstall
+}
+
+@test "refuses to remove the caller from the controllers unless forced" {
+    assert_command dfx identity new alice
+    assert_command dfx identity new bob
+
+    assert_command dfx identity use alice
+
+    dfx_start
+    ALICE_WALLET=$(dfx --identity alice identity get-wallet)
+    BOB_WALLET=$(dfx --identity bob identity get-wallet)
+
+    dfx canister create hello
+    dfx build hello
+    dfx canister install hello
+
+    assert_command_fail dfx canister update-settings hello --remove-controller "${ALICE_WALLET}"
+    assert_match "Refusing to update the controllers of \"hello\""
+    assert_command_fail dfx canister update-settings hello --controller "${BOB_WALLET}"
+    assert_match "Pass --force to do it anyway."
+
+    assert_command dfx canister update-settings hello --add-controller "${BOB_WALLET}" --remove-controller "${ALICE_WALLET}" --force
+    assert_match "- ${ALICE_WALLET}"
+    assert_match "\+ ${BOB_WALLET}"
+}
+
+@test "deploy applies the settings changed in dfx.json" {
+    dfx_start
+    dfx deploy hello
+
+    assert_command dfx canister settings diff
+    assert_match "hello: in sync"
+
+    cat <<<"$(jq '.canisters.hello.initialization_values.compute_allocation="5"' dfx.json)" >dfx.json
+    assert_command dfx canister settings diff hello
+    assert_match "compute_allocation: dfx.json 5, live 0"
+
+    assert_command dfx deploy hello
+    assert_match "Updating compute_allocation of canister hello: 0 -> 5"
+
+    assert_command dfx canister settings diff hello
+    assert_match "hello: in sync"
+}
+
+@test "deploy applies the controllers in dfx.json" {
+    assert_command dfx identity new alice
+    assert_command dfx identity use alice
+
+    dfx_start
+    dfx deploy hello
+    ALICE_PRINCIPAL=$(dfx identity get-principal)
+    ALICE_WALLET=$(dfx identity get-wallet)
+
+    cat <<<"$(jq '.canisters.hello.initialization_values.controllers=["alice","'"$ALICE_WALLET"'"]' dfx.json)" >dfx.json
+    assert_command dfx canister settings diff hello
+    assert_match "controllers: dfx.json"
+
+    assert_command dfx deploy hello
+    assert_command dfx canister info hello
+    assert_match "$ALICE_PRINCIPAL"
+
+    cat <<<"$(jq '.canisters.hello.initialization_values.controllers=["aaaaa-aa"]' dfx.json)" >dfx.json
+    assert_command_fail dfx deploy hello
+    assert_match "Add one of them to keep control of the canister."
+}
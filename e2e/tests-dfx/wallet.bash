@@ -171,3 +171,18 @@ teardown() {
     assert_command dfx canister --no-wallet call e2e_project amInitializer
     assert_eq '(true)'
 }
+
+@test "wallet events lists created canisters with their names" {
+    dfx_new hello
+    dfx_start
+
+    dfx canister create hello
+    HELLO_ID=$(dfx canister id hello)
+
+    assert_command dfx wallet events
+    assert_match "Created canister $HELLO_ID \(hello\) with [0-9]+ cycles"
+
+    assert_command dfx wallet events --output json
+    assert_match "\"CanisterCreated\""
+    assert_match "\"canister_name\": \"hello\""
+}
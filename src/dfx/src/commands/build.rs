@@ -1,11 +1,14 @@
-use crate::lib::builders::BuildConfig;
+use crate::lib::builders::{BuildConfig, IdlBuildOutput, WasmBuildOutput};
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::models::canister::CanisterPool;
 use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::output::{print_json, OutputFormat};
 use crate::lib::provider::create_agent_environment;
 
 use clap::Clap;
+use serde::Serialize;
+use std::path::PathBuf;
 
 /// Builds all or specific canisters from the code in your project. By default, all canisters are built.
 #[derive(Clap)]
@@ -30,6 +33,15 @@ pub struct CanisterBuildOpts {
     network: Option<String>,
 }
 
+/// The output of a canister build, for the json output.
+#[derive(Serialize)]
+struct BuiltCanister {
+    canister: String,
+    id: String,
+    wasm: PathBuf,
+    candid: PathBuf,
+}
+
 pub fn exec(env: &dyn Environment, opts: CanisterBuildOpts) -> DfxResult {
     let env = create_agent_environment(env, opts.network)?;
 
@@ -74,5 +86,24 @@ pub fn exec(env: &dyn Environment, opts: CanisterBuildOpts) -> DfxResult {
         BuildConfig::from_config(&config)?.with_build_mode_check(build_mode_check),
     )?;
 
+    if env.get_output_format() == OutputFormat::Json {
+        let built: Vec<BuiltCanister> = canister_pool
+            .get_canister_list()
+            .into_iter()
+            .filter_map(|canister| {
+                canister.get_build_output().map(|output| {
+                    let WasmBuildOutput::File(wasm) = &output.wasm;
+                    let IdlBuildOutput::File(candid) = &output.idl;
+                    BuiltCanister {
+                        canister: canister.get_name().to_string(),
+                        id: output.canister_id.to_text(),
+                        wasm: wasm.clone(),
+                        candid: candid.clone(),
+                    }
+                })
+            })
+            .collect();
+        print_json(&built)?;
+    }
     Ok(())
 }
@@ -0,0 +1,334 @@
+use crate::commands::canister::call::call_method;
+use crate::commands::canister::session::Target;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_utils::{self, CallSender};
+use crate::lib::operations::canister::get_canister_status;
+use crate::lib::output::print_result;
+use crate::lib::root_key::fetch_root_key_if_needed;
+use crate::util::{blob_from_arguments, expiry_duration};
+
+use anyhow::{anyhow, bail};
+use clap::Clap;
+use humanize_rs::duration;
+use ic_agent::AgentError;
+use num_traits::cast::ToPrimitive;
+use serde::Serialize;
+use slog::{info, warn};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Calls a method of a canister concurrently for a while, and reports the latency, throughput
+/// and failures of the calls. For update calls it also reports the cycles they consumed.
+#[derive(Clap)]
+pub struct CanisterBenchOpts {
+    /// Specifies the name or id of the canister to call.
+    canister: String,
+
+    /// Specifies the method to call.
+    method: String,
+
+    /// Specifies the argument of every call.
+    argument: Option<String>,
+
+    /// Specifies the data type of the argument.
+    #[clap(long, requires("argument"), possible_values(&["idl", "raw", "json"]))]
+    r#type: Option<String>,
+
+    /// Sends query calls. This is the default if the method is a query method.
+    #[clap(long)]
+    query: bool,
+
+    /// Sends update calls. This is the default if the method is not a query method.
+    #[clap(long, conflicts_with("query"))]
+    update: bool,
+
+    /// Specifies the number of calls in flight at any time.
+    #[clap(long, default_value("10"))]
+    concurrency: u64,
+
+    /// Specifies how long to keep calling, e.g. `30s` or `2m`.
+    #[clap(long, default_value("30s"))]
+    duration: String,
+}
+
+#[derive(Serialize)]
+struct Latency {
+    mean: f64,
+    p50: f64,
+    p90: f64,
+    p99: f64,
+    max: f64,
+}
+
+#[derive(Serialize)]
+struct BenchSummary {
+    canister: String,
+    id: String,
+    method: String,
+    kind: &'static str,
+    concurrency: u64,
+    duration_seconds: f64,
+    calls: u64,
+    successes: u64,
+    /// The number of rejected calls by reject code.
+    rejects: BTreeMap<u64, u64>,
+    errors: u64,
+    throughput_per_second: f64,
+    /// Of the successful calls, in milliseconds.
+    latency_ms: Option<Latency>,
+    cycles_consumed: Option<u64>,
+    cycles_per_call: Option<f64>,
+}
+
+/// The outcomes of the calls of one worker.
+#[derive(Default)]
+struct Outcomes {
+    latencies: Vec<Duration>,
+    rejects: BTreeMap<u64, u64>,
+    errors: u64,
+}
+
+pub async fn exec(
+    env: &dyn Environment,
+    opts: CanisterBenchOpts,
+    call_sender: &CallSender,
+) -> DfxResult {
+    let log = env.get_logger();
+    fetch_root_key_if_needed(env).await?;
+
+    let target = Target::load(env, &opts.canister)?;
+    let method_type = target.method_type(&opts.method);
+    if target.service.is_some() && method_type.is_none() {
+        bail!("{} has no method '{}'.", target.name, opts.method);
+    }
+    let is_query = if opts.query {
+        true
+    } else if opts.update {
+        false
+    } else {
+        method_type
+            .as_ref()
+            .map_or(false, |(_, func)| func.is_query())
+    };
+    if opts.concurrency == 0 {
+        bail!("The concurrency must be at least 1.");
+    }
+    let duration = duration::parse(&opts.duration)
+        .map_err(|_| anyhow!("Cannot parse duration as a duration (e.g. `30s`, `1m 30s`)"))?;
+    let arg_value = blob_from_arguments(
+        opts.argument.as_deref(),
+        None,
+        opts.r#type.as_deref(),
+        &method_type,
+    )?;
+
+    // The cycles consumed by update calls are the difference of the balance of the canister
+    // before and after.
+    let balance_before = if is_query {
+        None
+    } else {
+        match cycle_balance(env, &target, call_sender).await {
+            Ok(balance) => Some(balance),
+            Err(err) => {
+                warn!(
+                    log,
+                    "Cannot read the cycle balance of {}, so the cycles consumed are not reported: {:#}",
+                    target.name,
+                    err
+                );
+                None
+            }
+        }
+    };
+
+    info!(
+        log,
+        "Calling {} of {} with {} concurrent {} calls for {}.",
+        opts.method,
+        target.name,
+        opts.concurrency,
+        if is_query { "query" } else { "update" },
+        opts.duration
+    );
+    let start = Instant::now();
+    let deadline = start + duration;
+    let workers = (0..opts.concurrency).map(|_| {
+        let arg_value = arg_value.clone();
+        let method = opts.method.as_str();
+        let canister_id = target.id;
+        async move {
+            let mut outcomes = Outcomes::default();
+            while Instant::now() < deadline {
+                let call_start = Instant::now();
+                let result = call_method(
+                    env,
+                    call_sender,
+                    canister_id,
+                    method,
+                    arg_value.clone(),
+                    is_query,
+                    0,
+                )
+                .await;
+                match result {
+                    Ok(_) => outcomes.latencies.push(call_start.elapsed()),
+                    Err(err) => match err.downcast_ref::<AgentError>() {
+                        Some(AgentError::ReplicaError { reject_code, .. }) => {
+                            *outcomes.rejects.entry(*reject_code).or_default() += 1
+                        }
+                        _ => outcomes.errors += 1,
+                    },
+                }
+            }
+            outcomes
+        }
+    });
+    let outcomes = futures::future::join_all(workers).await;
+    let elapsed = start.elapsed();
+
+    let mut latencies = vec![];
+    let mut rejects = BTreeMap::new();
+    let mut errors = 0;
+    for worker in outcomes {
+        latencies.extend(worker.latencies);
+        for (code, count) in worker.rejects {
+            *rejects.entry(code).or_default() += count;
+        }
+        errors += worker.errors;
+    }
+    latencies.sort();
+    let successes = latencies.len() as u64;
+    let calls = successes + rejects.values().sum::<u64>() + errors;
+
+    let cycles_consumed = match balance_before {
+        Some(before) => match cycle_balance(env, &target, call_sender).await {
+            Ok(after) => Some(before.saturating_sub(after)),
+            Err(err) => {
+                warn!(
+                    log,
+                    "Cannot read the cycle balance of {}: {:#}", target.name, err
+                );
+                None
+            }
+        },
+        None => None,
+    };
+
+    let summary = BenchSummary {
+        canister: target.name.clone(),
+        id: target.id.to_text(),
+        method: opts.method.clone(),
+        kind: if is_query { "query" } else { "update" },
+        concurrency: opts.concurrency,
+        duration_seconds: elapsed.as_secs_f64(),
+        calls,
+        successes,
+        rejects,
+        errors,
+        throughput_per_second: successes as f64 / elapsed.as_secs_f64(),
+        latency_ms: latency(&latencies),
+        cycles_consumed,
+        cycles_per_call: cycles_consumed
+            .filter(|_| calls > 0)
+            .map(|cycles| cycles as f64 / calls as f64),
+    };
+    print_result(env, &summary, print_summary)
+}
+
+/// Read the cycle balance of the canister. Only its controllers can, which is usually the wallet
+/// of the identity rather than the identity that the calls are sent with.
+async fn cycle_balance(
+    env: &dyn Environment,
+    target: &Target,
+    call_sender: &CallSender,
+) -> DfxResult<u64> {
+    let timeout = expiry_duration();
+    let status = match get_canister_status(env, target.id, timeout, call_sender).await {
+        Ok(status) => status,
+        Err(err) if *call_sender == CallSender::SelectedId => {
+            let wallet_sender = identity_utils::call_sender(env, &None, false, true)
+                .await
+                .map_err(|_| err)?;
+            get_canister_status(env, target.id, timeout, &wallet_sender).await?
+        }
+        Err(err) => return Err(err),
+    };
+    Ok(status.cycles.0.to_u64().unwrap_or(u64::MAX))
+}
+
+/// The statistics of sorted latencies, in milliseconds.
+/// Percentiles use the nearest rank: the smallest latency that `p` percent of the calls reach.
+fn latency(sorted: &[Duration]) -> Option<Latency> {
+    let last = sorted.last()?;
+    let ms = |duration: &Duration| duration.as_secs_f64() * 1000.0;
+    let percentile = |p: usize| {
+        let rank = (sorted.len() * p + 99) / 100;
+        ms(&sorted[rank.max(1) - 1])
+    };
+    Some(Latency {
+        mean: sorted.iter().map(ms).sum::<f64>() / sorted.len() as f64,
+        p50: percentile(50),
+        p90: percentile(90),
+        p99: percentile(99),
+        max: ms(last),
+    })
+}
+
+fn print_summary(summary: &BenchSummary) {
+    println!(
+        "{} {} calls of {} on {} in {:.1}s with concurrency {}",
+        summary.calls,
+        summary.kind,
+        summary.method,
+        summary.canister,
+        summary.duration_seconds,
+        summary.concurrency
+    );
+    println!(
+        "  Succeeded:  {} ({:.1} calls/s)",
+        summary.successes, summary.throughput_per_second
+    );
+    for (code, count) in &summary.rejects {
+        println!("  Rejected:   {} (reject code {})", count, code);
+    }
+    if summary.errors > 0 {
+        println!("  Errors:     {}", summary.errors);
+    }
+    if let Some(latency) = &summary.latency_ms {
+        println!(
+            "  Latency:    mean {:.1}ms, p50 {:.1}ms, p90 {:.1}ms, p99 {:.1}ms, max {:.1}ms",
+            latency.mean, latency.p50, latency.p90, latency.p99, latency.max
+        );
+    }
+    if let (Some(cycles), Some(per_call)) = (summary.cycles_consumed, summary.cycles_per_call) {
+        println!("  Cycles:     {} ({:.0} per call)", cycles, per_call);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_percentiles() {
+        let sorted: Vec<Duration> = (1..=100).map(Duration::from_secs).collect();
+        let latency = latency(&sorted).unwrap();
+        assert_eq!(latency.p50, 50_000.0);
+        assert_eq!(latency.p90, 90_000.0);
+        assert_eq!(latency.p99, 99_000.0);
+        assert_eq!(latency.max, 100_000.0);
+        assert_eq!(latency.mean, 50_500.0);
+
+        let sorted: Vec<Duration> = (1..=10).map(Duration::from_secs).collect();
+        let latency = super::latency(&sorted).unwrap();
+        assert_eq!(latency.p50, 5_000.0);
+        assert_eq!(latency.p90, 9_000.0);
+        assert_eq!(latency.p99, 10_000.0);
+
+        let latency = super::latency(&[Duration::from_secs(1)]).unwrap();
+        assert_eq!(latency.p50, 1_000.0);
+        assert_eq!(latency.p99, 1_000.0);
+        assert!(super::latency(&[]).is_none());
+    }
+}
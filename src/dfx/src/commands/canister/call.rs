@@ -1,24 +1,29 @@
 use crate::lib::environment::Environment;
-use crate::lib::error::{DfxError, DfxResult};
+use crate::lib::error::DfxResult;
+use crate::lib::golden::{self, ExpectConfig};
 use crate::lib::identity::identity_utils::CallSender;
-use crate::lib::identity::Identity;
 use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::operations::call::{wallet_call, CallIn};
 use crate::lib::operations::canister::get_local_cid_and_candid_path;
+use crate::lib::output::print_result;
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::lib::waiter::waiter_with_exponential_backoff;
 use crate::util::clap::validators::cycle_amount_validator;
-use crate::util::{blob_from_arguments, expiry_duration, get_candid_type, print_idl_blob};
+use crate::util::{
+    blob_from_arguments, format_idl_blob, get_candid_type, ingress_expiry, json_from_idl_blob,
+};
 
 use anyhow::{anyhow, bail, Context};
-use candid::{CandidType, Decode, Deserialize};
+use candid::parser::typing::TypeEnv;
+use candid::types::Function;
+use candid::{CandidType, Decode, Deserialize, Encode};
 use clap::{ArgSettings, Clap};
 use ic_types::principal::Principal as CanisterId;
-use ic_utils::canister::{Argument, Canister};
 use ic_utils::interfaces::management_canister::builders::{CanisterInstall, CanisterSettings};
 use ic_utils::interfaces::management_canister::MgmtMethod;
-use ic_utils::interfaces::wallet::{CallForwarder, CallResult};
-use ic_utils::interfaces::Wallet;
+use serde::Serialize;
 use std::option::Option;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 /// Calls a method on a deployed canister.
@@ -53,52 +58,67 @@ pub struct CanisterCallOpts {
     random: Option<String>,
 
     /// Specifies the data type for the argument when making the call using an argument.
-    #[clap(long, requires("argument"), possible_values(&["idl", "raw"]))]
+    /// `json` is a JSON array with one element per argument, converted using the Candid
+    /// interface of the method.
+    #[clap(long, requires("argument"), possible_values(&["idl", "raw", "json"]))]
     r#type: Option<String>,
 
     /// Specifies the format for displaying the method's return result.
+    /// `json` is a JSON array with one element per return value, with the record field
+    /// names and variant tags of the Candid interface of the method.
     #[clap(long, conflicts_with("async"),
-        possible_values(&["idl", "raw", "pp"]))]
+        possible_values(&["idl", "raw", "pp", "json"]))]
     output: Option<String>,
 
+    /// Writes the result of the call to a file, as JSON converted with the Candid types of the
+    /// method, to be compared with later results with --expect.
+    #[clap(long, conflicts_with("async"), conflicts_with("expect"))]
+    record: Option<PathBuf>,
+
+    /// Compares the result of the call with a file written by --record,
+    /// and fails with the differences if they do not match.
+    #[clap(long, conflicts_with("async"))]
+    expect: Option<PathBuf>,
+
+    /// Specifies a JSON file with the paths that --expect ignores, e.g.
+    /// `{ "ignore": ["/0/updated_at", "/0/items/*/timestamp"] }`.
+    #[clap(long, requires("expect"))]
+    expect_config: Option<PathBuf>,
+
     /// Specifies the amount of cycles to send on the call.
     /// Deducted from the wallet.
     #[clap(long, validator(cycle_amount_validator))]
     with_cycles: Option<String>,
 }
 
-#[derive(CandidType, Deserialize)]
-struct CallIn {
-    canister: CanisterId,
-    method_name: String,
-    #[serde(with = "serde_bytes")]
-    args: Vec<u8>,
-    cycles: u64,
+#[derive(Serialize)]
+struct SubmittedCall {
+    request_id: String,
 }
 
-async fn do_wallet_call(wallet: &Canister<'_, Wallet>, args: &CallIn) -> DfxResult<Vec<u8>> {
-    let (result,): (Result<CallResult, String>,) = wallet
-        .update_("wallet_call")
-        .with_arg(args)
-        .build()
-        .call_and_wait(waiter_with_exponential_backoff())
-        .await?;
-    Ok(result.map_err(|err| anyhow!(err))?.r#return)
+/// The reply of a canister method, formatted as requested by `--output`.
+/// It is a string, except in the `json` output where it is the converted JSON value.
+#[derive(Serialize)]
+struct Reply {
+    reply: serde_json::Value,
 }
 
-async fn request_id_via_wallet_call(
-    wallet: &Canister<'_, Wallet>,
-    canister: &Canister<'_>,
-    method_name: &str,
-    args: Argument,
-    cycles: u64,
-) -> DfxResult<ic_agent::RequestId> {
-    let call_forwarder: CallForwarder<'_, '_, (CallResult,)> =
-        wallet.call(canister, method_name, args, cycles);
-    call_forwarder
-        .call()
-        .await
-        .map_err(|err| anyhow!("Agent error {}", err))
+/// Print the reply of a canister method.
+pub fn print_reply(
+    env: &dyn Environment,
+    blob: &[u8],
+    output_type: Option<&str>,
+    method_type: &Option<(TypeEnv, Function)>,
+) -> DfxResult {
+    let reply = match output_type {
+        Some("json") => json_from_idl_blob(blob, method_type),
+        _ => format_idl_blob(blob, output_type, method_type).map(serde_json::Value::String),
+    }
+    .context("Invalid data: Invalid IDL blob.")?;
+    print_result(env, &Reply { reply }, |result| match &result.reply {
+        serde_json::Value::String(reply) => println!("{}", reply),
+        reply => println!("{:#}", reply),
+    })
 }
 
 pub fn get_effective_canister_id(
@@ -155,6 +175,57 @@ pub fn get_effective_canister_id(
     }
 }
 
+/// Call a method of a canister as the call sender, and return the reply.
+pub async fn call_method(
+    env: &dyn Environment,
+    call_sender: &CallSender,
+    canister_id: CanisterId,
+    method_name: &str,
+    arg_value: Vec<u8>,
+    is_query: bool,
+    cycles: u64,
+) -> DfxResult<Vec<u8>> {
+    match call_sender {
+        CallSender::SelectedId => {
+            let agent = env
+                .get_agent()
+                .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?;
+            let effective_canister_id = get_effective_canister_id(
+                canister_id == CanisterId::management_canister(),
+                method_name,
+                &arg_value,
+                canister_id,
+            )?;
+            let blob = if is_query {
+                agent
+                    .query(&canister_id, method_name)
+                    .with_effective_canister_id(effective_canister_id)
+                    .with_arg(&arg_value)
+                    .call()
+                    .await?
+            } else {
+                agent
+                    .update(&canister_id, method_name)
+                    .with_effective_canister_id(effective_canister_id)
+                    .with_arg(&arg_value)
+                    .expire_at(ingress_expiry(env))
+                    .call_and_wait(waiter_with_exponential_backoff())
+                    .await?
+            };
+            Ok(blob)
+        }
+        CallSender::Wallet(wallet_id) | CallSender::SelectedIdWallet(wallet_id) => {
+            let call = CallIn {
+                canister: canister_id,
+                method_name: method_name.to_string(),
+                args: arg_value,
+                cycles,
+            };
+            wallet_call(env, *wallet_id, &call, waiter_with_exponential_backoff()).await
+        }
+    }
+}
+
 pub async fn exec(
     env: &dyn Environment,
     opts: CanisterCallOpts,
@@ -215,47 +286,13 @@ pub async fn exec(
 
     fetch_root_key_if_needed(env).await?;
 
-    let timeout = expiry_duration();
-
     // amount has been validated by cycle_amount_validator
     let cycles = opts
         .with_cycles
         .as_deref()
         .map_or(0_u64, |amount| amount.parse::<u64>().unwrap());
 
-    if is_query {
-        let blob = match call_sender {
-            CallSender::SelectedId => {
-                let effective_canister_id = get_effective_canister_id(
-                    is_management_canister,
-                    method_name,
-                    &arg_value,
-                    canister_id,
-                )?;
-                agent
-                    .query(&canister_id, method_name)
-                    .with_effective_canister_id(effective_canister_id)
-                    .with_arg(&arg_value)
-                    .call()
-                    .await?
-            }
-            CallSender::Wallet(wallet_id) | CallSender::SelectedIdWallet(wallet_id) => {
-                let wallet = Identity::build_wallet_canister(*wallet_id, env)?;
-                do_wallet_call(
-                    &wallet,
-                    &CallIn {
-                        canister: canister_id,
-                        method_name: method_name.to_string(),
-                        args: arg_value,
-                        cycles,
-                    },
-                )
-                .await?
-            }
-        };
-        print_idl_blob(&blob, output_type, &method_type)
-            .context("Invalid data: Invalid IDL blob.")?;
-    } else if opts.r#async {
+    if opts.r#async {
         let request_id = match call_sender {
             CallSender::SelectedId => {
                 let effective_canister_id = get_effective_canister_id(
@@ -268,60 +305,57 @@ pub async fn exec(
                     .update(&canister_id, method_name)
                     .with_effective_canister_id(effective_canister_id)
                     .with_arg(&arg_value)
+                    .expire_at(ingress_expiry(env))
                     .call()
                     .await?
             }
             CallSender::Wallet(wallet_id) | CallSender::SelectedIdWallet(wallet_id) => {
-                let wallet = Identity::build_wallet_canister(*wallet_id, env)?;
-                // This is overkill, wallet.call should accept a Principal parameter
-                // Why do we need to construct a Canister?
-                let canister = Canister::builder()
-                    .with_agent(agent)
-                    .with_canister_id(canister_id)
-                    .build()
-                    .map_err(DfxError::from)?;
-                let mut args = Argument::default();
-                args.set_raw_arg(arg_value);
-
-                request_id_via_wallet_call(&wallet, &canister, method_name, args, cycles).await?
-            }
-        };
-        eprint!("Request ID: ");
-        println!("0x{}", String::from(request_id));
-    } else {
-        let blob = match call_sender {
-            CallSender::SelectedId => {
-                let effective_canister_id = get_effective_canister_id(
-                    is_management_canister,
-                    method_name,
-                    &arg_value,
-                    canister_id,
-                )?;
+                let call = CallIn {
+                    canister: canister_id,
+                    method_name: method_name.to_string(),
+                    args: arg_value,
+                    cycles,
+                };
                 agent
-                    .update(&canister_id, method_name)
-                    .with_effective_canister_id(effective_canister_id)
-                    .with_arg(&arg_value)
-                    .expire_after(timeout)
-                    .call_and_wait(waiter_with_exponential_backoff())
+                    .update(wallet_id, "wallet_call")
+                    .with_effective_canister_id(*wallet_id)
+                    .with_arg(Encode!(&call)?)
+                    .expire_at(ingress_expiry(env))
+                    .call()
                     .await?
             }
-            CallSender::Wallet(wallet_id) | CallSender::SelectedIdWallet(wallet_id) => {
-                let wallet = Identity::build_wallet_canister(*wallet_id, env)?;
-                do_wallet_call(
-                    &wallet,
-                    &CallIn {
-                        canister: canister_id,
-                        method_name: method_name.to_string(),
-                        args: arg_value,
-                        cycles,
-                    },
-                )
-                .await?
-            }
         };
+        let result = SubmittedCall {
+            request_id: format!("0x{}", String::from(request_id)),
+        };
+        print_result(env, &result, |result| {
+            eprint!("Request ID: ");
+            println!("{}", result.request_id);
+        })?;
+    } else {
+        let blob = call_method(
+            env,
+            call_sender,
+            canister_id,
+            method_name,
+            arg_value,
+            is_query,
+            cycles,
+        )
+        .await?;
+
+        print_reply(env, &blob, output_type, &method_type)?;
 
-        print_idl_blob(&blob, output_type, &method_type)
-            .context("Invalid data: Invalid IDL blob.")?;
+        if let Some(path) = &opts.record {
+            golden::record(path, &json_from_idl_blob(&blob, &method_type)?)?;
+        }
+        if let Some(path) = &opts.expect {
+            let config = match &opts.expect_config {
+                Some(config_path) => ExpectConfig::load(config_path)?,
+                None => ExpectConfig::default(),
+            };
+            golden::expect(path, &json_from_idl_blob(&blob, &method_type)?, &config)?;
+        }
     }
 
     Ok(())
@@ -3,9 +3,9 @@ use crate::lib::error::DfxResult;
 use crate::lib::ic_attributes::{
     get_compute_allocation, get_freezing_threshold, get_memory_allocation, CanisterSettings,
 };
-use crate::lib::identity::identity_manager::IdentityManager;
-use crate::lib::identity::identity_utils::CallSender;
+use crate::lib::identity::identity_utils::{controller_principal, CallSender};
 use crate::lib::operations::canister::create_canister;
+use crate::lib::output::{named_canister_ids, print_json, OutputFormat};
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::util::clap::validators::cycle_amount_validator;
 use crate::util::clap::validators::{
@@ -13,10 +13,7 @@ use crate::util::clap::validators::{
 };
 use crate::util::expiry_duration;
 
-use anyhow::anyhow;
 use clap::{ArgSettings, Clap};
-use ic_agent::identity::Identity;
-use ic_types::principal::Principal as CanisterId;
 
 /// Creates an empty canister on the Internet Computer and
 /// associates the Internet Computer assigned Canister ID to the canister name.
@@ -75,24 +72,7 @@ pub async fn exec(
         .map(|controllers| {
             controllers
                 .iter()
-                .map(
-                    |controller| match CanisterId::from_text(controller.clone()) {
-                        Ok(principal) => Ok(principal),
-                        Err(_) => {
-                            let current_id = env.get_selected_identity().unwrap();
-                            if current_id == controller {
-                                Ok(env.get_selected_identity_principal().unwrap())
-                            } else {
-                                let identity_name = controller;
-                                IdentityManager::new(env)?
-                                    .instantiate_identity_from_name(identity_name)
-                                    .and_then(|identity| {
-                                        identity.sender().map_err(|err| anyhow!(err))
-                                    })
-                            }
-                        }
-                    },
-                )
+                .map(|controller| controller_principal(env, controller))
                 .collect::<DfxResult<Vec<_>>>()
         })
         .transpose()?;
@@ -127,9 +107,10 @@ pub async fn exec(
             },
         )
         .await?;
-        Ok(())
+        print_created(env, &[canister_name.to_string()])
     } else if opts.all {
         // Create all canisters.
+        let mut created = vec![];
         if let Some(canisters) = &config.get_config().canisters {
             for canister_name in canisters.keys() {
                 let compute_allocation = get_compute_allocation(
@@ -161,10 +142,19 @@ pub async fn exec(
                     },
                 )
                 .await?;
+                created.push(canister_name.clone());
             }
         }
-        Ok(())
+        print_created(env, &created)
     } else {
         unreachable!()
     }
 }
+
+/// In the json format, print the ids of the canisters. The text format only logs them.
+fn print_created(env: &dyn Environment, canister_names: &[String]) -> DfxResult {
+    match env.get_output_format() {
+        OutputFormat::Text => Ok(()),
+        OutputFormat::Json => print_json(&named_canister_ids(env, canister_names)?),
+    }
+}
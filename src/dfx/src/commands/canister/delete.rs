@@ -1,29 +1,29 @@
+use crate::lib::confirmation::{confirm_destructive_operation, DestructiveOperation};
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::ic_attributes::CanisterSettings;
 use crate::lib::identity::identity_utils::CallSender;
 use crate::lib::identity::Identity;
 use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::operations::call::{wallet_call, CallIn};
 use crate::lib::operations::canister;
 use crate::lib::operations::canister::{
-    deposit_cycles, start_canister, stop_canister, update_settings,
+    deposit_cycles, install_code, start_canister, stop_canister, update_settings,
 };
 use crate::lib::provider::create_agent_environment;
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::lib::waiter::waiter_with_timeout;
 use crate::util::assets::wallet_wasm;
 use crate::util::{blob_from_arguments, expiry_duration};
-use ic_utils::call::AsyncCall;
 use ic_utils::interfaces::management_canister::attributes::{
     ComputeAllocation, FreezingThreshold, MemoryAllocation,
 };
 use ic_utils::interfaces::management_canister::CanisterStatus;
 
-use anyhow::anyhow;
+use candid::Encode;
 use clap::Clap;
 use ic_types::Principal;
-use ic_utils::interfaces::management_canister::builders::InstallMode;
-use ic_utils::interfaces::ManagementCanister;
+use ic_utils::interfaces::management_canister::builders::{CanisterInstall, InstallMode};
 use num_traits::cast::ToPrimitive;
 use slog::info;
 use std::convert::TryFrom;
@@ -54,6 +54,10 @@ pub struct CanisterDeleteOpts {
     /// Withdraw cycles to dank with the given principal.
     #[clap(long, conflicts_with("withdraw-cycles-to-canister"))]
     withdraw_cycles_to_dank_principal: Option<String>,
+
+    /// Skips the confirmation prompt on protected networks.
+    #[clap(long)]
+    yes: bool,
 }
 
 async fn delete_canister(
@@ -64,11 +68,22 @@ async fn delete_canister(
     withdraw_cycles_to_canister: Option<String>,
     withdraw_cycles_to_dank: bool,
     withdraw_cycles_to_dank_principal: Option<String>,
+    yes: bool,
 ) -> DfxResult {
     let log = env.get_logger();
     let mut canister_id_store = CanisterIdStore::for_env(env)?;
     let canister_id =
         Principal::from_text(canister).or_else(|_| canister_id_store.get(canister))?;
+    confirm_destructive_operation(
+        env,
+        &DestructiveOperation {
+            action: "Delete canister",
+            canister,
+            canister_id: Some(canister_id),
+            cycles: None,
+        },
+        yes,
+    )?;
     let mut call_sender = call_sender;
     let to_dank = withdraw_cycles_to_dank || withdraw_cycles_to_dank_principal.is_some();
 
@@ -118,10 +133,6 @@ async fn delete_canister(
                 "Beginning withdrawl of {} cycles to canister {}.", cycles, target_canister_id
             );
 
-            let agent = env
-                .get_agent()
-                .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?;
-            let mgr = ManagementCanister::create(agent);
             let canister_id =
                 Principal::from_text(canister).or_else(|_| canister_id_store.get(canister))?;
 
@@ -143,15 +154,13 @@ async fn delete_canister(
                 canister
             );
             let args = blob_from_arguments(None, None, None, &None)?;
-            let mode = InstallMode::Reinstall;
-            let install_builder = mgr
-                .install_code(&canister_id, &wasm_module)
-                .with_raw_arg(args.to_vec())
-                .with_mode(mode);
-            install_builder
-                .build()?
-                .call_and_wait(waiter_with_timeout(timeout))
-                .await?;
+            let install = CanisterInstall {
+                mode: InstallMode::Reinstall,
+                canister_id,
+                wasm_module,
+                arg: args,
+            };
+            install_code(env, install, timeout, &CallSender::SelectedId).await?;
             start_canister(env, canister_id, timeout, &CallSender::SelectedId).await?;
 
             if !to_dank {
@@ -173,20 +182,13 @@ async fn delete_canister(
                     log,
                     "Transfering {} cycles to dank principal {}.", cycles, dank_target_principal
                 );
-                let dank = ic_utils::Canister::builder()
-                    .with_agent(
-                        env.get_agent()
-                            .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?,
-                    )
-                    .with_canister_id(target_canister_id)
-                    .build()
-                    .unwrap();
-                let wallet = Identity::build_wallet_canister(canister_id, env)?;
-                let opt_principal = Some(dank_target_principal);
-                wallet
-                    .call_forward(dank.update_("mint").with_arg(opt_principal).build(), cycles)?
-                    .call_and_wait(waiter_with_timeout(timeout))
-                    .await?;
+                let call = CallIn {
+                    canister: target_canister_id,
+                    method_name: "mint".to_string(),
+                    args: Encode!(&Some(dank_target_principal))?,
+                    cycles,
+                };
+                wallet_call(env, canister_id, &call, waiter_with_timeout(timeout)).await?;
             }
             stop_canister(env, canister_id, timeout, &CallSender::SelectedId).await?;
             call_sender = &CallSender::SelectedId;
@@ -233,6 +235,7 @@ pub async fn exec(
             opts.withdraw_cycles_to_canister,
             opts.withdraw_cycles_to_dank,
             opts.withdraw_cycles_to_dank_principal,
+            opts.yes,
         )
         .await
     } else if opts.all {
@@ -246,6 +249,7 @@ pub async fn exec(
                     opts.withdraw_cycles_to_canister.clone(),
                     opts.withdraw_cycles_to_dank,
                     opts.withdraw_cycles_to_dank_principal.clone(),
+                    opts.yes,
                 )
                 .await?;
             }
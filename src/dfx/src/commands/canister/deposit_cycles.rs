@@ -3,6 +3,7 @@ use crate::lib::error::DfxResult;
 use crate::lib::identity::identity_utils::CallSender;
 use crate::lib::models::canister_id_store::CanisterIdStore;
 use crate::lib::operations::canister;
+use crate::lib::output::{print_json, OutputFormat};
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::util::clap::validators::cycle_amount_validator;
 use crate::util::expiry_duration;
@@ -10,6 +11,8 @@ use crate::util::expiry_duration;
 use anyhow::bail;
 use clap::Clap;
 use ic_types::Principal;
+use num_traits::cast::ToPrimitive;
+use serde::Serialize;
 use slog::info;
 use std::time::Duration;
 
@@ -30,13 +33,21 @@ pub struct DepositCyclesOpts {
     all: bool,
 }
 
+#[derive(Serialize)]
+struct Deposit {
+    canister: String,
+    id: String,
+    cycles: u64,
+    balance: u64,
+}
+
 async fn deposit_cycles(
     env: &dyn Environment,
     canister: &str,
     timeout: Duration,
     call_sender: &CallSender,
     cycles: u64,
-) -> DfxResult {
+) -> DfxResult<Deposit> {
     let log = env.get_logger();
     let canister_id_store = CanisterIdStore::for_env(env)?;
     let canister_id =
@@ -53,7 +64,12 @@ async fn deposit_cycles(
         "Deposited {} cycles, updated balance: {} cycles", cycles, status.cycles
     );
 
-    Ok(())
+    Ok(Deposit {
+        canister: canister.to_string(),
+        id: canister_id.to_text(),
+        cycles,
+        balance: status.cycles.0.to_u64().unwrap_or(u64::MAX),
+    })
 }
 
 pub async fn exec(
@@ -73,16 +89,21 @@ pub async fn exec(
     fetch_root_key_if_needed(env).await?;
     let timeout = expiry_duration();
 
+    let mut deposits = vec![];
     if let Some(canister) = opts.canister.as_deref() {
-        deposit_cycles(env, &canister, timeout, call_sender, cycles).await
+        deposits.push(deposit_cycles(env, &canister, timeout, call_sender, cycles).await?);
     } else if opts.all {
         if let Some(canisters) = &config.get_config().canisters {
             for canister in canisters.keys() {
-                deposit_cycles(env, &canister, timeout, call_sender, cycles).await?;
+                deposits.push(deposit_cycles(env, &canister, timeout, call_sender, cycles).await?);
             }
         }
-        Ok(())
     } else {
         unreachable!()
     }
+
+    match env.get_output_format() {
+        OutputFormat::Text => Ok(()),
+        OutputFormat::Json => print_json(&deposits),
+    }
 }
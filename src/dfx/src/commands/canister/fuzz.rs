@@ -0,0 +1,266 @@
+use crate::commands::canister::call::call_method;
+use crate::commands::canister::session::Target;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_utils::CallSender;
+use crate::lib::root_key::fetch_root_key_if_needed;
+
+use anyhow::{anyhow, bail};
+use candid::parser::configs::Configs;
+use candid::IDLArgs;
+use clap::Clap;
+use ic_agent::AgentError;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use slog::{info, warn, Logger};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Calls the methods of a canister repeatedly with random arguments generated from their
+/// Candid types, and reports the calls that trap, are rejected, or return values that cannot
+/// be decoded. Fails if any call does.
+#[derive(Clap)]
+pub struct CanisterFuzzOpts {
+    /// Specifies the name or id of the canister to fuzz.
+    canister: String,
+
+    /// Specifies the methods to call, separated by commas. Defaults to all methods of the canister.
+    #[clap(long, multiple(true), use_delimiter(true))]
+    methods: Vec<String>,
+
+    /// Specifies the number of calls of each method.
+    #[clap(long, default_value("100"))]
+    iterations: u64,
+
+    /// Specifies the seed of the first call. Call `i` uses the seed plus `i`, so a failure can be
+    /// reproduced with `--seed <its seed> --iterations 1`. Defaults to a random seed.
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// Specifies a Dhall config for generating the arguments of a method, as `<method>=<file>`.
+    /// It has the format of the config of `dfx canister call --random`.
+    #[clap(long, multiple(true), number_of_values(1))]
+    config: Vec<String>,
+
+    /// Specifies the file that the report is written to.
+    #[clap(long, default_value("fuzz-report.json"))]
+    report: PathBuf,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum FailureKind {
+    /// The canister trapped.
+    Trap,
+    /// The call was rejected, by the canister or the system.
+    Reject,
+    /// The reply does not decode as the return types of the method.
+    Decode,
+    /// Any other error, e.g. of the connection to the replica.
+    Error,
+    /// No argument could be generated from the config, or it could not be encoded.
+    Generate,
+}
+
+#[derive(Serialize)]
+struct Failure {
+    method: String,
+    kind: FailureKind,
+    seed: u64,
+    /// The argument of the call, unless it could not be generated.
+    argument: Option<String>,
+    reject_code: Option<u64>,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct FuzzReport {
+    canister: String,
+    id: String,
+    seed: u64,
+    iterations: u64,
+    calls: u64,
+    failures: Vec<Failure>,
+}
+
+pub async fn exec(
+    env: &dyn Environment,
+    opts: CanisterFuzzOpts,
+    call_sender: &CallSender,
+) -> DfxResult {
+    let log = env.get_logger();
+    fetch_root_key_if_needed(env).await?;
+
+    let target = Target::load(env, &opts.canister)?;
+    let (type_env, _) = target.service.as_ref().ok_or_else(|| {
+        anyhow!(
+            "The Candid interface of {} is unknown, so its arguments cannot be generated.",
+            target.name
+        )
+    })?;
+    let methods = if opts.methods.is_empty() {
+        target.methods()
+    } else {
+        opts.methods
+            .iter()
+            .map(|method| {
+                target
+                    .method_type(method)
+                    .map(|(_, func)| (method.clone(), func))
+                    .ok_or_else(|| anyhow!("{} has no method '{}'.", target.name, method))
+            })
+            .collect::<DfxResult<Vec<_>>>()?
+    };
+
+    let mut configs = BTreeMap::new();
+    for config in &opts.config {
+        let (method, path) = match config.find('=') {
+            Some(i) => (&config[..i], &config[i + 1..]),
+            None => bail!("Expected a config as <method>=<file>, found '{}'.", config),
+        };
+        if !methods.iter().any(|(name, _)| name == method) {
+            bail!(
+                "The config {} is for '{}', which is not one of the fuzzed methods.",
+                path,
+                method
+            );
+        }
+        let dhall = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read the config {}: {}", path, e))?;
+        let config =
+            Configs::from_dhall(&dhall).map_err(|e| anyhow!("Invalid config {}: {}", path, e))?;
+        configs.insert(method.to_string(), config);
+    }
+    let default_config = Configs::from_dhall("{=}")?;
+
+    let seed = opts.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    info!(log, "Fuzzing {} with seed {}.", target.name, seed);
+
+    let mut calls = 0;
+    let mut failures = vec![];
+    for (method, func) in &methods {
+        let config = configs.get(method).unwrap_or(&default_config);
+        info!(
+            log,
+            "Calling {} {} times with random arguments.", method, opts.iterations
+        );
+        for i in 0..opts.iterations {
+            let call_seed = seed.wrapping_add(i);
+            let mut rng = StdRng::seed_from_u64(call_seed);
+            let bytes: Vec<u8> = (0..2048).map(|_| rng.gen::<u8>()).collect();
+            // An argument that cannot be generated or encoded is reported like a failed call,
+            // so that the report still covers the calls made so far.
+            let args = match IDLArgs::any(&bytes, config, type_env, &func.args) {
+                Ok(args) => args,
+                Err(err) => {
+                    failures.push(generate_failure(log, method, call_seed, None, err));
+                    continue;
+                }
+            };
+            let arg_value = match args.to_bytes_with_types(type_env, &func.args) {
+                Ok(arg_value) => arg_value,
+                Err(err) => {
+                    let argument = Some(args.to_string());
+                    failures.push(generate_failure(log, method, call_seed, argument, err));
+                    continue;
+                }
+            };
+
+            calls += 1;
+            let result = call_method(
+                env,
+                call_sender,
+                target.id,
+                method,
+                arg_value,
+                func.is_query(),
+                0,
+            )
+            .await;
+            let failure = match result {
+                Ok(blob) => match IDLArgs::from_bytes_with_types(&blob, type_env, &func.rets) {
+                    Ok(_) => None,
+                    Err(err) => Some((FailureKind::Decode, None, err.to_string())),
+                },
+                Err(err) => Some(match err.downcast_ref::<AgentError>() {
+                    Some(AgentError::ReplicaError {
+                        reject_code,
+                        reject_message,
+                    }) => {
+                        // Reject code 5 is a canister error, i.e. a trap.
+                        let kind = if *reject_code == 5 {
+                            FailureKind::Trap
+                        } else {
+                            FailureKind::Reject
+                        };
+                        (kind, Some(*reject_code), reject_message.clone())
+                    }
+                    _ => (FailureKind::Error, None, format!("{:#}", err)),
+                }),
+            };
+            if let Some((kind, reject_code, message)) = failure {
+                warn!(
+                    log,
+                    "Call of {} with seed {} failed: {}", method, call_seed, message
+                );
+                failures.push(Failure {
+                    method: method.clone(),
+                    kind,
+                    seed: call_seed,
+                    argument: Some(args.to_string()),
+                    reject_code,
+                    message,
+                });
+            }
+        }
+    }
+
+    let report = FuzzReport {
+        canister: target.name.clone(),
+        id: target.id.to_text(),
+        seed,
+        iterations: opts.iterations,
+        calls,
+        failures,
+    };
+    std::fs::write(&opts.report, serde_json::to_string_pretty(&report)?)
+        .map_err(|e| anyhow!("Cannot write the report {}: {}", opts.report.display(), e))?;
+
+    if !report.failures.is_empty() {
+        bail!(
+            "{} of {} calls failed. See the report {}.",
+            report.failures.len(),
+            calls,
+            opts.report.display()
+        );
+    }
+    info!(
+        log,
+        "All {} calls succeeded. The report is in {}.",
+        calls,
+        opts.report.display()
+    );
+    Ok(())
+}
+
+fn generate_failure(
+    log: &Logger,
+    method: &str,
+    seed: u64,
+    argument: Option<String>,
+    err: candid::Error,
+) -> Failure {
+    warn!(
+        log,
+        "No argument of {} could be generated with seed {}: {}", method, seed, err
+    );
+    Failure {
+        method: method.to_string(),
+        kind: FailureKind::Generate,
+        seed,
+        argument,
+        reject_code: None,
+        message: err.to_string(),
+    }
+}
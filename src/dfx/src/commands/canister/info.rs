@@ -1,12 +1,14 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::output::print_result;
 use crate::lib::root_key::fetch_root_key_if_needed;
 
 use anyhow::{anyhow, bail};
 use clap::Clap;
 use ic_agent::AgentError;
 use ic_types::Principal;
+use serde::Serialize;
 use serde_cbor::Value;
 use std::convert::TryFrom;
 
@@ -17,6 +19,12 @@ pub struct InfoOpts {
     canister: String,
 }
 
+#[derive(Serialize)]
+struct CanisterInfo {
+    controllers: Vec<String>,
+    module_hash: Option<String>,
+}
+
 pub async fn exec(env: &dyn Environment, opts: InfoOpts) -> DfxResult {
     let agent = env
         .get_agent()
@@ -55,24 +63,26 @@ pub async fn exec(env: &dyn Environment, opts: InfoOpts) -> DfxResult {
         .read_state_canister_info(canister_id, "module_hash")
         .await
     {
-        Ok(blob) => format!("0x{}", hex::encode(&blob)),
+        Ok(blob) => Some(format!("0x{}", hex::encode(&blob))),
         // If the canister is empty, this path does not exist.
         // The replica doesn't support negative lookups, therefore if the canister
         // is empty, the replica will return lookup_path([], Pruned _) = Unknown
-        Err(AgentError::LookupPathUnknown(_)) | Err(AgentError::LookupPathAbsent(_)) => {
-            "None".to_string()
-        }
+        Err(AgentError::LookupPathUnknown(_)) | Err(AgentError::LookupPathAbsent(_)) => None,
         Err(x) => bail!(x),
     };
 
     let mut controllers_sorted = controllers;
     controllers_sorted.sort();
 
-    println!(
-        "Controllers: {}\nModule hash: {}",
-        controllers_sorted.join(" "),
-        module_hash_hex
-    );
-
-    Ok(())
+    let result = CanisterInfo {
+        controllers: controllers_sorted,
+        module_hash: module_hash_hex,
+    };
+    print_result(env, &result, |result| {
+        println!(
+            "Controllers: {}\nModule hash: {}",
+            result.controllers.join(" "),
+            result.module_hash.as_deref().unwrap_or("None")
+        )
+    })
 }
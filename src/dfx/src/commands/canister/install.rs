@@ -1,9 +1,11 @@
 use crate::lib::canister_info::CanisterInfo;
+use crate::lib::confirmation::{confirm_destructive_operation, DestructiveOperation};
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::identity::identity_utils::CallSender;
 use crate::lib::models::canister_id_store::CanisterIdStore;
 use crate::lib::operations::canister::install_canister;
+use crate::lib::output::{print_json, NamedCanisterId, OutputFormat};
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::util::{blob_from_arguments, expiry_duration, get_candid_init_type};
 
@@ -38,8 +40,12 @@ pub struct CanisterInstallOpts {
     argument: Option<String>,
 
     /// Specifies the data type for the argument when making the call using an argument.
-    #[clap(long, requires("argument"), possible_values(&["idl", "raw"]))]
+    #[clap(long, requires("argument"), possible_values(&["idl", "raw", "json"]))]
     argument_type: Option<String>,
+
+    /// Skips the confirmation prompt on protected networks.
+    #[clap(long)]
+    yes: bool,
 }
 
 pub async fn exec(
@@ -58,10 +64,12 @@ pub async fn exec(
     let mode = InstallMode::from_str(opts.mode.as_str()).map_err(|err| anyhow!(err))?;
     let canister_id_store = CanisterIdStore::for_env(env)?;
 
+    let mut installed = vec![];
     if let Some(canister) = opts.canister.as_deref() {
         let canister_id =
             Principal::from_text(canister).or_else(|_| canister_id_store.get(canister))?;
         let canister_info = CanisterInfo::load(&config, canister, Some(canister_id))?;
+        confirm_reinstall(env, mode, canister, canister_id, opts.yes)?;
 
         let maybe_path = canister_info.get_output_idl_path();
         let init_type = maybe_path.and_then(|path| get_candid_init_type(&path));
@@ -81,7 +89,11 @@ pub async fn exec(
             call_sender,
             installed_module_hash,
         )
-        .await
+        .await?;
+        installed.push(NamedCanisterId {
+            canister: canister.to_string(),
+            id: canister_id.to_text(),
+        });
     } else if opts.all {
         // Install all canisters.
         if let Some(canisters) = &config.get_config().canisters {
@@ -89,6 +101,7 @@ pub async fn exec(
                 let canister_id =
                     Principal::from_text(canister).or_else(|_| canister_id_store.get(canister))?;
                 let canister_info = CanisterInfo::load(&config, canister, Some(canister_id))?;
+                confirm_reinstall(env, mode, canister, canister_id, opts.yes)?;
                 let installed_module_hash =
                     read_module_hash(agent, &canister_id_store, &canister_info).await?;
 
@@ -105,12 +118,43 @@ pub async fn exec(
                     installed_module_hash,
                 )
                 .await?;
+                installed.push(NamedCanisterId {
+                    canister: canister.clone(),
+                    id: canister_id.to_text(),
+                });
             }
         }
-        Ok(())
     } else {
         unreachable!()
     }
+
+    match env.get_output_format() {
+        OutputFormat::Text => Ok(()),
+        OutputFormat::Json => print_json(&installed),
+    }
+}
+
+/// Reinstalling wipes the state of the canister.
+fn confirm_reinstall(
+    env: &dyn Environment,
+    mode: InstallMode,
+    canister: &str,
+    canister_id: Principal,
+    yes: bool,
+) -> DfxResult {
+    if !matches!(mode, InstallMode::Reinstall) {
+        return Ok(());
+    }
+    confirm_destructive_operation(
+        env,
+        &DestructiveOperation {
+            action: "Reinstall canister, deleting all of its state",
+            canister,
+            canister_id: Some(canister_id),
+            cycles: None,
+        },
+        yes,
+    )
 }
 
 async fn read_module_hash(
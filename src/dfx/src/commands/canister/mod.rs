@@ -6,19 +6,26 @@ use crate::lib::provider::create_agent_environment;
 use clap::Clap;
 use tokio::runtime::Runtime;
 
+mod bench;
 mod call;
 mod create;
 mod delete;
 mod deposit_cycles;
+mod fuzz;
 mod id;
 mod info;
 mod install;
+mod repl;
 mod request_status;
+mod run_script;
 mod send;
+mod session;
+mod settings;
 mod sign;
 mod start;
 mod status;
 mod stop;
+mod top_up;
 mod uninstall_code;
 mod update_settings;
 
@@ -49,19 +56,25 @@ pub struct CanisterOpts {
 
 #[derive(Clap)]
 enum SubCommand {
+    Bench(bench::CanisterBenchOpts),
     Call(call::CanisterCallOpts),
     Create(create::CanisterCreateOpts),
     Delete(delete::CanisterDeleteOpts),
     DepositCycles(deposit_cycles::DepositCyclesOpts),
+    Fuzz(fuzz::CanisterFuzzOpts),
     Id(id::CanisterIdOpts),
     Info(info::InfoOpts),
     Install(install::CanisterInstallOpts),
+    Repl(repl::CanisterReplOpts),
     RequestStatus(request_status::RequestStatusOpts),
+    RunScript(run_script::RunScriptOpts),
     Send(send::CanisterSendOpts),
+    Settings(settings::SettingsOpts),
     Sign(sign::CanisterSignOpts),
     Start(start::CanisterStartOpts),
     Status(status::CanisterStatusOpts),
     Stop(stop::CanisterStopOpts),
+    TopUp(top_up::CanisterTopUpOpts),
     UninstallCode(uninstall_code::UninstallCodeOpts),
     UpdateSettings(update_settings::UpdateSettingsOpts),
 }
@@ -71,7 +84,13 @@ pub fn exec(env: &dyn Environment, opts: CanisterOpts) -> DfxResult {
     let runtime = Runtime::new().expect("Unable to create a runtime");
     let default_wallet_proxy = !matches!(
         opts.subcmd,
-        SubCommand::Call(_) | SubCommand::Send(_) | SubCommand::Sign(_)
+        SubCommand::Bench(_)
+            | SubCommand::Call(_)
+            | SubCommand::Fuzz(_)
+            | SubCommand::Repl(_)
+            | SubCommand::RunScript(_)
+            | SubCommand::Send(_)
+            | SubCommand::Sign(_)
     );
 
     runtime.block_on(async {
@@ -83,19 +102,25 @@ pub fn exec(env: &dyn Environment, opts: CanisterOpts) -> DfxResult {
         )
         .await?;
         match opts.subcmd {
+            SubCommand::Bench(v) => bench::exec(&agent_env, v, &call_sender).await,
             SubCommand::Call(v) => call::exec(&agent_env, v, &call_sender).await,
             SubCommand::Create(v) => create::exec(&agent_env, v, &call_sender).await,
             SubCommand::Delete(v) => delete::exec(&agent_env, v, &call_sender).await,
             SubCommand::DepositCycles(v) => deposit_cycles::exec(&agent_env, v, &call_sender).await,
+            SubCommand::Fuzz(v) => fuzz::exec(&agent_env, v, &call_sender).await,
             SubCommand::Id(v) => id::exec(&agent_env, v).await,
             SubCommand::Install(v) => install::exec(&agent_env, v, &call_sender).await,
             SubCommand::Info(v) => info::exec(&agent_env, v).await,
+            SubCommand::Repl(v) => repl::exec(&agent_env, v, &call_sender).await,
             SubCommand::RequestStatus(v) => request_status::exec(&agent_env, v).await,
+            SubCommand::RunScript(v) => run_script::exec(&agent_env, v, &call_sender).await,
             SubCommand::Send(v) => send::exec(&agent_env, v, &call_sender).await,
+            SubCommand::Settings(v) => settings::exec(&agent_env, v, &call_sender).await,
             SubCommand::Sign(v) => sign::exec(&agent_env, v, &call_sender).await,
             SubCommand::Start(v) => start::exec(&agent_env, v, &call_sender).await,
             SubCommand::Status(v) => status::exec(&agent_env, v, &call_sender).await,
             SubCommand::Stop(v) => stop::exec(&agent_env, v, &call_sender).await,
+            SubCommand::TopUp(v) => top_up::exec(&agent_env, v, &call_sender).await,
             SubCommand::UninstallCode(v) => uninstall_code::exec(&agent_env, v, &call_sender).await,
             SubCommand::UpdateSettings(v) => {
                 update_settings::exec(&agent_env, v, &call_sender).await
@@ -0,0 +1,125 @@
+use crate::commands::canister::session::{Session, COMMANDS};
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_utils::CallSender;
+use crate::lib::root_key::fetch_root_key_if_needed;
+
+use clap::Clap;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Editor, Helper};
+
+/// Starts an interactive shell to call the methods of a canister, with command history and
+/// completion of method names. Type `:help` in the shell for its commands.
+#[derive(Clap)]
+pub struct CanisterReplOpts {
+    /// Specifies the name or id of the canister to call.
+    /// Another canister can be selected in the shell with `:canister`.
+    canister: Option<String>,
+}
+
+/// Completes the commands of the shell and the method names of the canister.
+#[derive(Default)]
+struct ReplHelper {
+    methods: Vec<String>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..];
+        let before = line[..start].trim();
+        let candidates: Vec<&str> = if start == 0 && word.starts_with(':') {
+            COMMANDS.to_vec()
+        } else if start == 0
+            || before == ":type"
+            || before == "assert"
+            || (before.starts_with("let ") && before.ends_with('='))
+        {
+            self.methods.iter().map(String::as_str).collect()
+        } else {
+            vec![]
+        };
+        let pairs = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .map(|candidate| Pair {
+                display: candidate.to_string(),
+                replacement: candidate.to_string(),
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+pub async fn exec(
+    env: &dyn Environment,
+    opts: CanisterReplOpts,
+    call_sender: &CallSender,
+) -> DfxResult {
+    fetch_root_key_if_needed(env).await?;
+
+    let mut session = Session::new(env, call_sender.clone());
+    if let Some(canister) = &opts.canister {
+        session.select_canister(canister)?;
+    }
+
+    let mut editor = Editor::<ReplHelper>::new();
+    editor.set_helper(Some(ReplHelper::default()));
+    let history_path = env
+        .get_config()
+        .map(|config| config.get_temp_path().join("repl_history"));
+    if let Some(history_path) = &history_path {
+        // There is no history before the first session.
+        let _ = editor.load_history(history_path);
+    }
+
+    eprintln!("Type :help for the commands, :quit to leave.");
+    loop {
+        if let Some(helper) = editor.helper_mut() {
+            helper.methods = session.method_names();
+        }
+
+        let line = match editor.readline(&session.prompt()) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        };
+        editor.add_history_entry(line.as_str());
+        match session.eval(&line).await {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(err) => eprintln!("Error: {:#}", err),
+        }
+    }
+
+    if let Some(history_path) = &history_path {
+        if let Some(parent) = history_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        editor.save_history(history_path)?;
+    }
+    Ok(())
+}
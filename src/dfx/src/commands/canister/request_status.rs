@@ -1,10 +1,10 @@
+use crate::commands::canister::call::print_reply;
 use crate::lib::environment::Environment;
 use crate::lib::error::{DfxError, DfxResult};
 use crate::lib::models::canister_id_store::CanisterIdStore;
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::lib::waiter::waiter_with_exponential_backoff;
 use crate::util::clap::validators;
-use crate::util::print_idl_blob;
 
 use anyhow::{anyhow, Context};
 use clap::Clap;
@@ -32,7 +32,7 @@ pub struct RequestStatusOpts {
 
     /// Specifies the format for displaying the method's return result.
     #[clap(long,
-        possible_values(&["idl", "raw", "pp"]))]
+        possible_values(&["idl", "raw", "pp", "json"]))]
     output: Option<String>,
 }
 
@@ -97,6 +97,5 @@ pub async fn exec(env: &dyn Environment, opts: RequestStatusOpts) -> DfxResult {
     .map_err(DfxError::from)?;
 
     let output_type = opts.output.as_deref();
-    print_idl_blob(&blob, output_type, &None).context("Invalid data: Invalid IDL blob.")?;
-    Ok(())
+    print_reply(env, &blob, output_type, &None)
 }
@@ -0,0 +1,53 @@
+use crate::commands::canister::session::Session;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_utils::CallSender;
+use crate::lib::root_key::fetch_root_key_if_needed;
+
+use anyhow::{anyhow, bail};
+use clap::Clap;
+use std::path::PathBuf;
+
+/// Runs a script of canister calls against the selected network, and stops at the first
+/// failed call or assertion. Scripts have one statement per line, in the language of
+/// `dfx canister repl`, e.g. `let id = create_user "Alice"` or `assert get_name $id == "Alice"`.
+#[derive(Clap)]
+pub struct RunScriptOpts {
+    /// Specifies the path of the script.
+    script: PathBuf,
+
+    /// Specifies the name or id of the canister that calls without a canister name go to.
+    /// The script can select another one with `:canister`.
+    #[clap(long)]
+    canister: Option<String>,
+}
+
+pub async fn exec(
+    env: &dyn Environment,
+    opts: RunScriptOpts,
+    call_sender: &CallSender,
+) -> DfxResult {
+    let script = std::fs::read_to_string(&opts.script)
+        .map_err(|e| anyhow!("Cannot read the script {}: {}", opts.script.display(), e))?;
+
+    fetch_root_key_if_needed(env).await?;
+
+    let mut session = Session::new(env, call_sender.clone());
+    if let Some(canister) = &opts.canister {
+        session.select_canister(canister)?;
+    }
+    for (i, line) in script.lines().enumerate() {
+        match session.eval(line).await {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(err) => bail!(
+                "Failed at {}:{}: {}\n{:#}",
+                opts.script.display(),
+                i + 1,
+                line.trim(),
+                err
+            ),
+        }
+    }
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::identity::identity_utils::CallSender;
+use crate::lib::output::print_result;
 use crate::lib::sign::signed_message::SignedMessageV1;
 
 use ic_agent::agent::ReplicaV2Transport;
@@ -9,6 +10,7 @@ use ic_agent::{agent::http_transport::ReqwestHttpReplicaV2Transport, RequestId};
 use anyhow::{anyhow, bail};
 use clap::Clap;
 use ic_types::Principal;
+use serde::Serialize;
 use std::{fs::File, path::Path};
 use std::{io::Read, str::FromStr};
 
@@ -23,8 +25,20 @@ pub struct CanisterSendOpts {
     status: bool,
 }
 
+/// The response to a query or request-status call, as hex.
+#[derive(Serialize)]
+struct Response {
+    response: String,
+}
+
+#[derive(Serialize)]
+struct SubmittedCall {
+    request_id: String,
+    canister_id: String,
+}
+
 pub async fn exec(
-    _env: &dyn Environment,
+    env: &dyn Environment,
     opts: CanisterSendOpts,
     call_sender: &CallSender,
 ) -> DfxResult {
@@ -55,10 +69,7 @@ pub async fn exec(
         }
         let envelope = hex::decode(&message.signed_request_status.unwrap())?;
         let response = transport.read_state(canister_id, envelope).await?;
-        eprintln!("To see the content of response, copy-paste the encoded string into cbor.me.");
-        eprint!("Response: ");
-        println!("{}", hex::encode(response));
-        return Ok(());
+        return print_response(env, &response);
     }
 
     eprintln!("Will send message:");
@@ -82,11 +93,7 @@ pub async fn exec(
     match message.call_type.as_str() {
         "query" => {
             let response = transport.query(canister_id, content).await?;
-            eprintln!(
-                "To see the content of response, copy-paste the encoded string into cbor.me."
-            );
-            eprint!("Response: ");
-            println!("{}", hex::encode(response));
+            print_response(env, &response)
         }
         "update" => {
             let request_id = RequestId::from_str(
@@ -101,13 +108,29 @@ pub async fn exec(
             );
             eprintln!("e.g. `dfx canister send message.json --status`");
             eprintln!("Alternatively, if you have the correct identity on this machine, using `dfx canister request-status` with following arguments.");
-            eprint!("Request ID: ");
-            println!("0x{}", String::from(request_id));
-            eprint!("Canister ID: ");
-            println!("{}", canister_id.to_string());
+            let result = SubmittedCall {
+                request_id: format!("0x{}", String::from(request_id)),
+                canister_id: canister_id.to_string(),
+            };
+            print_result(env, &result, |result| {
+                eprint!("Request ID: ");
+                println!("{}", result.request_id);
+                eprint!("Canister ID: ");
+                println!("{}", result.canister_id);
+            })
         }
         // message.validate() guarantee that call_type must be query or update
         _ => unreachable!(),
     }
-    Ok(())
+}
+
+fn print_response(env: &dyn Environment, response: &[u8]) -> DfxResult {
+    let result = Response {
+        response: hex::encode(response),
+    };
+    print_result(env, &result, |result| {
+        eprintln!("To see the content of response, copy-paste the encoded string into cbor.me.");
+        eprint!("Response: ");
+        println!("{}", result.response);
+    })
 }
@@ -0,0 +1,540 @@
+//! The language of `dfx canister repl` and `dfx canister run-script`: calls of canister methods
+//! with Candid arguments, variables bound to their results, assertions on the results, and
+//! commands to select the canister, the identity and the kind of calls.
+use crate::commands::canister::call::call_method;
+use crate::lib::environment::{AgentEnvironment, Environment};
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_manager::IdentityManager;
+use crate::lib::identity::identity_utils::{call_sender, CallSender};
+use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::operations::canister::get_local_cid_and_candid_path;
+use crate::lib::root_key::fetch_root_key_if_needed;
+use crate::util::{blob_from_arguments, check_candid_file, expiry_duration};
+
+use anyhow::{anyhow, bail};
+use candid::parser::typing::TypeEnv;
+use candid::parser::value::{IDLField, IDLValue};
+use candid::types::{Field, Function, Label, Type};
+use candid::IDLArgs;
+use ic_types::Principal;
+use std::collections::BTreeMap;
+
+pub const HELP: &str = r#"<method> [<argument>]          Call a method. The argument is Candid, e.g. ("Alice", 42).
+<canister>.<method> [...]      Call a method of another canister.
+let <name> = <call>            Call a method and keep its result in $<name>.
+                               The result of the last call is kept in $_.
+assert <call or $name> == <value>
+                               Fail unless the result equals the Candid value.
+:methods                       List the methods of the canister with their signatures.
+:type <method>                 Show the signature of a method.
+:canister <name or id>         Call the methods of another canister.
+:query | :update | :auto       Send calls as queries, as updates, or as declared by the method.
+:identity [<name>]             Show or switch the identity that makes the calls.
+:vars                          List the variables.
+:help                          Show this help.
+:quit                          Leave the shell.
+# <comment>                    Ignored."#;
+
+pub const COMMANDS: &[&str] = &[
+    ":auto",
+    ":canister",
+    ":exit",
+    ":help",
+    ":identity",
+    ":methods",
+    ":query",
+    ":quit",
+    ":type",
+    ":update",
+    ":vars",
+];
+
+/// A canister that the session calls.
+pub struct Target {
+    pub name: String,
+    pub id: Principal,
+    /// The Candid service of the canister, if its interface is known.
+    pub service: Option<(TypeEnv, Type)>,
+}
+
+impl Target {
+    pub fn load(env: &dyn Environment, canister: &str) -> DfxResult<Self> {
+        let canister_id_store = CanisterIdStore::for_env(env)?;
+        let (name, id, candid_path) = match Principal::from_text(canister) {
+            Ok(id) => match canister_id_store.get_name(canister) {
+                Some(name) => {
+                    let (id, path) = get_local_cid_and_candid_path(env, name, Some(id))?;
+                    (name.to_string(), id, path)
+                }
+                None => (canister.to_string(), id, None),
+            },
+            Err(_) => {
+                let id = canister_id_store.get(canister)?;
+                let (id, path) = get_local_cid_and_candid_path(env, canister, Some(id))?;
+                (canister.to_string(), id, path)
+            }
+        };
+        let service = match candid_path.map(|path| check_candid_file(&path)) {
+            Some(Ok((env, Some(actor)))) => Some((env, actor)),
+            _ => None,
+        };
+        Ok(Target { name, id, service })
+    }
+
+    pub fn methods(&self) -> Vec<(String, Function)> {
+        let (env, actor) = match &self.service {
+            Some(service) => service,
+            None => return vec![],
+        };
+        env.as_service(actor)
+            .map(|methods| {
+                methods
+                    .iter()
+                    .filter_map(|(name, ty)| {
+                        env.as_func(ty)
+                            .ok()
+                            .map(|func| (name.clone(), func.clone()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn method_type(&self, method: &str) -> Option<(TypeEnv, Function)> {
+        let (env, actor) = self.service.as_ref()?;
+        let func = env.get_method(actor, method).ok()?.clone();
+        Some((env.clone(), func))
+    }
+}
+
+/// A result kept by the session, with its type if the Candid interface of the method is known.
+#[derive(Clone)]
+struct Variable {
+    value: IDLValue,
+    ty: Option<(TypeEnv, Type)>,
+}
+
+impl Variable {
+    /// The value of a method returning one value, otherwise the tuple of the values.
+    fn from_result(result: IDLArgs, method_type: &Option<(TypeEnv, Function)>) -> Self {
+        let mut values = result.args;
+        let ty = method_type.as_ref().map(|(env, func)| {
+            let ty = if func.rets.len() == 1 {
+                func.rets[0].clone()
+            } else {
+                Type::Record(
+                    func.rets
+                        .iter()
+                        .enumerate()
+                        .map(|(i, ty)| Field {
+                            id: Label::Unnamed(i as u32),
+                            ty: ty.clone(),
+                        })
+                        .collect(),
+                )
+            };
+            (env.clone(), ty)
+        });
+        let value = if values.len() == 1 {
+            values.remove(0)
+        } else {
+            IDLValue::Record(
+                values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, val)| IDLField {
+                        id: Label::Unnamed(i as u32),
+                        val,
+                    })
+                    .collect(),
+            )
+        };
+        Variable { value, ty }
+    }
+
+    /// Bring a value parsed from Candid text to the form of this variable's value, so that they
+    /// can be compared: numbers get the type of the variable, and fields their names.
+    fn normalize(&self, value: IDLValue) -> DfxResult<IDLValue> {
+        let args = IDLArgs::new(&[value]);
+        let mut args = match &self.ty {
+            Some((env, ty)) => {
+                let types = [ty.clone()];
+                IDLArgs::from_bytes_with_types(
+                    &args.to_bytes_with_types(env, &types)?,
+                    env,
+                    &types,
+                )?
+            }
+            None => IDLArgs::from_bytes(&args.to_bytes()?)?,
+        };
+        Ok(args.args.remove(0))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CallMode {
+    Auto,
+    Query,
+    Update,
+}
+
+pub struct Session<'a> {
+    env: &'a dyn Environment,
+    /// The environment of the identity selected with `:identity`, if any.
+    identity_env: Option<AgentEnvironment<'a>>,
+    call_sender: CallSender,
+    target: Option<Target>,
+    mode: CallMode,
+    variables: BTreeMap<String, Variable>,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(env: &'a dyn Environment, call_sender: CallSender) -> Self {
+        Session {
+            env,
+            identity_env: None,
+            call_sender,
+            target: None,
+            mode: CallMode::Auto,
+            variables: BTreeMap::new(),
+        }
+    }
+
+    /// Select the canister that calls without a canister name go to.
+    pub fn select_canister(&mut self, canister: &str) -> DfxResult {
+        self.target = Some(Target::load(self.agent_env(), canister)?);
+        Ok(())
+    }
+
+    pub fn method_names(&self) -> Vec<String> {
+        self.target
+            .as_ref()
+            .map(|target| target.methods().into_iter().map(|(name, _)| name).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn prompt(&self) -> String {
+        let identity = match &self.identity_env {
+            Some(identity_env) => identity_env
+                .get_selected_identity()
+                .map(|identity| format!("{}@", identity))
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+        let canister = self
+            .target
+            .as_ref()
+            .map_or("dfx", |target| target.name.as_str());
+        let mode = match self.mode {
+            CallMode::Auto => "",
+            CallMode::Query => " (query)",
+            CallMode::Update => " (update)",
+        };
+        format!("{}{}{}> ", identity, canister, mode)
+    }
+
+    /// Evaluate a line. Returns false when the session ends.
+    pub async fn eval(&mut self, line: &str) -> DfxResult<bool> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(true);
+        }
+        if let Some(command) = line.strip_prefix(':') {
+            let mut words = command.split_whitespace();
+            match (words.next().unwrap_or(""), words.next()) {
+                ("quit", _) | ("exit", _) => return Ok(false),
+                ("help", _) => println!("{}", HELP),
+                ("methods", _) => self.print_methods()?,
+                ("type", Some(method)) => self.print_method(method)?,
+                ("canister", Some(canister)) => self.select_canister(canister)?,
+                ("query", _) => self.mode = CallMode::Query,
+                ("update", _) => self.mode = CallMode::Update,
+                ("auto", _) => self.mode = CallMode::Auto,
+                ("identity", None) => {
+                    if let Some(identity) = self.agent_env().get_selected_identity() {
+                        println!("{}", identity);
+                    }
+                }
+                ("identity", Some(identity)) => self.switch_identity(identity).await?,
+                ("vars", _) => {
+                    for (name, variable) in &self.variables {
+                        println!("${} = {}", name, variable.value);
+                    }
+                }
+                _ => bail!("Unknown command '{}'. Type :help for the commands.", line),
+            }
+            return Ok(true);
+        }
+        if let Some(assertion) = line.strip_prefix("assert ") {
+            self.assert(assertion).await?;
+            return Ok(true);
+        }
+
+        let (variable, call) = parse_let(line)?;
+        let result = self.call(call).await?;
+        if let Some(variable) = variable {
+            self.variables.insert(variable, result);
+        }
+        Ok(true)
+    }
+
+    fn agent_env(&self) -> &dyn Environment {
+        match &self.identity_env {
+            Some(identity_env) => identity_env,
+            None => self.env,
+        }
+    }
+
+    fn target(&self) -> DfxResult<&Target> {
+        self.target.as_ref().ok_or_else(|| {
+            anyhow!("No canister is selected. Select one with :canister <name or id>.")
+        })
+    }
+
+    /// Call a method, print its result and keep it in `$_`.
+    async fn call(&mut self, call: &str) -> DfxResult<Variable> {
+        let (method, argument) = match call.find(|c: char| c.is_whitespace() || c == '(') {
+            Some(i) => (&call[..i], call[i..].trim()),
+            None => (call, ""),
+        };
+        let other_target;
+        let (target, method) = match method.rfind('.') {
+            Some(i) => {
+                other_target = Target::load(self.agent_env(), &method[..i])?;
+                (&other_target, &method[i + 1..])
+            }
+            None => (self.target()?, method),
+        };
+        let method_type = target.method_type(method);
+        if target.service.is_some() && method_type.is_none() {
+            bail!("{} has no method '{}'.", target.name, method);
+        }
+
+        let argument = substitute_variables(argument, &self.variables)?;
+        let argument = Some(argument.as_str()).filter(|argument| !argument.is_empty());
+        let arg_value = blob_from_arguments(argument, None, None, &method_type)?;
+        let is_query = match self.mode {
+            CallMode::Query => true,
+            CallMode::Update => false,
+            CallMode::Auto => method_type
+                .as_ref()
+                .map_or(false, |(_, func)| func.is_query()),
+        };
+        let blob = call_method(
+            self.agent_env(),
+            &self.call_sender,
+            target.id,
+            method,
+            arg_value,
+            is_query,
+            0,
+        )
+        .await?;
+
+        let result = match &method_type {
+            Some((env, func)) => IDLArgs::from_bytes_with_types(&blob, env, &func.rets)?,
+            None => IDLArgs::from_bytes(&blob)?,
+        };
+        println!("{}", result);
+        let result = Variable::from_result(result, &method_type);
+        self.variables.insert("_".to_string(), result.clone());
+        Ok(result)
+    }
+
+    /// Check `<call or $name> == <value>`.
+    async fn assert(&mut self, assertion: &str) -> DfxResult {
+        let equals = find_outside_text(assertion, "==")
+            .ok_or_else(|| anyhow!("Expected 'assert <call or $name> == <value>'."))?;
+        let actual = assertion[..equals].trim();
+        let actual = match actual.strip_prefix('$') {
+            Some(name) => self.variable(name)?.clone(),
+            None => self.call(actual).await?,
+        };
+        let expected = substitute_variables(assertion[equals + 2..].trim(), &self.variables)?;
+        let expected = candid::pretty_parse::<IDLValue>("Candid value", &expected)
+            .map_err(|e| anyhow!("Invalid Candid value: {}", e))?;
+        let expected = actual.normalize(expected)?;
+        if expected != actual.value {
+            bail!(
+                "Assertion failed: expected {} but found {}.",
+                expected,
+                actual.value
+            );
+        }
+        Ok(())
+    }
+
+    fn variable(&self, name: &str) -> DfxResult<&Variable> {
+        self.variables
+            .get(name)
+            .ok_or_else(|| anyhow!("Unknown variable ${}.", name))
+    }
+
+    fn print_methods(&self) -> DfxResult {
+        let target = self.target()?;
+        if target.service.is_none() {
+            bail!("The Candid interface of {} is unknown.", target.name);
+        }
+        for (name, func) in target.methods() {
+            println!("{}", format_method(&name, &func));
+        }
+        Ok(())
+    }
+
+    fn print_method(&self, method: &str) -> DfxResult {
+        let target = self.target()?;
+        let (_, func) = target
+            .method_type(method)
+            .ok_or_else(|| anyhow!("{} has no method '{}'.", target.name, method))?;
+        println!("{}", format_method(method, &func));
+        Ok(())
+    }
+
+    async fn switch_identity(&mut self, identity: &str) -> DfxResult {
+        let network_descriptor = self
+            .env
+            .get_network_descriptor()
+            .ok_or_else(|| anyhow!("No network descriptor."))?
+            .clone();
+        let mut identity_manager = IdentityManager::for_network(self.env, &network_descriptor)?;
+        identity_manager.select_identity(identity)?;
+        let identity_env = AgentEnvironment::with_identity_manager(
+            self.env,
+            network_descriptor,
+            expiry_duration(),
+            identity_manager,
+        )?;
+        fetch_root_key_if_needed(&identity_env).await?;
+        // The wallet of the previous identity is not the wallet of this one.
+        if let CallSender::SelectedIdWallet(_) = self.call_sender {
+            self.call_sender = call_sender(&identity_env, &None, false, true).await?;
+        }
+        self.identity_env = Some(identity_env);
+        Ok(())
+    }
+}
+
+fn format_method(name: &str, func: &Function) -> String {
+    let format_types = |types: &[Type]| {
+        types
+            .iter()
+            .map(|ty| ty.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!(
+        "{} : ({}) -> ({}){}",
+        name,
+        format_types(&func.args),
+        format_types(&func.rets),
+        if func.is_query() { " query" } else { "" }
+    )
+}
+
+/// Split `let <name> = <call>` into the name of the variable and the call.
+fn parse_let(line: &str) -> DfxResult<(Option<String>, &str)> {
+    let rest = match line.strip_prefix("let ") {
+        Some(rest) => rest,
+        None => return Ok((None, line)),
+    };
+    let equals = rest
+        .find('=')
+        .ok_or_else(|| anyhow!("Expected 'let <name> = <call>'."))?;
+    let name = rest[..equals].trim();
+    let is_valid = name
+        .chars()
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_valid {
+        bail!("Invalid variable name '{}'.", name);
+    }
+    Ok((Some(name.to_string()), rest[equals + 1..].trim()))
+}
+
+/// The position of the first occurrence of a pattern outside of text literals.
+fn find_outside_text(line: &str, pattern: &str) -> Option<usize> {
+    let mut in_text = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if in_text {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_text = false;
+            }
+        } else if c == '"' {
+            in_text = true;
+        } else if line[i..].starts_with(pattern) {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Replace each `$<name>` outside of text literals by the Candid value of the variable.
+fn substitute_variables(
+    argument: &str,
+    variables: &BTreeMap<String, Variable>,
+) -> DfxResult<String> {
+    let mut result = String::new();
+    let mut rest = argument;
+    while let Some(i) = find_outside_text(rest, "$") {
+        result.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or_else(|| rest.len());
+        let name = &rest[..end];
+        let variable = variables
+            .get(name)
+            .ok_or_else(|| anyhow!("Unknown variable ${}.", name))?;
+        result.push_str(&variable.value.to_string());
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_let() {
+        assert_eq!(
+            parse_let("let greeting = greet (\"Alice\")").unwrap(),
+            (Some("greeting".to_string()), "greet (\"Alice\")")
+        );
+        assert_eq!(parse_let("greet \"Bob\"").unwrap(), (None, "greet \"Bob\""));
+        assert!(parse_let("let 1x = greet").is_err());
+    }
+
+    #[test]
+    fn finds_patterns_outside_of_text() {
+        assert_eq!(
+            find_outside_text(r#"greet "a == b" == "c""#, "=="),
+            Some(15)
+        );
+        assert_eq!(find_outside_text(r#"greet "\" == \"""#, "=="), None);
+    }
+
+    #[test]
+    fn substitutes_variables_outside_of_text() {
+        let mut variables = BTreeMap::new();
+        variables.insert(
+            "name".to_string(),
+            Variable {
+                value: IDLValue::Text("Alice".to_string()),
+                ty: None,
+            },
+        );
+        assert_eq!(
+            substitute_variables(r#"($name, "$name \" $name")"#, &variables).unwrap(),
+            r#"("Alice", "$name \" $name")"#
+        );
+        assert!(substitute_variables("($other)", &variables).is_err());
+    }
+}
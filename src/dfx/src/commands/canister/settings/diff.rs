@@ -0,0 +1,95 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_utils::CallSender;
+use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::operations::canister::{settings_drift, SettingDrift};
+use crate::lib::output::print_result;
+use crate::lib::root_key::fetch_root_key_if_needed;
+use crate::util::expiry_duration;
+
+use clap::Clap;
+use serde::Serialize;
+
+/// Reports the settings in dfx.json that differ from the live settings of the canisters,
+/// without changing anything. `dfx deploy` applies them.
+#[derive(Clap)]
+pub struct SettingsDiffOpts {
+    /// Specifies the name of the canister to compare. Compares all canisters if omitted.
+    canister: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CanisterSettingsDiff {
+    canister: String,
+    created: bool,
+    drift: Vec<SettingDrift>,
+}
+
+pub async fn exec(
+    env: &dyn Environment,
+    opts: SettingsDiffOpts,
+    call_sender: &CallSender,
+) -> DfxResult {
+    let config = env.get_config_or_anyhow()?;
+    let config_interface = config.get_config();
+    let timeout = expiry_duration();
+
+    fetch_root_key_if_needed(env).await?;
+
+    let canister_names = match opts.canister {
+        Some(canister) => vec![canister],
+        None => config_interface
+            .canisters
+            .as_ref()
+            .map(|canisters| canisters.keys().cloned().collect())
+            .unwrap_or_default(),
+    };
+
+    let canister_id_store = CanisterIdStore::for_env(env)?;
+    let mut diffs = vec![];
+    for canister_name in canister_names {
+        let canister_id = match canister_id_store.find(&canister_name) {
+            Some(canister_id) => canister_id,
+            None => {
+                diffs.push(CanisterSettingsDiff {
+                    canister: canister_name,
+                    created: false,
+                    drift: vec![],
+                });
+                continue;
+            }
+        };
+        let (drift, _) = settings_drift(
+            env,
+            config_interface,
+            &canister_name,
+            canister_id,
+            timeout,
+            call_sender,
+        )
+        .await?;
+        diffs.push(CanisterSettingsDiff {
+            canister: canister_name,
+            created: true,
+            drift,
+        });
+    }
+
+    print_result(env, &diffs, |diffs| {
+        for diff in diffs {
+            if !diff.created {
+                println!("{}: not created", diff.canister);
+            } else if diff.drift.is_empty() {
+                println!("{}: in sync", diff.canister);
+            } else {
+                println!("{}:", diff.canister);
+                for setting in &diff.drift {
+                    println!(
+                        "  {}: dfx.json {}, live {}",
+                        setting.setting, setting.configured, setting.live
+                    );
+                }
+            }
+        }
+    })
+}
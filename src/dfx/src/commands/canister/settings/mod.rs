@@ -0,0 +1,30 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_utils::CallSender;
+
+use clap::Clap;
+
+mod diff;
+
+/// Compares the canister settings in dfx.json with the live settings of the canisters.
+#[derive(Clap)]
+#[clap(name("settings"))]
+pub struct SettingsOpts {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap)]
+enum SubCommand {
+    Diff(diff::SettingsDiffOpts),
+}
+
+pub async fn exec(
+    env: &dyn Environment,
+    opts: SettingsOpts,
+    call_sender: &CallSender,
+) -> DfxResult {
+    match opts.subcmd {
+        SubCommand::Diff(v) => diff::exec(env, v, call_sender).await,
+    }
+}
@@ -50,7 +50,7 @@ pub struct CanisterSignOpts {
     random: Option<String>,
 
     /// Specifies the data type for the argument when making the call using an argument.
-    #[clap(long, requires("argument"), possible_values(&["idl", "raw"]))]
+    #[clap(long, requires("argument"), possible_values(&["idl", "raw", "json"]))]
     r#type: Option<String>,
 
     /// Specifies how long will the message be valid in seconds, default to be 300s (5 minutes)
@@ -2,12 +2,17 @@ use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::identity::identity_utils::CallSender;
 use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::models::status_history::{days_until_frozen, StatusHistory};
 use crate::lib::operations::canister;
+use crate::lib::output::command_output_format;
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::util::expiry_duration;
 
+use candid::Nat;
 use clap::Clap;
 use ic_types::Principal;
+use num_traits::cast::ToPrimitive;
+use serde::Serialize;
 use slog::info;
 use std::time::Duration;
 
@@ -21,6 +26,32 @@ pub struct CanisterStatusOpts {
     /// Returns status information for all of the canisters configured in the dfx.json file.
     #[clap(long, required_unless_present("canister"))]
     all: bool,
+
+    /// Specifies the format of the output. `json` and `table` include the cycles burned per day
+    /// and the days until the freezing threshold is reached, estimated from previous calls.
+    /// Defaults to the global `--output` format.
+    #[clap(long, possible_values(&["text", "json", "table"]))]
+    output: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CanisterStatusReport {
+    canister: String,
+    id: String,
+    status: String,
+    controllers: Vec<String>,
+    memory_size: u64,
+    cycles: u64,
+    module_hash: Option<String>,
+    memory_allocation: u64,
+    compute_allocation: u64,
+    freezing_threshold: u64,
+    cycles_burned_per_day: Option<u64>,
+    days_until_frozen: Option<f64>,
+}
+
+fn nat_to_u64(nat: &Nat) -> u64 {
+    nat.0.to_u64().unwrap_or(u64::MAX)
 }
 
 async fn canister_status(
@@ -28,8 +59,7 @@ async fn canister_status(
     canister: &str,
     timeout: Duration,
     call_sender: &CallSender,
-) -> DfxResult {
-    let log = env.get_logger();
+) -> DfxResult<CanisterStatusReport> {
     let canister_id_store = CanisterIdStore::for_env(env)?;
     let canister_id =
         Principal::from_text(canister).or_else(|_| canister_id_store.get(canister))?;
@@ -44,18 +74,110 @@ async fn canister_status(
         .collect();
     controllers.sort();
 
-    info!(log, "Canister status call result for {}.\nStatus: {}\nControllers: {}\nMemory allocation: {}\nCompute allocation: {}\nFreezing threshold: {}\nMemory Size: {:?}\nBalance: {} Cycles\nModule hash: {}",
-        canister,
-        status.status,
-        controllers.join(" "),
-        status.settings.memory_allocation,
-        status.settings.compute_allocation,
-        status.settings.freezing_threshold,
-        status.memory_size,
-        status.cycles,
-        status.module_hash.map_or_else(|| "None".to_string(), |v| format!("0x{}", hex::encode(v)))
+    let cycles = nat_to_u64(&status.cycles);
+    let freezing_threshold = nat_to_u64(&status.settings.freezing_threshold);
+    let mut history = StatusHistory::load(env, &canister_id)?;
+    history.record(cycles)?;
+    let burn_per_day = history.burn_per_day();
+
+    Ok(CanisterStatusReport {
+        canister: canister.to_string(),
+        id: canister_id.to_text(),
+        status: status.status.to_string(),
+        controllers,
+        memory_size: nat_to_u64(&status.memory_size),
+        cycles,
+        module_hash: status.module_hash.map(|v| format!("0x{}", hex::encode(v))),
+        memory_allocation: nat_to_u64(&status.settings.memory_allocation),
+        compute_allocation: nat_to_u64(&status.settings.compute_allocation),
+        freezing_threshold,
+        cycles_burned_per_day: burn_per_day.map(|burn| burn.round() as u64),
+        days_until_frozen: burn_per_day
+            .and_then(|burn| days_until_frozen(cycles, burn, freezing_threshold)),
+    })
+}
+
+fn print_text(env: &dyn Environment, report: &CanisterStatusReport) {
+    info!(env.get_logger(), "Canister status call result for {}.\nStatus: {}\nControllers: {}\nMemory allocation: {}\nCompute allocation: {}\nFreezing threshold: {}\nMemory Size: Nat({})\nBalance: {} Cycles\nModule hash: {}",
+        report.canister,
+        report.status,
+        report.controllers.join(" "),
+        report.memory_allocation,
+        report.compute_allocation,
+        report.freezing_threshold,
+        report.memory_size,
+        report.cycles,
+        report.module_hash.as_deref().unwrap_or("None")
     );
-    Ok(())
+}
+
+fn print_table(reports: &[CanisterStatusReport]) {
+    let header = [
+        "CANISTER",
+        "ID",
+        "STATUS",
+        "CYCLES",
+        "BURN/DAY",
+        "DAYS LEFT",
+        "MEMORY",
+        "COMPUTE",
+        "MEMORY ALLOC",
+        "FREEZING",
+        "MODULE HASH",
+        "CONTROLLERS",
+    ];
+    let rows: Vec<Vec<String>> = reports
+        .iter()
+        .map(|report| {
+            vec![
+                report.canister.clone(),
+                report.id.clone(),
+                report.status.clone(),
+                report.cycles.to_string(),
+                report
+                    .cycles_burned_per_day
+                    .map_or_else(|| "-".to_string(), |burn| burn.to_string()),
+                report
+                    .days_until_frozen
+                    .map_or_else(|| "-".to_string(), |days| format!("{:.1}", days)),
+                report.memory_size.to_string(),
+                report.compute_allocation.to_string(),
+                report.memory_allocation.to_string(),
+                report.freezing_threshold.to_string(),
+                report
+                    .module_hash
+                    .clone()
+                    .unwrap_or_else(|| "None".to_string()),
+                report.controllers.join(" "),
+            ]
+        })
+        .collect();
+
+    let widths: Vec<usize> = header
+        .iter()
+        .enumerate()
+        .map(|(i, title)| {
+            rows.iter()
+                .map(|row| row[i].len())
+                .chain(std::iter::once(title.len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    println!("{}", format_row(header.to_vec()));
+    for row in &rows {
+        println!("{}", format_row(row.iter().map(String::as_str).collect()));
+    }
 }
 
 pub async fn exec(
@@ -68,16 +190,33 @@ pub async fn exec(
     fetch_root_key_if_needed(env).await?;
     let timeout = expiry_duration();
 
-    if let Some(canister) = opts.canister.as_deref() {
-        canister_status(env, &canister, timeout, call_sender).await
+    let canisters: Vec<String> = if let Some(canister) = opts.canister {
+        vec![canister]
     } else if opts.all {
-        if let Some(canisters) = &config.get_config().canisters {
-            for canister in canisters.keys() {
-                canister_status(env, &canister, timeout, call_sender).await?;
-            }
-        }
-        Ok(())
+        config
+            .get_config()
+            .canisters
+            .as_ref()
+            .map(|canisters| canisters.keys().cloned().collect())
+            .unwrap_or_default()
     } else {
         unreachable!()
+    };
+
+    let output = command_output_format(env, opts.output.as_deref())?;
+    let mut reports = vec![];
+    for canister in &canisters {
+        let report = canister_status(env, canister, timeout, call_sender).await?;
+        if output == "text" {
+            print_text(env, &report);
+        }
+        reports.push(report);
     }
+
+    match output {
+        "json" => println!("{}", serde_json::to_string_pretty(&reports)?),
+        "table" => print_table(&reports),
+        _ => {}
+    }
+    Ok(())
 }
@@ -0,0 +1,118 @@
+use crate::commands::ledger::top_up_with_icp;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_utils::CallSender;
+use crate::lib::nns_types::CyclesResponse;
+use crate::lib::operations::canister::{plan_top_ups, top_up_from_wallet, TopUp, TopUpResult};
+use crate::lib::output::{print_json, OutputFormat};
+use crate::lib::root_key::fetch_root_key_if_needed;
+use crate::util::expiry_duration;
+
+use anyhow::bail;
+use clap::Clap;
+use slog::info;
+
+/// Top up the canisters whose cycles balance is below the minimum of the `cycles` policy
+/// in dfx.json, e.g. `"cycles": { "minimum": 1000000000000, "target": 5000000000000 }`,
+/// to the target of the policy.
+#[derive(Clap)]
+pub struct CanisterTopUpOpts {
+    /// Specifies the name of the canister to top up.
+    /// You must specify either a canister name or the --all option.
+    canister_name: Option<String>,
+
+    /// Tops up all of the canisters configured in the dfx.json file.
+    #[clap(long, required_unless_present("canister-name"))]
+    all: bool,
+
+    /// Shows the top-ups without making them.
+    #[clap(long)]
+    dry_run: bool,
+
+    /// Buys the cycles with ICP from the selected identity's ledger account,
+    /// instead of depositing them from the wallet.
+    #[clap(long)]
+    from_icp: bool,
+}
+
+pub async fn exec(
+    env: &dyn Environment,
+    opts: CanisterTopUpOpts,
+    call_sender: &CallSender,
+) -> DfxResult {
+    if !opts.from_icp && call_sender == &CallSender::SelectedId {
+        bail!("Depositing cycles needs to be proxied via the wallet canister. Invoke this command without the `--no-wallet` flag, or with `--from-icp`.");
+    }
+
+    let log = env.get_logger();
+    let config = env.get_config_or_anyhow()?;
+    let config_interface = config.get_config();
+    let canister_names = match opts.canister_name {
+        Some(canister_name) => vec![canister_name],
+        None => config_interface
+            .canisters
+            .as_ref()
+            .map(|canisters| canisters.keys().cloned().collect())
+            .unwrap_or_default(),
+    };
+
+    fetch_root_key_if_needed(env).await?;
+    let timeout = expiry_duration();
+
+    let top_ups =
+        plan_top_ups(env, config_interface, &canister_names, timeout, call_sender).await?;
+    if top_ups.is_empty() {
+        info!(log, "No canister needs a top-up.");
+    }
+
+    if opts.from_icp {
+        top_up_from_icp(env, &top_ups, opts.dry_run).await?;
+    } else {
+        top_up_from_wallet(env, &top_ups, timeout, call_sender, opts.dry_run).await?;
+    }
+
+    match env.get_output_format() {
+        OutputFormat::Text => Ok(()),
+        OutputFormat::Json => print_json(
+            &top_ups
+                .iter()
+                .map(|top_up| TopUpResult::new(top_up, opts.dry_run))
+                .collect::<Vec<_>>(),
+        ),
+    }
+}
+
+async fn top_up_from_icp(env: &dyn Environment, top_ups: &[TopUp], dry_run: bool) -> DfxResult {
+    let log = env.get_logger();
+    for top_up in top_ups {
+        if dry_run {
+            info!(
+                log,
+                "Would buy {} cycles with ICP for {}, which has {} cycles, to reach its target of {}.",
+                top_up.shortfall(),
+                top_up.canister_name,
+                top_up.balance,
+                top_up.policy.target
+            );
+            continue;
+        }
+        info!(
+            log,
+            "Buying {} cycles with ICP for {}, which has {} cycles, to reach its target of {}.",
+            top_up.shortfall(),
+            top_up.canister_name,
+            top_up.balance,
+            top_up.policy.target
+        );
+        match top_up_with_icp(env, top_up.canister_id, top_up.shortfall()).await? {
+            CyclesResponse::ToppedUp(()) => {}
+            CyclesResponse::Refunded(msg, _) => bail!(
+                "The top-up of {} was refunded: {}",
+                top_up.canister_name,
+                msg
+            ),
+            CyclesResponse::CanisterCreated(_) => unreachable!(),
+        }
+    }
+    Ok(())
+}
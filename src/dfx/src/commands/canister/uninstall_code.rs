@@ -1,3 +1,4 @@
+use crate::lib::confirmation::{confirm_destructive_operation, DestructiveOperation};
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::identity::identity_utils::CallSender;
@@ -21,6 +22,10 @@ pub struct UninstallCodeOpts {
     /// Uninstalls all of the canisters configured in the dfx.json file.
     #[clap(long, required_unless_present("canister"))]
     all: bool,
+
+    /// Skips the confirmation prompt on protected networks.
+    #[clap(long)]
+    yes: bool,
 }
 
 async fn uninstall_code(
@@ -28,11 +33,22 @@ async fn uninstall_code(
     canister: &str,
     timeout: Duration,
     call_sender: &CallSender,
+    yes: bool,
 ) -> DfxResult {
     let log = env.get_logger();
     let canister_id_store = CanisterIdStore::for_env(env)?;
     let canister_id =
         Principal::from_text(canister).or_else(|_| canister_id_store.get(canister))?;
+    confirm_destructive_operation(
+        env,
+        &DestructiveOperation {
+            action: "Uninstall code and delete all state of canister",
+            canister,
+            canister_id: Some(canister_id),
+            cycles: None,
+        },
+        yes,
+    )?;
 
     info!(
         log,
@@ -57,11 +73,11 @@ pub async fn exec(
     let timeout = expiry_duration();
 
     if let Some(canister) = opts.canister.as_deref() {
-        uninstall_code(env, &canister, timeout, call_sender).await
+        uninstall_code(env, &canister, timeout, call_sender, opts.yes).await
     } else if opts.all {
         if let Some(canisters) = &config.get_config().canisters {
             for canister in canisters.keys() {
-                uninstall_code(env, &canister, timeout, call_sender).await?;
+                uninstall_code(env, &canister, timeout, call_sender, opts.yes).await?;
             }
         }
         Ok(())
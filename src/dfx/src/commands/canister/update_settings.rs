@@ -1,12 +1,15 @@
+use crate::lib::confirmation::{
+    caller_principal, confirm_destructive_operation, DestructiveOperation,
+};
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::ic_attributes::{
     get_compute_allocation, get_freezing_threshold, get_memory_allocation, CanisterSettings,
 };
-use crate::lib::identity::identity_manager::IdentityManager;
-use crate::lib::identity::identity_utils::CallSender;
+use crate::lib::identity::identity_utils::{caller_principals, controller_principal, CallSender};
 use crate::lib::models::canister_id_store::CanisterIdStore;
-use crate::lib::operations::canister::update_settings;
+use crate::lib::operations::canister::{get_canister_status, update_settings};
+use crate::lib::output::{print_json, print_progress, OutputFormat};
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::util::clap::validators::{
     compute_allocation_validator, freezing_threshold_validator, memory_allocation_validator,
@@ -15,8 +18,10 @@ use crate::util::expiry_duration;
 
 use anyhow::{anyhow, bail};
 use clap::{ArgSettings, Clap};
-use ic_agent::identity::Identity;
 use ic_types::principal::Principal as CanisterId;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::time::Duration;
 
 /// Update one or more of a canister's settings (i.e its controller, compute allocation, or memory allocation.)
 #[derive(Clap)]
@@ -32,6 +37,24 @@ pub struct UpdateSettingsOpts {
     #[clap(long, multiple(true), number_of_values(1))]
     controller: Option<Vec<String>>,
 
+    /// Adds a controller, given by identity name or principal, to the current controllers.
+    #[clap(
+        long,
+        multiple(true),
+        number_of_values(1),
+        conflicts_with("controller")
+    )]
+    add_controller: Option<Vec<String>>,
+
+    /// Removes a controller, given by identity name or principal, from the current controllers.
+    #[clap(
+        long,
+        multiple(true),
+        number_of_values(1),
+        conflicts_with("controller")
+    )]
+    remove_controller: Option<Vec<String>>,
+
     /// Specifies the canister's compute allocation. This should be a percent in the range [0..100]
     #[clap(long, short('c'), validator(compute_allocation_validator))]
     compute_allocation: Option<String>,
@@ -46,6 +69,47 @@ pub struct UpdateSettingsOpts {
 
     #[clap(long, validator(freezing_threshold_validator), setting = ArgSettings::Hidden)]
     freezing_threshold: Option<String>,
+
+    /// Allows changing the controllers so that neither your identity nor your wallet controls
+    /// the canister anymore.
+    #[clap(long)]
+    force: bool,
+
+    /// Skips the confirmation prompt on protected networks.
+    #[clap(long)]
+    yes: bool,
+}
+
+/// The requested change to the controllers of a canister.
+enum ControllerChange {
+    Set(Vec<CanisterId>),
+    Update {
+        add: Vec<CanisterId>,
+        remove: Vec<CanisterId>,
+    },
+}
+
+/// The settings of a canister after the update, for the json output.
+#[derive(Serialize)]
+struct UpdatedSettings {
+    canister: String,
+    id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    controllers: Option<Vec<String>>,
+}
+
+impl UpdatedSettings {
+    fn new(canister: &str, canister_id: CanisterId, settings: &CanisterSettings) -> Self {
+        UpdatedSettings {
+            canister: canister.to_string(),
+            id: canister_id.to_text(),
+            controllers: settings
+                .controllers
+                .as_ref()
+                .map(|controllers| controllers.iter().map(CanisterId::to_text).collect()),
+        }
+    }
 }
 
 pub async fn exec(
@@ -58,31 +122,27 @@ pub async fn exec(
     let config_interface = config.get_config();
     fetch_root_key_if_needed(env).await?;
 
-    let controllers: Option<DfxResult<Vec<_>>> = opts.controller.clone().map(|controllers| {
-        let y: DfxResult<Vec<_>> = controllers
+    let resolve = |controllers: &Option<Vec<String>>| -> DfxResult<Vec<CanisterId>> {
+        controllers
             .iter()
-            .map(
-                |controller| match CanisterId::from_text(controller.clone()) {
-                    Ok(principal) => Ok(principal),
-                    Err(_) => {
-                        let current_id = env.get_selected_identity().unwrap();
-                        if current_id == controller {
-                            Ok(env.get_selected_identity_principal().unwrap())
-                        } else {
-                            let identity_name = controller;
-                            IdentityManager::new(env)?
-                                .instantiate_identity_from_name(identity_name)
-                                .and_then(|identity| identity.sender().map_err(|err| anyhow!(err)))
-                        }
-                    }
-                },
-            )
-            .collect::<DfxResult<Vec<_>>>();
-        y
-    });
-    let controllers = controllers.transpose()?;
+            .flatten()
+            .map(|controller| controller_principal(env, controller))
+            .collect()
+    };
+    let change = if opts.controller.is_some() {
+        Some(ControllerChange::Set(resolve(&opts.controller)?))
+    } else if opts.add_controller.is_some() || opts.remove_controller.is_some() {
+        Some(ControllerChange::Update {
+            add: resolve(&opts.add_controller)?,
+            remove: resolve(&opts.remove_controller)?,
+        })
+    } else {
+        None
+    };
 
     let canister_id_store = CanisterIdStore::for_env(env)?;
+    let text_output = env.get_output_format() == OutputFormat::Text;
+    let mut updated = vec![];
 
     if let Some(canister_name_or_id) = opts.canister.as_deref() {
         let canister_id = CanisterId::from_text(canister_name_or_id)
@@ -91,6 +151,16 @@ pub async fn exec(
         let canister_name = canister_id_store
             .get_name(&textual_cid)
             .ok_or_else(|| anyhow!("Cannot find canister name for id '{}'.", textual_cid))?;
+        let controllers = new_controllers(
+            env,
+            &opts,
+            &change,
+            canister_name_or_id,
+            canister_id,
+            timeout,
+            call_sender,
+        )
+        .await?;
 
         let compute_allocation = get_compute_allocation(
             opts.compute_allocation.clone(),
@@ -113,13 +183,27 @@ pub async fn exec(
             memory_allocation,
             freezing_threshold,
         };
+        let result = UpdatedSettings::new(canister_name_or_id, canister_id, &settings);
         update_settings(env, canister_id, settings, timeout, call_sender).await?;
-        display_controller_update(&opts, canister_name_or_id);
+        if text_output {
+            display_controller_update(&opts, canister_name_or_id);
+        }
+        updated.push(result);
     } else if opts.all {
         // Update all canister settings.
         if let Some(canisters) = &config.get_config().canisters {
             for canister_name in canisters.keys() {
                 let canister_id = canister_id_store.get(canister_name)?;
+                let controllers = new_controllers(
+                    env,
+                    &opts,
+                    &change,
+                    canister_name,
+                    canister_id,
+                    timeout,
+                    call_sender,
+                )
+                .await?;
                 let compute_allocation = get_compute_allocation(
                     opts.compute_allocation.clone(),
                     config_interface,
@@ -136,20 +220,125 @@ pub async fn exec(
                     canister_name,
                 )?;
                 let settings = CanisterSettings {
-                    controllers: controllers.clone(),
+                    controllers,
                     compute_allocation,
                     memory_allocation,
                     freezing_threshold,
                 };
+                let result = UpdatedSettings::new(canister_name, canister_id, &settings);
                 update_settings(env, canister_id, settings, timeout, call_sender).await?;
-                display_controller_update(&opts, canister_name);
+                if text_output {
+                    display_controller_update(&opts, canister_name);
+                }
+                updated.push(result);
             }
         }
     } else {
         bail!("Cannot find canister name.")
     }
 
-    Ok(())
+    if text_output {
+        Ok(())
+    } else {
+        print_json(&updated)
+    }
+}
+
+/// Compute the new controllers of a canister, refusing to lock the caller out unless forced.
+async fn new_controllers(
+    env: &dyn Environment,
+    opts: &UpdateSettingsOpts,
+    change: &Option<ControllerChange>,
+    canister_name_or_id: &str,
+    canister_id: CanisterId,
+    timeout: Duration,
+    call_sender: &CallSender,
+) -> DfxResult<Option<Vec<CanisterId>>> {
+    let change = match change {
+        Some(change) => change,
+        None => return Ok(None),
+    };
+
+    let current = get_canister_status(env, canister_id, timeout, call_sender)
+        .await?
+        .settings
+        .controllers;
+    let controllers = match change {
+        ControllerChange::Set(controllers) => controllers.clone(),
+        ControllerChange::Update { add, remove } => {
+            let mut controllers = current.clone();
+            for controller in add {
+                if !controllers.contains(controller) {
+                    controllers.push(*controller);
+                }
+            }
+            controllers.retain(|controller| !remove.contains(controller));
+            controllers
+        }
+    };
+
+    let callers = caller_principals(env, call_sender);
+    if !opts.force && !callers.iter().any(|caller| controllers.contains(caller)) {
+        bail!(
+            "Refusing to update the controllers of {:?}: neither {} would control it anymore. Pass --force to do it anyway.",
+            canister_name_or_id,
+            callers
+                .iter()
+                .map(CanisterId::to_text)
+                .collect::<Vec<_>>()
+                .join(" nor ")
+        );
+    }
+
+    display_controller_diff(env, canister_name_or_id, &current, &controllers);
+
+    // Replacing the controllers without the caller among them locks the caller out.
+    if let Some(caller) = caller_principal(env, call_sender) {
+        if !controllers.contains(&caller) {
+            confirm_controller_removal(env, canister_name_or_id, canister_id, opts.yes)?;
+        }
+    }
+
+    Ok(Some(controllers))
+}
+
+fn confirm_controller_removal(
+    env: &dyn Environment,
+    canister: &str,
+    canister_id: CanisterId,
+    yes: bool,
+) -> DfxResult {
+    confirm_destructive_operation(
+        env,
+        &DestructiveOperation {
+            action: "Remove yourself from the controllers of canister",
+            canister,
+            canister_id: Some(canister_id),
+            cycles: None,
+        },
+        yes,
+    )
+}
+
+/// Show the change of the controllers before it is confirmed.
+fn display_controller_diff(
+    env: &dyn Environment,
+    canister_name_or_id: &str,
+    before: &[CanisterId],
+    after: &[CanisterId],
+) {
+    let before: BTreeSet<_> = before.iter().map(CanisterId::to_text).collect();
+    let after: BTreeSet<_> = after.iter().map(CanisterId::to_text).collect();
+
+    print_progress(env, &format!("Controllers of {:?}:", canister_name_or_id));
+    for controller in before.union(&after) {
+        let marker = match (before.contains(controller), after.contains(controller)) {
+            (true, false) => "-",
+            (false, true) => "+",
+            _ => " ",
+        };
+        print_progress(env, &format!("  {} {}", marker, controller));
+    }
 }
 
 fn display_controller_update(opts: &UpdateSettingsOpts, canister_name_or_id: &str) {
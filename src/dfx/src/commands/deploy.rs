@@ -1,13 +1,17 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::identity::identity_utils::call_sender;
-use crate::lib::operations::canister::deploy_canisters;
+use crate::lib::operations::canister::{
+    deploy_canisters, plan_top_ups, top_up_from_wallet, TopUpResult,
+};
+use crate::lib::output::{named_canister_ids, print_json, NamedCanisterId, OutputFormat};
 use crate::lib::provider::create_agent_environment;
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::util::clap::validators::cycle_amount_validator;
 use crate::util::expiry_duration;
 
 use clap::Clap;
+use serde::Serialize;
 use tokio::runtime::Runtime;
 
 /// Deploys all or a specific canister from the code in your project. By default, all canisters are deployed.
@@ -22,7 +26,7 @@ pub struct DeployOpts {
     argument: Option<String>,
 
     /// Specifies the data type for the argument when making the call using an argument.
-    #[clap(long, requires("argument"), possible_values(&["idl", "raw"]))]
+    #[clap(long, requires("argument"), possible_values(&["idl", "raw", "json"]))]
     argument_type: Option<String>,
 
     /// Override the compute network to connect to. By default, the local network is used.
@@ -47,6 +51,17 @@ pub struct DeployOpts {
     /// Bypasses the Wallet canister.
     #[clap(long, conflicts_with("wallet"))]
     no_wallet: bool,
+
+    /// After installing, deposits cycles from the wallet onto the deployed canisters whose
+    /// balance is below the minimum of their `cycles` policy in dfx.json, up to its target.
+    #[clap(long, conflicts_with("no-wallet"))]
+    ensure_cycles: bool,
+}
+
+#[derive(Serialize)]
+struct Deployment {
+    canisters: Vec<NamedCanisterId>,
+    top_ups: Vec<TopUpResult>,
 }
 
 pub fn exec(env: &dyn Environment, opts: DeployOpts) -> DfxResult {
@@ -77,5 +92,40 @@ pub fn exec(env: &dyn Environment, opts: DeployOpts) -> DfxResult {
         timeout,
         with_cycles,
         &call_sender,
-    ))
+    ))?;
+
+    let config = env.get_config_or_anyhow()?;
+    let mut canister_names = config
+        .get_config()
+        .get_canister_names_with_dependencies(canister_name)?;
+    canister_names.sort();
+    let mut deployment = Deployment {
+        canisters: named_canister_ids(&env, &canister_names)?,
+        top_ups: vec![],
+    };
+
+    if opts.ensure_cycles {
+        deployment.top_ups = runtime.block_on(async {
+            let top_ups = plan_top_ups(
+                &env,
+                config.get_config(),
+                &canister_names,
+                timeout,
+                &call_sender,
+            )
+            .await?;
+            top_up_from_wallet(&env, &top_ups, timeout, &call_sender, false).await?;
+            DfxResult::Ok(
+                top_ups
+                    .iter()
+                    .map(|top_up| TopUpResult::new(top_up, false))
+                    .collect(),
+            )
+        })?;
+    }
+
+    match env.get_output_format() {
+        OutputFormat::Text => Ok(()),
+        OutputFormat::Json => print_json(&deployment),
+    }
 }
@@ -0,0 +1,67 @@
+use crate::commands::identity::network_identity_manager;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::{delegation, ANONYMOUS_IDENTITY_NAME};
+use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::provider::get_network_descriptor;
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Utc};
+use clap::Clap;
+use humanize_rs::duration;
+use ic_types::Principal;
+use slog::info;
+
+/// Creates a short-lived session key for the selected identity, signed off by the identity's key.
+/// Until the delegation expires, requests are signed with the session key instead, which avoids
+/// an HSM PIN prompt and signing round trip for every request.
+#[derive(Clap)]
+pub struct DelegateOpts {
+    /// How long the delegation is valid, e.g. `30m`, `1h` or `1h 30m`.
+    #[clap(long, default_value("1h"))]
+    expire_after: String,
+
+    /// Restricts the delegation to the given canisters, specified by name or id.
+    /// By default the session key can call any canister.
+    #[clap(long, multiple(true), use_delimiter(true))]
+    targets: Option<Vec<String>>,
+}
+
+pub fn exec(env: &dyn Environment, opts: DelegateOpts, network: Option<String>) -> DfxResult {
+    let log = env.get_logger();
+    let manager = network_identity_manager(env, network.clone())?;
+    let name = manager.get_selected_identity_name().clone();
+    if name == ANONYMOUS_IDENTITY_NAME {
+        bail!("The anonymous identity cannot delegate.");
+    }
+
+    let expire_after = duration::parse(&opts.expire_after)
+        .map_err(|_| anyhow!("Cannot parse expire_after as a duration (e.g. `1h`, `1h 30m`)"))?;
+
+    let targets = match opts.targets {
+        Some(targets) => {
+            let network = get_network_descriptor(env, network)?;
+            let canister_id_store = CanisterIdStore::for_network(&network)?;
+            let targets = targets
+                .iter()
+                .map(|target| {
+                    Principal::from_text(target).or_else(|_| canister_id_store.get(target))
+                })
+                .collect::<DfxResult<Vec<_>>>()?;
+            Some(targets)
+        }
+        None => None,
+    };
+
+    let delegation = delegation::create(&manager, &name, expire_after, targets)?;
+    let expiration: DateTime<Utc> = delegation.expiration_time().into();
+    info!(
+        log,
+        r#"Created a session key for identity "{}", valid until {}."#, name, expiration
+    );
+    if let Some(targets) = &delegation.targets {
+        let targets: Vec<String> = targets.iter().map(Principal::to_text).collect();
+        info!(log, "It can only call: {}", targets.join(", "));
+    }
+    Ok(())
+}
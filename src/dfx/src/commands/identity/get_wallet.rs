@@ -1,16 +1,23 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::identity::Identity;
+use crate::lib::output::print_result;
 use crate::lib::provider::{create_agent_environment, get_network_descriptor};
 use crate::lib::root_key::fetch_root_key_if_needed;
 
 use clap::Clap;
+use serde::Serialize;
 use tokio::runtime::Runtime;
 
 /// Gets the canister ID for the wallet associated with your identity on a network.
 #[derive(Clap)]
 pub struct GetWalletOpts {}
 
+#[derive(Serialize)]
+struct GetWallet {
+    wallet: String,
+}
+
 pub fn exec(env: &dyn Environment, _opts: GetWalletOpts, network: Option<String>) -> DfxResult {
     let agent_env = create_agent_environment(env, network.clone())?;
     let runtime = Runtime::new().expect("Unable to create a runtime");
@@ -23,13 +30,12 @@ pub fn exec(env: &dyn Environment, _opts: GetWalletOpts, network: Option<String>
         .to_string();
     let network = get_network_descriptor(&agent_env, network)?;
 
-    runtime.block_on(async {
-        println!(
-            "{}",
-            Identity::get_or_create_wallet(&agent_env, &network, &identity_name, true).await?
-        );
-        DfxResult::Ok(())
+    let wallet = runtime.block_on(async {
+        Identity::get_or_create_wallet(&agent_env, &network, &identity_name, true).await
     })?;
 
-    Ok(())
+    let result = GetWallet {
+        wallet: wallet.to_text(),
+    };
+    print_result(env, &result, |result| println!("{}", result.wallet))
 }
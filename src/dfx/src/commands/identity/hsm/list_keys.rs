@@ -0,0 +1,28 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::hsm;
+use crate::lib::output::print_result;
+
+use clap::Clap;
+
+/// Lists the EC keys on a token, by key id and label.
+/// Set the DFX_HSM_PIN environment variable if the token only shows its keys after login.
+#[derive(Clap)]
+pub struct ListKeysOpts {
+    /// The file path to the opensc-pkcs11 library e.g. "/usr/local/lib/opensc-pkcs11.so"
+    #[clap(long)]
+    pkcs11_lib_path: String,
+
+    /// The index of the slot holding the token, as shown by `dfx identity hsm list-slots`.
+    #[clap(long, default_value("0"))]
+    slot: usize,
+}
+
+pub fn exec(env: &dyn Environment, opts: ListKeysOpts) -> DfxResult {
+    let keys = hsm::list_keys(&opts.pkcs11_lib_path, opts.slot)?;
+    print_result(env, &keys, |keys| {
+        for key in keys {
+            println!("{} \"{}\"", key.key_id, key.label);
+        }
+    })
+}
@@ -0,0 +1,32 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::hsm;
+use crate::lib::output::print_result;
+
+use clap::Clap;
+
+/// Lists the slots that have a token present.
+/// Use the index in the first column as `--hsm-slot` for `dfx identity new`.
+#[derive(Clap)]
+pub struct ListSlotsOpts {
+    /// The file path to the opensc-pkcs11 library e.g. "/usr/local/lib/opensc-pkcs11.so"
+    #[clap(long)]
+    pkcs11_lib_path: String,
+}
+
+pub fn exec(env: &dyn Environment, opts: ListSlotsOpts) -> DfxResult {
+    let slots = hsm::list_slots(&opts.pkcs11_lib_path)?;
+    print_result(env, &slots, |slots| {
+        for slot in slots {
+            println!(
+                "{}: slot {} \"{}\" {} {} (serial {})",
+                slot.index,
+                slot.slot_id,
+                slot.token_label,
+                slot.manufacturer,
+                slot.model,
+                slot.serial_number
+            );
+        }
+    })
+}
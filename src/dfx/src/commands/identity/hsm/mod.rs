@@ -0,0 +1,28 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+
+use clap::Clap;
+
+mod list_keys;
+mod list_slots;
+
+/// Inspects PKCS#11 tokens (HSMs) to set up hardware identities.
+#[derive(Clap)]
+#[clap(name("hsm"))]
+pub struct HsmOpts {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Clap)]
+enum SubCommand {
+    ListKeys(list_keys::ListKeysOpts),
+    ListSlots(list_slots::ListSlotsOpts),
+}
+
+pub fn exec(env: &dyn Environment, opts: HsmOpts) -> DfxResult {
+    match opts.subcmd {
+        SubCommand::ListKeys(v) => list_keys::exec(env, v),
+        SubCommand::ListSlots(v) => list_slots::exec(env, v),
+    }
+}
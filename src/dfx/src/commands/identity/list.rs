@@ -1,29 +1,40 @@
+use crate::commands::identity::network_identity_manager;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
-use crate::lib::identity::identity_manager::IdentityManager;
+use crate::lib::output::print_result;
 
 use clap::Clap;
+use serde::Serialize;
 use std::io::Write;
 
 /// Lists existing identities.
 #[derive(Clap)]
 pub struct ListOpts {}
 
-pub fn exec(env: &dyn Environment, _opts: ListOpts) -> DfxResult {
-    let mgr = IdentityManager::new(env)?;
-    let identities = mgr.get_identity_names()?;
-    let current_identity = mgr.get_selected_identity_name();
-    for identity in identities {
-        if current_identity == &identity {
-            // same identity, suffix with '*'.
-            print!("{}", identity);
-            std::io::stdout().flush()?;
-            eprint!(" *");
-            std::io::stderr().flush()?;
-            println!();
-        } else {
-            println!("{}", identity);
+#[derive(Serialize)]
+struct IdentityList<'a> {
+    identities: Vec<String>,
+    selected: &'a str,
+}
+
+pub fn exec(env: &dyn Environment, _opts: ListOpts, network: Option<String>) -> DfxResult {
+    let mgr = network_identity_manager(env, network)?;
+    let result = IdentityList {
+        identities: mgr.get_identity_names()?,
+        selected: mgr.get_selected_identity_name(),
+    };
+    print_result(env, &result, |result| {
+        for identity in &result.identities {
+            if result.selected == identity {
+                // same identity, suffix with '*'.
+                print!("{}", identity);
+                let _ = std::io::stdout().flush();
+                eprint!(" *");
+                let _ = std::io::stderr().flush();
+                println!();
+            } else {
+                println!("{}", identity);
+            }
         }
-    }
-    Ok(())
+    })
 }
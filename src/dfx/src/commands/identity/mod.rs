@@ -1,16 +1,21 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_manager::IdentityManager;
+use crate::lib::provider::get_network_descriptor;
 
 use clap::Clap;
 
+mod delegate;
 mod deploy_wallet;
 mod get_wallet;
+mod hsm;
 mod import;
 mod list;
 mod new;
 mod principal;
 mod remove;
 mod rename;
+mod revoke_delegation;
 mod set_wallet;
 mod r#use;
 mod whoami;
@@ -30,14 +35,17 @@ pub struct IdentityOpt {
 
 #[derive(Clap)]
 enum SubCommand {
+    Delegate(delegate::DelegateOpts),
     DeployWallet(deploy_wallet::DeployWalletOpts),
     GetWallet(get_wallet::GetWalletOpts),
+    Hsm(hsm::HsmOpts),
     Import(import::ImportOpts),
     List(list::ListOpts),
     New(new::NewIdentityOpts),
     GetPrincipal(principal::GetPrincipalOpts),
     Remove(remove::RemoveOpts),
     Rename(rename::RenameOpts),
+    RevokeDelegation(revoke_delegation::RevokeDelegationOpts),
     SetWallet(set_wallet::SetWalletOpts),
     Use(r#use::UseOpts),
     Whoami(whoami::WhoAmIOpts),
@@ -45,16 +53,33 @@ enum SubCommand {
 
 pub fn exec(env: &dyn Environment, opts: IdentityOpt) -> DfxResult {
     match opts.subcmd {
+        SubCommand::Delegate(v) => delegate::exec(env, v, opts.network.clone()),
         SubCommand::DeployWallet(v) => deploy_wallet::exec(env, v, opts.network.clone()),
         SubCommand::GetWallet(v) => get_wallet::exec(env, v, opts.network.clone()),
-        SubCommand::List(v) => list::exec(env, v),
+        SubCommand::Hsm(v) => hsm::exec(env, v),
+        SubCommand::List(v) => list::exec(env, v, opts.network.clone()),
         SubCommand::New(v) => new::exec(env, v),
-        SubCommand::GetPrincipal(v) => principal::exec(env, v),
+        SubCommand::GetPrincipal(v) => principal::exec(env, v, opts.network.clone()),
         SubCommand::Import(v) => import::exec(env, v),
         SubCommand::Remove(v) => remove::exec(env, v),
         SubCommand::Rename(v) => rename::exec(env, v),
+        SubCommand::RevokeDelegation(v) => revoke_delegation::exec(env, v, opts.network.clone()),
         SubCommand::SetWallet(v) => set_wallet::exec(env, v, opts.network.clone()),
         SubCommand::Use(v) => r#use::exec(env, v),
-        SubCommand::Whoami(v) => whoami::exec(env, v),
+        SubCommand::Whoami(v) => whoami::exec(env, v, opts.network.clone()),
     }
 }
+
+/// The identity manager with the identity that dfx uses on the network, which is the one pinned
+/// in dfx.json if there is one.
+fn network_identity_manager(
+    env: &dyn Environment,
+    network: Option<String>,
+) -> DfxResult<IdentityManager> {
+    // Identities are only pinned in dfx.json.
+    if env.get_config().is_none() {
+        return IdentityManager::new(env);
+    }
+    let network = get_network_descriptor(env, network)?;
+    IdentityManager::for_network(env, &network)
+}
@@ -1,13 +1,14 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::identity::identity_manager::{
-    HardwareIdentityConfiguration, IdentityCreationParameters, IdentityManager,
+    ExternalIdentityConfiguration, HardwareIdentityConfiguration, IdentityCreationParameters,
+    IdentityManager,
 };
 use crate::util::clap::validators::is_hsm_key_id;
 
 use clap::Clap;
 use slog::info;
-use IdentityCreationParameters::{Hardware, Pem};
+use IdentityCreationParameters::{External, Hardware, NewHardwareKey, Pem};
 
 /// Creates a new identity.
 #[derive(Clap)]
@@ -22,6 +23,31 @@ pub struct NewIdentityOpts {
     /// A sequence of pairs of hex digits
     #[clap(long, requires("hsm-pkcs11-lib-path"), validator(is_hsm_key_id))]
     hsm_key_id: Option<String>,
+
+    /// The index of the slot holding the token, as shown by `dfx identity hsm list-slots`.
+    /// Defaults to the first slot with a token.
+    #[clap(long, requires("hsm-pkcs11-lib-path"))]
+    hsm_slot: Option<usize>,
+
+    /// Generates a new key pair with the given key id on the token, rather than using an existing key.
+    /// Requires the DFX_HSM_PIN environment variable.
+    #[clap(long, requires("hsm-pkcs11-lib-path"))]
+    hsm_generate_key: bool,
+
+    /// A program that signs messages on behalf of this identity, speaking the
+    /// external signer protocol over stdin/stdout.
+    #[clap(long, conflicts_with_all(&["hsm-pkcs11-lib-path", "hsm-key-id"]))]
+    external_signer: Option<String>,
+
+    /// An argument to pass to the external signer. Can be specified more than once.
+    #[clap(
+        long,
+        multiple(true),
+        number_of_values(1),
+        allow_hyphen_values(true),
+        requires("external-signer")
+    )]
+    external_signer_arg: Option<Vec<String>>,
 }
 
 pub fn exec(env: &dyn Environment, opts: NewIdentityOpts) -> DfxResult {
@@ -30,10 +56,26 @@ pub fn exec(env: &dyn Environment, opts: NewIdentityOpts) -> DfxResult {
     let log = env.get_logger();
     info!(log, r#"Creating identity: "{}"."#, name);
 
-    let creation_parameters = match (opts.hsm_pkcs11_lib_path, opts.hsm_key_id) {
-        (Some(pkcs11_lib_path), Some(key_id)) => Hardware(HardwareIdentityConfiguration {
-            pkcs11_lib_path,
-            key_id,
+    let creation_parameters = match (
+        opts.hsm_pkcs11_lib_path,
+        opts.hsm_key_id,
+        opts.external_signer,
+    ) {
+        (Some(pkcs11_lib_path), Some(key_id), _) => {
+            let parameters = HardwareIdentityConfiguration {
+                pkcs11_lib_path,
+                slot: opts.hsm_slot.unwrap_or(0),
+                key_id,
+            };
+            if opts.hsm_generate_key {
+                NewHardwareKey(parameters)
+            } else {
+                Hardware(parameters)
+            }
+        }
+        (_, _, Some(command)) => External(ExternalIdentityConfiguration {
+            command,
+            args: opts.external_signer_arg.unwrap_or_default(),
         }),
         _ => Pem(),
     };
@@ -1,21 +1,30 @@
+use crate::commands::identity::network_identity_manager;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
-use crate::lib::identity::identity_manager::IdentityManager;
+use crate::lib::output::print_result;
 
 use anyhow::anyhow;
 use clap::Clap;
 use ic_agent::identity::Identity;
+use serde::Serialize;
 
 /// Shows the textual representation of the Principal associated with the current identity.
 #[derive(Clap)]
 pub struct GetPrincipalOpts {}
 
-pub fn exec(env: &dyn Environment, _opts: GetPrincipalOpts) -> DfxResult {
-    let identity = IdentityManager::new(env)?.instantiate_selected_identity()?;
+#[derive(Serialize)]
+struct GetPrincipal {
+    principal: String,
+}
+
+pub fn exec(env: &dyn Environment, _opts: GetPrincipalOpts, network: Option<String>) -> DfxResult {
+    let identity = network_identity_manager(env, network)?.instantiate_selected_identity()?;
     let principal_id = identity
         .as_ref()
         .sender()
         .map_err(|err| anyhow!("{}", err))?;
-    println!("{}", principal_id.to_text());
-    Ok(())
+    let result = GetPrincipal {
+        principal: principal_id.to_text(),
+    };
+    print_result(env, &result, |result| println!("{}", result.principal))
 }
@@ -0,0 +1,31 @@
+use crate::commands::identity::network_identity_manager;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::delegation;
+
+use clap::Clap;
+use slog::info;
+
+/// Deletes the session key and delegation of the selected identity, so that requests are
+/// signed with the identity's own key again. The delegation itself stays valid until it expires;
+/// only the session key that can use it is destroyed.
+#[derive(Clap)]
+pub struct RevokeDelegationOpts {}
+
+pub fn exec(
+    env: &dyn Environment,
+    _opts: RevokeDelegationOpts,
+    network: Option<String>,
+) -> DfxResult {
+    let log = env.get_logger();
+    let manager = network_identity_manager(env, network)?;
+    let name = manager.get_selected_identity_name();
+    let dir = manager.get_identity_dir_path(name);
+
+    if delegation::revoke(&dir)? {
+        info!(log, r#"Deleted the session key of identity "{}"."#, name);
+    } else {
+        info!(log, r#"Identity "{}" has no session key."#, name);
+    }
+    Ok(())
+}
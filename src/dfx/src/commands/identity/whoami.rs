@@ -1,16 +1,24 @@
+use crate::commands::identity::network_identity_manager;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
-use crate::lib::identity::identity_manager::IdentityManager;
+use crate::lib::output::print_result;
 
 use clap::Clap;
+use serde::Serialize;
 
 /// Shows the name of the current identity.
 #[derive(Clap)]
 pub struct WhoAmIOpts {}
 
-pub fn exec(env: &dyn Environment, _opts: WhoAmIOpts) -> DfxResult {
-    let mgr = IdentityManager::new(env)?;
+#[derive(Serialize)]
+struct WhoAmI<'a> {
+    identity: &'a str,
+}
+
+pub fn exec(env: &dyn Environment, _opts: WhoAmIOpts, network: Option<String>) -> DfxResult {
+    let mgr = network_identity_manager(env, network)?;
     let identity = mgr.get_selected_identity_name();
-    println!("{}", identity);
-    Ok(())
+    print_result(env, &WhoAmI { identity }, |result| {
+        println!("{}", result.identity)
+    })
 }
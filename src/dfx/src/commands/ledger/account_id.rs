@@ -1,17 +1,32 @@
+use crate::commands::ledger::get_account_from_args;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
-use crate::lib::nns_types::account_identifier::AccountIdentifier;
+use crate::lib::output::print_result;
+use crate::util::clap::validators::subaccount_validator;
 
 use clap::Clap;
+use serde::Serialize;
 
 /// Prints the selected identity's AccountIdentifier.
 #[derive(Clap)]
-pub struct AccountIdOpts {}
+pub struct AccountIdOpts {
+    /// Principal to print the AccountIdentifier of, instead of the selected identity.
+    #[clap(long)]
+    of_principal: Option<String>,
 
-pub async fn exec(env: &dyn Environment, _opts: AccountIdOpts) -> DfxResult {
-    let sender = env
-        .get_selected_identity_principal()
-        .expect("Selected identity not instantiated.");
-    println!("{}", AccountIdentifier::new(sender, None));
-    Ok(())
+    /// Subaccount of the principal, as an index or as 64 hex digits.
+    #[clap(long, validator(subaccount_validator))]
+    subaccount: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AccountId {
+    account_id: String,
+}
+
+pub async fn exec(env: &dyn Environment, opts: AccountIdOpts) -> DfxResult {
+    let result = AccountId {
+        account_id: get_account_from_args(env, opts.of_principal, opts.subaccount)?.to_string(),
+    };
+    print_result(env, &result, |result| println!("{}", result.account_id))
 }
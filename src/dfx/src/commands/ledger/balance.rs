@@ -1,14 +1,16 @@
+use crate::commands::ledger::get_account_from_args;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::nns_types::account_identifier::AccountIdentifier;
 use crate::lib::nns_types::icpts::ICPTs;
 use crate::lib::nns_types::AccountBalanceArgs;
-use crate::lib::nns_types::LEDGER_CANISTER_ID;
+use crate::lib::output::print_result;
+use crate::util::clap::validators::subaccount_validator;
 
 use anyhow::anyhow;
 use candid::{Decode, Encode};
 use clap::Clap;
-use ic_types::principal::Principal;
+use serde::Serialize;
 use std::str::FromStr;
 
 const ACCOUNT_BALANCE_METHOD: &str = "account_balance_dfx";
@@ -18,23 +20,38 @@ const ACCOUNT_BALANCE_METHOD: &str = "account_balance_dfx";
 pub struct BalanceOpts {
     /// Specifies an AccountIdentifier to get the balance of
     of: Option<String>,
+
+    /// Principal to get the balance of, instead of the selected identity.
+    #[clap(long, conflicts_with("of"))]
+    of_principal: Option<String>,
+
+    /// Subaccount to get the balance of, as an index or as 64 hex digits.
+    #[clap(long, validator(subaccount_validator), conflicts_with("of"))]
+    subaccount: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AccountBalance {
+    account_id: String,
+
+    /// The balance as a decimal number of ICP.
+    balance: String,
+
+    e8s: u64,
 }
 
 pub async fn exec(env: &dyn Environment, opts: BalanceOpts) -> DfxResult {
-    let sender = env
-        .get_selected_identity_principal()
-        .expect("Selected identity not instantiated.");
-    let acc_id = opts
-        .of
-        .map_or_else(
-            || Ok(AccountIdentifier::new(sender, None)),
-            |v| AccountIdentifier::from_str(&v),
-        )
-        .map_err(|err| anyhow!(err))?;
+    let acc_id = match opts.of {
+        Some(of) => AccountIdentifier::from_str(&of).map_err(|err| anyhow!(err))?,
+        None => get_account_from_args(env, opts.of_principal, opts.subaccount)?,
+    };
     let agent = env
         .get_agent()
         .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?;
-    let canister_id = Principal::from_text(LEDGER_CANISTER_ID)?;
+    let canister_id = env
+        .get_network_descriptor()
+        .expect("No network descriptor.")
+        .ledger_canister_id()?;
 
     let result = agent
         .query(&canister_id, ACCOUNT_BALANCE_METHOD)
@@ -46,7 +63,10 @@ pub async fn exec(env: &dyn Environment, opts: BalanceOpts) -> DfxResult {
 
     let balance = Decode!(&result, ICPTs)?;
 
-    println!("{}", balance);
-
-    Ok(())
+    let result = AccountBalance {
+        account_id: acc_id.to_string(),
+        balance: balance.to_string(),
+        e8s: balance.get_e8s(),
+    };
+    print_result(env, &result, |result| println!("{}", result.balance))
 }
@@ -0,0 +1,50 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::ledger::blocks::{chain_length, get_blocks};
+use crate::lib::output::command_output_format;
+use crate::lib::root_key::fetch_root_key_if_needed;
+
+use anyhow::{anyhow, bail};
+use clap::Clap;
+
+/// Prints a block of the ledger, e.g. the one a transfer was recorded at.
+#[derive(Clap)]
+pub struct BlockOpts {
+    /// The height of the block, as printed by `dfx ledger transfer`.
+    height: u64,
+
+    /// Specifies the format of the output. Defaults to the global `--output` format.
+    #[clap(long, possible_values(&["text", "json"]))]
+    output: Option<String>,
+}
+
+pub async fn exec(env: &dyn Environment, opts: BlockOpts) -> DfxResult {
+    let output = command_output_format(env, opts.output.as_deref())?;
+    let agent = env
+        .get_agent()
+        .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?;
+    fetch_root_key_if_needed(env).await?;
+    let ledger_canister_id = env
+        .get_network_descriptor()
+        .expect("No network descriptor.")
+        .ledger_canister_id()?;
+
+    let block = get_blocks(agent, &ledger_canister_id, opts.height, 1)
+        .await?
+        .into_iter()
+        .find(|block| block.height == opts.height);
+    let block = match block {
+        Some(block) => block,
+        None => bail!(
+            "Block {} does not exist, the ledger has {} blocks.",
+            opts.height,
+            chain_length(agent, &ledger_canister_id).await?
+        ),
+    };
+
+    match output {
+        "json" => println!("{}", serde_json::to_string_pretty(&block)?),
+        _ => println!("{}", block),
+    }
+    Ok(())
+}
@@ -1,11 +1,16 @@
-use crate::commands::ledger::{get_icpts_from_args, send_and_notify};
+use crate::commands::ledger::{
+    get_cycles_purchase_from_args, get_subaccount_from_arg, print_operation_result, send_and_notify,
+};
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::ledger::journal::OperationKind;
 use crate::lib::nns_types::account_identifier::Subaccount;
 use crate::lib::nns_types::icpts::{ICPTs, TRANSACTION_FEE};
-use crate::lib::nns_types::{CyclesResponse, Memo};
+use crate::lib::nns_types::Memo;
 
-use crate::util::clap::validators::{e8s_validator, icpts_amount_validator};
+use crate::util::clap::validators::{
+    cycle_amount_validator, e8s_validator, icpts_amount_validator, subaccount_validator,
+};
 
 use anyhow::anyhow;
 use clap::Clap;
@@ -34,6 +39,15 @@ pub struct CreateCanisterOpts {
     #[clap(long, validator(e8s_validator), conflicts_with("amount"))]
     e8s: Option<String>,
 
+    /// Cycles to mint, instead of an amount of ICP. The ICP needed is computed from the
+    /// conversion rate of the cycles minting canister, and must be confirmed.
+    #[clap(
+        long,
+        validator(cycle_amount_validator),
+        conflicts_with_all(&["amount", "icp", "e8s"])
+    )]
+    cycles: Option<String>,
+
     /// Transaction fee, default is 10000 e8s.
     #[clap(long, validator(icpts_amount_validator))]
     fee: Option<String>,
@@ -41,15 +55,32 @@ pub struct CreateCanisterOpts {
     /// Max fee, default is 10000 e8s.
     #[clap(long, validator(icpts_amount_validator))]
     max_fee: Option<String>,
+
+    /// Subaccount to transfer from, as an index or as 64 hex digits.
+    #[clap(long, validator(subaccount_validator))]
+    from_subaccount: Option<String>,
+
+    /// Do not ask for confirmation of the cycles to mint.
+    #[clap(long)]
+    yes: bool,
 }
 
 pub async fn exec(env: &dyn Environment, opts: CreateCanisterOpts) -> DfxResult {
-    let amount = get_icpts_from_args(opts.amount, opts.icp, opts.e8s)?;
-
     let fee = opts.fee.map_or(Ok(TRANSACTION_FEE), |v| {
         ICPTs::from_str(&v).map_err(|err| anyhow!(err))
     })?;
 
+    let amount = get_cycles_purchase_from_args(
+        env,
+        opts.amount,
+        opts.icp,
+        opts.e8s,
+        opts.cycles,
+        fee,
+        opts.yes,
+    )
+    .await?;
+
     let memo = Memo(MEMO_CREATE_CANISTER);
 
     let to_subaccount = Some(Subaccount::from(&Principal::from_text(opts.controller)?));
@@ -59,21 +90,23 @@ pub async fn exec(env: &dyn Environment, opts: CreateCanisterOpts) -> DfxResult
         .map_or(Ok(TRANSACTION_FEE), |v| ICPTs::from_str(&v))
         .map_err(|err| anyhow!(err))?;
 
-    let result = send_and_notify(env, memo, amount, fee, to_subaccount, max_fee).await?;
-
-    match result {
-        CyclesResponse::CanisterCreated(v) => {
-            println!("Canister created with id: {:?}", v.to_text());
-        }
-        CyclesResponse::Refunded(msg, maybe_block_height) => {
-            match maybe_block_height {
-                Some(height) => {
-                    println!("Refunded at block height {} with message :{}", height, msg)
-                }
-                None => println!("Refunded with message: {}", msg),
-            };
-        }
-        CyclesResponse::ToppedUp(()) => unreachable!(),
-    };
-    Ok(())
+    let from_subaccount = opts
+        .from_subaccount
+        .as_deref()
+        .map(get_subaccount_from_arg)
+        .transpose()?;
+
+    let (block_height, result) = send_and_notify(
+        env,
+        OperationKind::CreateCanister,
+        memo,
+        amount,
+        fee,
+        from_subaccount,
+        to_subaccount,
+        max_fee,
+    )
+    .await?;
+
+    print_operation_result(env, block_height, Some(result))
 }
@@ -0,0 +1,30 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::ledger::conversion::get_conversion_rate;
+use crate::lib::output::print_result;
+use crate::lib::root_key::fetch_root_key_if_needed;
+
+use clap::Clap;
+use serde::Serialize;
+
+/// Prints the conversion rate of ICP to cycles of the cycles minting canister.
+#[derive(Clap)]
+pub struct CyclesRateOpts {}
+
+#[derive(Serialize)]
+struct CyclesRate {
+    timestamp_seconds: u64,
+    xdr_permyriad_per_icp: u64,
+    cycles_per_icp: u128,
+}
+
+pub async fn exec(env: &dyn Environment, _opts: CyclesRateOpts) -> DfxResult {
+    fetch_root_key_if_needed(env).await?;
+    let rate = get_conversion_rate(env).await?;
+    let result = CyclesRate {
+        timestamp_seconds: rate.timestamp_seconds,
+        xdr_permyriad_per_icp: rate.xdr_permyriad_per_icp,
+        cycles_per_icp: rate.cycles_per_icp(),
+    };
+    print_result(env, &result, |_| println!("{}", rate))
+}
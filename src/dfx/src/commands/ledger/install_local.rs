@@ -0,0 +1,163 @@
+use crate::config::dfinity::NetworkType;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_utils::{controller_principal, CallSender};
+use crate::lib::nns_types::account_identifier::AccountIdentifier;
+use crate::lib::nns_types::icpts::ICPTs;
+use crate::lib::operations::canister::{install_code, provisional_create_canister};
+use crate::lib::root_key::fetch_root_key_if_needed;
+use crate::util::expiry_duration;
+
+use anyhow::{anyhow, bail, Context};
+use candid::{CandidType, Encode};
+use clap::Clap;
+use ic_types::Principal;
+use ic_utils::interfaces::management_canister::builders::{CanisterInstall, InstallMode};
+use serde_json::Value;
+use slog::info;
+use std::str::FromStr;
+
+/// Installs a ledger and a cycles minting canister on a local network, for development.
+/// Their ids are recorded for the network in dfx.json, and used by the other ledger commands.
+#[derive(Clap)]
+pub struct InstallLocalOpts {
+    /// The Wasm module of the ledger canister.
+    #[clap(long)]
+    ledger_wasm: String,
+
+    /// The Wasm module of the cycles minting canister.
+    #[clap(long)]
+    cmc_wasm: String,
+
+    /// An initial balance, as <identity or principal>=<ICP>, e.g. `default=100`.
+    /// Can be specified more than once.
+    #[clap(long, multiple(true), number_of_values(1))]
+    initial_balance: Vec<String>,
+}
+
+#[derive(CandidType)]
+struct LedgerInitPayload {
+    minting_account: AccountIdentifier,
+    initial_values: Vec<(AccountIdentifier, ICPTs)>,
+    max_message_size_bytes: Option<u64>,
+    send_whitelist: Vec<Principal>,
+}
+
+#[derive(CandidType)]
+struct CyclesMintingInitPayload {
+    ledger_canister_id: Principal,
+    governance_canister_id: Principal,
+    minting_account_id: Option<AccountIdentifier>,
+}
+
+fn parse_initial_balance(
+    env: &dyn Environment,
+    initial_balance: &str,
+) -> DfxResult<(AccountIdentifier, ICPTs)> {
+    let mut parts = initial_balance.splitn(2, '=');
+    let (owner, amount) = match (parts.next(), parts.next()) {
+        (Some(owner), Some(amount)) => (owner, amount),
+        _ => bail!(
+            "Invalid initial balance '{}', expected <identity or principal>=<ICP>.",
+            initial_balance
+        ),
+    };
+    let principal = controller_principal(env, owner)?;
+    let amount = ICPTs::from_str(amount).map_err(|err| anyhow!(err))?;
+    Ok((AccountIdentifier::new(principal, None), amount))
+}
+
+pub async fn exec(env: &dyn Environment, opts: InstallLocalOpts) -> DfxResult {
+    let network = env
+        .get_network_descriptor()
+        .expect("No network descriptor.");
+    if network.is_ic || network.r#type != NetworkType::Ephemeral {
+        bail!(
+            "The network '{}' is not a local network. Only the ledger of a local network can be installed.",
+            network.name
+        );
+    }
+
+    let mut config = env.get_config_or_anyhow()?.as_ref().clone();
+    let network_config = config
+        .get_config()
+        .get_network(&network.name)
+        .ok_or_else(|| anyhow!("The network '{}' is not defined in dfx.json.", network.name))?;
+
+    let initial_values = opts
+        .initial_balance
+        .iter()
+        .map(|initial_balance| parse_initial_balance(env, initial_balance))
+        .collect::<DfxResult<Vec<_>>>()?;
+    let ledger_wasm = std::fs::read(&opts.ledger_wasm)
+        .with_context(|| format!("Cannot read the ledger Wasm '{}'.", opts.ledger_wasm))?;
+    let cmc_wasm = std::fs::read(&opts.cmc_wasm).with_context(|| {
+        format!(
+            "Cannot read the cycles minting canister Wasm '{}'.",
+            opts.cmc_wasm
+        )
+    })?;
+    let governance_id = env
+        .get_selected_identity_principal()
+        .expect("Selected identity not instantiated.");
+
+    let log = env.get_logger();
+    fetch_root_key_if_needed(env).await?;
+
+    // The management canister of the replica assigns the ids, the ones of the IC cannot be
+    // requested. They are recorded in dfx.json instead.
+    info!(
+        log,
+        "Creating the ledger canisters on the {} network.", network.name
+    );
+    let ledger_id = provisional_create_canister(env, None, None, expiry_duration()).await?;
+    let cmc_id = provisional_create_canister(env, None, None, expiry_duration()).await?;
+
+    // The cycles minting canister burns ICP by sending it to the minting account.
+    let minting_account = AccountIdentifier::new(cmc_id, None);
+
+    info!(log, "Installing the ledger canister {}.", ledger_id);
+    let install = CanisterInstall {
+        mode: InstallMode::Install,
+        canister_id: ledger_id,
+        wasm_module: ledger_wasm,
+        arg: Encode!(&LedgerInitPayload {
+            minting_account,
+            initial_values,
+            max_message_size_bytes: None,
+            send_whitelist: vec![cmc_id],
+        })?,
+    };
+    install_code(env, install, expiry_duration(), &CallSender::SelectedId).await?;
+
+    info!(log, "Installing the cycles minting canister {}.", cmc_id);
+    let install = CanisterInstall {
+        mode: InstallMode::Install,
+        canister_id: cmc_id,
+        wasm_module: cmc_wasm,
+        arg: Encode!(&CyclesMintingInitPayload {
+            ledger_canister_id: ledger_id,
+            governance_canister_id: governance_id,
+            minting_account_id: Some(minting_account),
+        })?,
+    };
+    install_code(env, install, expiry_duration(), &CallSender::SelectedId).await?;
+
+    let json = config.get_mut_json();
+    if json["networks"][&network.name].is_null() {
+        json["networks"][&network.name] = serde_json::to_value(&network_config)?;
+    }
+    let network_json = &mut json["networks"][&network.name];
+    network_json["ledger_canister_id"] = Value::String(ledger_id.to_text());
+    network_json["cycles_minting_canister_id"] = Value::String(cmc_id.to_text());
+    config.save()?;
+
+    info!(
+        log,
+        "Recorded the ledger canister {} and the cycles minting canister {} for the {} network in dfx.json.",
+        ledger_id,
+        cmc_id,
+        network.name
+    );
+    Ok(())
+}
@@ -1,32 +1,53 @@
+use crate::lib::confirmation::ask_for_consent;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::ledger::blocks::find_transfer;
+use crate::lib::ledger::conversion::get_conversion_rate;
+use crate::lib::ledger::journal::{
+    encode_subaccount, LedgerJournal, OperationKind, PendingNotification, PendingOperation,
+};
 use crate::lib::nns_types::account_identifier::{AccountIdentifier, Subaccount};
-use crate::lib::nns_types::icpts::ICPTs;
+use crate::lib::nns_types::icpts::{ICPTs, TRANSACTION_FEE};
 use crate::lib::nns_types::{
-    BlockHeight, CyclesResponse, Memo, NotifyCanisterArgs, SendArgs, CYCLE_MINTER_CANISTER_ID,
-    LEDGER_CANISTER_ID,
+    BlockHeight, CyclesResponse, Memo, NotifyCanisterArgs, SendArgs, TimeStamp,
 };
+use crate::lib::output::{print_progress, print_result};
 use crate::lib::provider::create_agent_environment;
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::lib::waiter::waiter_with_timeout;
-use crate::util::expiry_duration;
+use crate::util::{expiry_duration, ingress_expiry};
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail, Context};
 use candid::{Decode, Encode};
 use clap::Clap;
+use ic_agent::AgentError;
 use ic_types::principal::Principal;
+use serde::Serialize;
+use std::convert::TryFrom;
 use std::str::FromStr;
 use tokio::runtime::Runtime;
 
 const SEND_METHOD: &str = "send_dfx";
+const MEMO_TOP_UP_CANISTER: u64 = 1347768404_u64;
 const NOTIFY_METHOD: &str = "notify_dfx";
+/// The number of most recent blocks searched for a transfer that the ledger reports as sent.
+const DUPLICATE_SEARCH_BLOCKS: u64 = 100_000;
+const RESUME_HINT: &str =
+    "The operation is kept in the ledger journal. Run `dfx ledger resume` to finish it.";
 
 mod account_id;
 mod balance;
+mod block;
 mod create_canister;
+mod cycles_rate;
+mod install_local;
 mod notify;
+mod pending;
+mod resume;
 mod top_up;
+mod transactions;
 mod transfer;
+mod transfer_batch;
 
 /// Ledger commands.
 #[derive(Clap)]
@@ -44,10 +65,17 @@ pub struct LedgerOpts {
 enum SubCommand {
     AccountId(account_id::AccountIdOpts),
     Balance(balance::BalanceOpts),
+    Block(block::BlockOpts),
     CreateCanister(create_canister::CreateCanisterOpts),
+    CyclesRate(cycles_rate::CyclesRateOpts),
+    InstallLocal(install_local::InstallLocalOpts),
     Notify(notify::NotifyOpts),
+    Pending(pending::PendingOpts),
+    Resume(resume::ResumeOpts),
     TopUp(top_up::TopUpOpts),
+    Transactions(transactions::TransactionsOpts),
     Transfer(transfer::TransferOpts),
+    TransferBatch(transfer_batch::TransferBatchOpts),
 }
 
 pub fn exec(env: &dyn Environment, opts: LedgerOpts) -> DfxResult {
@@ -57,14 +85,99 @@ pub fn exec(env: &dyn Environment, opts: LedgerOpts) -> DfxResult {
         match opts.subcmd {
             SubCommand::AccountId(v) => account_id::exec(&agent_env, v).await,
             SubCommand::Balance(v) => balance::exec(&agent_env, v).await,
+            SubCommand::Block(v) => block::exec(&agent_env, v).await,
             SubCommand::CreateCanister(v) => create_canister::exec(&agent_env, v).await,
+            SubCommand::CyclesRate(v) => cycles_rate::exec(&agent_env, v).await,
+            SubCommand::InstallLocal(v) => install_local::exec(&agent_env, v).await,
             SubCommand::Notify(v) => notify::exec(&agent_env, v).await,
+            SubCommand::Pending(v) => pending::exec(&agent_env, v).await,
+            SubCommand::Resume(v) => resume::exec(&agent_env, v).await,
             SubCommand::TopUp(v) => top_up::exec(&agent_env, v).await,
+            SubCommand::Transactions(v) => transactions::exec(&agent_env, v).await,
             SubCommand::Transfer(v) => transfer::exec(&agent_env, v).await,
+            SubCommand::TransferBatch(v) => transfer_batch::exec(&agent_env, v).await,
         }
     })
 }
 
+/// What a ledger operation did, for the json output.
+#[derive(Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+enum Outcome {
+    Transferred,
+    ToppedUp,
+    CanisterCreated {
+        canister_id: String,
+    },
+    Refunded {
+        message: String,
+        refund_block_height: Option<BlockHeight>,
+    },
+}
+
+#[derive(Serialize)]
+struct OperationResult {
+    /// The block height of the transfer.
+    block_height: BlockHeight,
+
+    #[serde(flatten)]
+    outcome: Outcome,
+}
+
+/// Print the outcome of a ledger operation: the response of the cycles minting canister,
+/// if it was notified of the transfer.
+fn print_operation_result(
+    env: &dyn Environment,
+    block_height: BlockHeight,
+    response: Option<CyclesResponse>,
+) -> DfxResult {
+    let result = operation_result(block_height, response);
+    print_result(env, &result, print_operation_text)
+}
+
+fn operation_result(
+    block_height: BlockHeight,
+    response: Option<CyclesResponse>,
+) -> OperationResult {
+    let outcome = match response {
+        None => Outcome::Transferred,
+        Some(CyclesResponse::ToppedUp(())) => Outcome::ToppedUp,
+        Some(CyclesResponse::CanisterCreated(v)) => Outcome::CanisterCreated {
+            canister_id: v.to_text(),
+        },
+        Some(CyclesResponse::Refunded(message, refund_block_height)) => Outcome::Refunded {
+            message,
+            refund_block_height,
+        },
+    };
+    OperationResult {
+        block_height,
+        outcome,
+    }
+}
+
+fn print_operation_text(result: &OperationResult) {
+    match &result.outcome {
+        // The block height of the transfer was printed when it was sent.
+        Outcome::Transferred => {}
+        Outcome::ToppedUp => println!("Canister was topped up!"),
+        Outcome::CanisterCreated { canister_id } => {
+            println!("Canister created with id: {:?}", canister_id)
+        }
+        Outcome::Refunded {
+            message,
+            refund_block_height: Some(height),
+        } => println!(
+            "Refunded at block height {} with message :{}",
+            height, message
+        ),
+        Outcome::Refunded {
+            message,
+            refund_block_height: None,
+        } => println!("Refunded with message: {}", message),
+    }
+}
+
 fn get_icpts_from_args(
     amount: Option<String>,
     icp: Option<String>,
@@ -95,17 +208,159 @@ fn get_icpts_from_args(
     }
 }
 
+/// The ICP to send to the cycles minting canister: the given amount, or the amount that mints
+/// `cycles` at the current conversion rate. Shows the expected cycles before sending, and asks
+/// for confirmation when the amount was computed from `cycles`, unless `yes` is set.
+async fn get_cycles_purchase_from_args(
+    env: &dyn Environment,
+    amount: Option<String>,
+    icp: Option<String>,
+    e8s: Option<String>,
+    cycles: Option<String>,
+    fee: ICPTs,
+    yes: bool,
+) -> DfxResult<ICPTs> {
+    fetch_root_key_if_needed(env).await?;
+    let cycles = match cycles {
+        // validated by cycle_amount_validator
+        Some(cycles) => cycles.parse::<u64>().unwrap(),
+        None => {
+            let amount = get_icpts_from_args(amount, icp, e8s)?;
+            // The estimate is informative only, the transfer does not depend on it.
+            if let Ok(rate) = get_conversion_rate(env).await {
+                eprintln!("Expected cycles: {} ({}).", rate.cycles_for(amount), rate);
+            }
+            return Ok(amount);
+        }
+    };
+
+    let rate = get_conversion_rate(env).await?;
+    let amount = rate.icpts_for(cycles as u128)?;
+    eprintln!("Will mint cycles:");
+    eprintln!("  Rate:     {}", rate);
+    eprintln!("  Amount:   {}", amount);
+    eprintln!("  Fee:      {}", fee);
+    eprintln!("  Cycles:   {}", rate.cycles_for(amount));
+
+    if !yes {
+        ask_for_consent("the cycles")?;
+    }
+    Ok(amount)
+}
+
+/// Top up a canister with cycles bought with ICP from the account of the selected identity.
+pub async fn top_up_with_icp(
+    env: &dyn Environment,
+    canister_id: Principal,
+    cycles: u64,
+) -> DfxResult<CyclesResponse> {
+    fetch_root_key_if_needed(env).await?;
+    let amount = get_conversion_rate(env).await?.icpts_for(cycles as u128)?;
+    let (_, response) = send_and_notify(
+        env,
+        OperationKind::TopUp,
+        Memo(MEMO_TOP_UP_CANISTER),
+        amount,
+        TRANSACTION_FEE,
+        None,
+        Some(Subaccount::from(&canister_id)),
+        TRANSACTION_FEE,
+    )
+    .await?;
+    Ok(response)
+}
+
+/// Parse a subaccount given as an index or as 64 hex digits.
+/// An index is stored big-endian in the last 8 bytes of the subaccount.
+/// 64 digits are always hex, even if they are all decimal digits.
+fn get_subaccount_from_arg(subaccount: &str) -> DfxResult<Subaccount> {
+    if subaccount.len() == 64 {
+        return Ok(Subaccount::try_from(hex::decode(subaccount)?.as_slice())?);
+    }
+    let index = subaccount.parse::<u64>()?;
+    let mut bytes = [0; 32];
+    bytes[24..].copy_from_slice(&index.to_be_bytes());
+    Ok(Subaccount(bytes))
+}
+
+/// The account of a principal, the selected identity by default, and an optional subaccount.
+fn get_account_from_args(
+    env: &dyn Environment,
+    of_principal: Option<String>,
+    subaccount: Option<String>,
+) -> DfxResult<AccountIdentifier> {
+    let principal = match of_principal {
+        Some(principal) => Principal::from_text(principal)?,
+        None => env
+            .get_selected_identity_principal()
+            .expect("Selected identity not instantiated."),
+    };
+    let subaccount = subaccount
+        .as_deref()
+        .map(get_subaccount_from_arg)
+        .transpose()?;
+    Ok(AccountIdentifier::new(principal, subaccount))
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn send_and_notify(
     env: &dyn Environment,
+    kind: OperationKind,
     memo: Memo,
     amount: ICPTs,
     fee: ICPTs,
+    from_subaccount: Option<Subaccount>,
     to_subaccount: Option<Subaccount>,
     max_fee: ICPTs,
-) -> DfxResult<CyclesResponse> {
-    let ledger_canister_id = Principal::from_text(LEDGER_CANISTER_ID)?;
+) -> DfxResult<(BlockHeight, CyclesResponse)> {
+    let network = env
+        .get_network_descriptor()
+        .expect("No network descriptor.");
+    let cycle_minter_id = network.cycles_minting_canister_id()?;
+    let to = AccountIdentifier::new(cycle_minter_id, to_subaccount);
+    let notify = PendingNotification {
+        to_canister: cycle_minter_id,
+        to_subaccount: encode_subaccount(to_subaccount),
+        max_fee,
+    };
+
+    let operation = PendingOperation::new(
+        kind,
+        &network.name,
+        memo,
+        amount,
+        fee,
+        from_subaccount,
+        to,
+        Some(notify),
+    )?;
+    let mut journal = LedgerJournal::load(env)?;
+    journal.record(&operation)?;
+
+    let (block_height, result) = execute_operation(env, &mut journal, operation).await?;
+    Ok((block_height, result.expect("Notification result missing.")))
+}
 
-    let cycle_minter_id = Principal::from_text(CYCLE_MINTER_CANISTER_ID)?;
+/// Whether the ledger rejected a transfer because one with the same content and creation time
+/// was recorded already. The ledger traps with "Transaction already exists on chain."
+fn is_duplicate_transfer(reject_message: &str) -> bool {
+    reject_message.contains("already exists")
+}
+
+/// Send the transfer of a journaled operation unless it was sent already, then notify the
+/// cycles minting canister if the operation requires it. The journal is updated after each step.
+/// A transfer that the ledger rejects is removed from the journal, unless it was rejected as a
+/// duplicate: then it was sent by an earlier attempt, and its block height is looked up instead.
+/// Returns the block height of the transfer, and the response of the notification.
+async fn execute_operation(
+    env: &dyn Environment,
+    journal: &mut LedgerJournal,
+    mut operation: PendingOperation,
+) -> DfxResult<(BlockHeight, Option<CyclesResponse>)> {
+    let ledger_canister_id = env
+        .get_network_descriptor()
+        .expect("No network descriptor.")
+        .ledger_canister_id()?;
 
     let agent = env
         .get_agent()
@@ -113,36 +368,106 @@ async fn send_and_notify(
 
     fetch_root_key_if_needed(env).await?;
 
-    let to = AccountIdentifier::new(cycle_minter_id, to_subaccount);
+    let from_subaccount = operation.from_subaccount()?;
+    let block_height = match operation.block_height {
+        Some(block_height) => block_height,
+        None => {
+            let result = agent
+                .update(&ledger_canister_id, SEND_METHOD)
+                .with_arg(Encode!(&SendArgs {
+                    memo: operation.memo,
+                    amount: operation.amount,
+                    fee: operation.fee,
+                    from_subaccount,
+                    to: operation.to,
+                    created_at_time: Some(TimeStamp {
+                        timestamp_nanos: operation.created_at_time,
+                    }),
+                })?)
+                .expire_at(ingress_expiry(env))
+                .call_and_wait(waiter_with_timeout(expiry_duration()))
+                .await;
 
-    let result = agent
-        .update(&ledger_canister_id, SEND_METHOD)
-        .with_arg(Encode!(&SendArgs {
-            memo,
-            amount,
-            fee,
-            from_subaccount: None,
-            to,
-            created_at_time: None,
-        })?)
-        .call_and_wait(waiter_with_timeout(expiry_duration()))
-        .await?;
+            let block_height = match result {
+                Ok(result) => {
+                    let block_height = Decode!(&result, BlockHeight)?;
+                    print_progress(
+                        env,
+                        &format!("Transfer sent at BlockHeight: {}", block_height),
+                    );
+                    block_height
+                }
+                Err(AgentError::ReplicaError { reject_message, .. })
+                    if is_duplicate_transfer(&reject_message) =>
+                {
+                    // An earlier attempt went through, but its reply was lost.
+                    let block_height = find_transfer(
+                        agent,
+                        &ledger_canister_id,
+                        operation.created_at_time,
+                        operation.memo,
+                        &operation.to,
+                        operation.amount,
+                        DUPLICATE_SEARCH_BLOCKS,
+                    )
+                    .await?
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "The ledger rejected the transfer as a duplicate, so it was sent already, \
+                             but its block was not found. Run `dfx ledger pending --discard {}` \
+                             once you have checked the transactions of the account.",
+                            operation.created_at_time
+                        )
+                    })?;
+                    print_progress(
+                        env,
+                        &format!("Transfer was sent already at BlockHeight: {}", block_height),
+                    );
+                    block_height
+                }
+                Err(AgentError::ReplicaError {
+                    reject_code,
+                    reject_message,
+                }) => {
+                    // A rejected transfer did not happen, so there is nothing left to resume.
+                    journal.complete(&operation)?;
+                    bail!(
+                        "The ledger rejected the transfer (reject code {}): {}",
+                        reject_code,
+                        reject_message
+                    );
+                }
+                Err(err) => return Err(err).context(RESUME_HINT),
+            };
+            operation.block_height = Some(block_height);
+            journal.record(&operation)?;
+            block_height
+        }
+    };
 
-    let block_height = Decode!(&result, BlockHeight)?;
-    println!("Transfer sent at BlockHeight: {}", block_height);
+    let notify = match &operation.notify {
+        Some(notify) => notify,
+        None => {
+            journal.complete(&operation)?;
+            return Ok((block_height, None));
+        }
+    };
 
     let result = agent
         .update(&ledger_canister_id, NOTIFY_METHOD)
         .with_arg(Encode!(&NotifyCanisterArgs {
             block_height,
-            max_fee,
-            from_subaccount: None,
-            to_canister: cycle_minter_id,
-            to_subaccount,
+            max_fee: notify.max_fee,
+            from_subaccount,
+            to_canister: notify.to_canister,
+            to_subaccount: notify.to_subaccount()?,
         })?)
+        .expire_at(ingress_expiry(env))
         .call_and_wait(waiter_with_timeout(expiry_duration()))
-        .await?;
+        .await
+        .context(RESUME_HINT)?;
 
     let result = Decode!(&result, CyclesResponse)?;
-    Ok(result)
+    journal.complete(&operation)?;
+    Ok((block_height, Some(result)))
 }
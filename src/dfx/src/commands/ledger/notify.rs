@@ -1,15 +1,14 @@
+use crate::commands::ledger::print_operation_result;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::nns_types::account_identifier::Subaccount;
 use crate::lib::nns_types::icpts::{ICPTs, TRANSACTION_FEE};
-use crate::lib::nns_types::{
-    CyclesResponse, NotifyCanisterArgs, CYCLE_MINTER_CANISTER_ID, LEDGER_CANISTER_ID,
-};
+use crate::lib::nns_types::{CyclesResponse, NotifyCanisterArgs};
 use crate::util::clap::validators::{e8s_validator, icpts_amount_validator};
 
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::lib::waiter::waiter_with_timeout;
-use crate::util::expiry_duration;
+use crate::util::{expiry_duration, ingress_expiry};
 
 use anyhow::anyhow;
 use candid::{Decode, Encode};
@@ -48,8 +47,11 @@ pub async fn exec(env: &dyn Environment, opts: NotifyOpts) -> DfxResult {
         .map_or(Ok(TRANSACTION_FEE), |v| ICPTs::from_str(&v))
         .map_err(|err| anyhow!(err))?;
 
-    let ledger_canister_id = Principal::from_text(LEDGER_CANISTER_ID)?;
-    let cycle_minter_id = Principal::from_text(CYCLE_MINTER_CANISTER_ID)?;
+    let network = env
+        .get_network_descriptor()
+        .expect("No network descriptor.");
+    let ledger_canister_id = network.ledger_canister_id()?;
+    let cycle_minter_id = network.cycles_minting_canister_id()?;
 
     let to_subaccount = Some(Subaccount::from(&Principal::from_text(
         opts.destination_principal,
@@ -70,27 +72,10 @@ pub async fn exec(env: &dyn Environment, opts: NotifyOpts) -> DfxResult {
             to_canister: cycle_minter_id,
             to_subaccount,
         })?)
+        .expire_at(ingress_expiry(env))
         .call_and_wait(waiter_with_timeout(expiry_duration()))
         .await?;
 
     let result = Decode!(&result, CyclesResponse)?;
-
-    match result {
-        CyclesResponse::ToppedUp(()) => {
-            println!("Canister was topped up!");
-        }
-        CyclesResponse::Refunded(msg, maybe_block_height) => {
-            match maybe_block_height {
-                Some(height) => {
-                    println!("Refunded at block height {} with message :{}", height, msg)
-                }
-                None => println!("Refunded with message: {}", msg),
-            };
-        }
-        CyclesResponse::CanisterCreated(v) => {
-            println!("Canister created with id: {:?}", v.to_text());
-        }
-    };
-
-    Ok(())
+    print_operation_result(env, block_height, Some(result))
 }
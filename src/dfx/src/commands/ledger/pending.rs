@@ -0,0 +1,56 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::ledger::journal::LedgerJournal;
+use crate::lib::output::print_result;
+
+use anyhow::bail;
+use clap::Clap;
+
+/// Lists the ledger operations of the selected identity that did not complete.
+/// Finish them with `dfx ledger resume`.
+#[derive(Clap)]
+pub struct PendingOpts {
+    /// Removes the operation with this id from the journal without finishing it,
+    /// e.g. once you have checked that its transfer was never recorded.
+    #[clap(long)]
+    discard: Option<u64>,
+}
+
+pub async fn exec(env: &dyn Environment, opts: PendingOpts) -> DfxResult {
+    let network = env
+        .get_network_descriptor()
+        .expect("No network descriptor.");
+    let mut journal = LedgerJournal::load(env)?;
+    if let Some(id) = opts.discard {
+        return match journal.discard(id)? {
+            Some(operation) => {
+                eprintln!(
+                    "Discarded {} {} of {}.",
+                    operation.kind, operation.created_at_time, operation.amount
+                );
+                Ok(())
+            }
+            None => bail!("There is no pending ledger operation with id {}.", id),
+        };
+    }
+    let pending = journal.pending(&network.name);
+    print_result(env, &pending, |pending| {
+        if pending.is_empty() {
+            eprintln!("No pending ledger operations.");
+        }
+        for operation in pending {
+            let block_height = operation
+                .block_height
+                .map_or_else(String::new, |height| format!(" at BlockHeight {}", height));
+            println!(
+                "{} {} of {} to {}: {}{}",
+                operation.created_at_time,
+                operation.kind,
+                operation.amount,
+                operation.to,
+                operation.state(),
+                block_height
+            );
+        }
+    })
+}
@@ -0,0 +1,55 @@
+use crate::commands::ledger::{execute_operation, operation_result, print_operation_text, Outcome};
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::ledger::journal::LedgerJournal;
+use crate::lib::output::print_result;
+
+use anyhow::bail;
+use clap::Clap;
+
+/// Finishes the ledger operations listed by `dfx ledger pending`.
+/// A transfer that was not recorded yet is sent again with its original creation time,
+/// so the ledger rejects it as a duplicate if the first attempt went through after all.
+#[derive(Clap)]
+pub struct ResumeOpts {
+    /// The id of the operation to resume, as listed by `dfx ledger pending`.
+    /// Resumes all pending operations if omitted.
+    id: Option<u64>,
+}
+
+pub async fn exec(env: &dyn Environment, opts: ResumeOpts) -> DfxResult {
+    let network = env
+        .get_network_descriptor()
+        .expect("No network descriptor.");
+    let mut journal = LedgerJournal::load(env)?;
+    let pending: Vec<_> = journal
+        .pending(&network.name)
+        .into_iter()
+        .filter(|operation| opts.id.map_or(true, |id| operation.created_at_time == id))
+        .collect();
+    if pending.is_empty() {
+        match opts.id {
+            Some(id) => bail!("There is no pending ledger operation with id {}.", id),
+            None => eprintln!("No pending ledger operations."),
+        }
+    }
+
+    // The results are printed together, so that the json output is a single document.
+    let mut results = Vec::new();
+    for operation in pending {
+        eprintln!(
+            "Resuming {} {} of {}.",
+            operation.kind, operation.created_at_time, operation.amount
+        );
+        let (block_height, response) = execute_operation(env, &mut journal, operation).await?;
+        results.push(operation_result(block_height, response));
+    }
+    print_result(env, &results, |results| {
+        for result in results {
+            match result.outcome {
+                Outcome::Transferred => println!("Transfer complete."),
+                _ => print_operation_text(result),
+            }
+        }
+    })
+}
@@ -0,0 +1,81 @@
+use crate::commands::ledger::get_account_from_args;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::ledger::blocks::{chain_length, get_blocks};
+use crate::lib::nns_types::account_identifier::AccountIdentifier;
+use crate::lib::output::command_output_format;
+use crate::lib::root_key::fetch_root_key_if_needed;
+
+use anyhow::anyhow;
+use clap::Clap;
+use std::str::FromStr;
+
+/// The number of blocks fetched at once.
+const BATCH_SIZE: u64 = 1000;
+
+/// Prints the most recent transactions of an account, newest first.
+/// The ledger has no index by account, so only the most recent blocks are searched.
+#[derive(Clap)]
+pub struct TransactionsOpts {
+    /// The AccountIdentifier to list the transactions of.
+    /// Defaults to the account of the selected identity.
+    #[clap(long)]
+    account: Option<String>,
+
+    /// The maximum number of transactions to print.
+    #[clap(long, default_value("10"))]
+    limit: usize,
+
+    /// The number of most recent blocks to search.
+    #[clap(long, default_value("10000"))]
+    max_blocks: u64,
+
+    /// Specifies the format of the output. Defaults to the global `--output` format.
+    #[clap(long, possible_values(&["text", "json"]))]
+    output: Option<String>,
+}
+
+pub async fn exec(env: &dyn Environment, opts: TransactionsOpts) -> DfxResult {
+    let output = command_output_format(env, opts.output.as_deref())?;
+    let account = match opts.account {
+        Some(account) => AccountIdentifier::from_str(&account).map_err(|err| anyhow!(err))?,
+        None => get_account_from_args(env, None, None)?,
+    };
+    let agent = env
+        .get_agent()
+        .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?;
+    fetch_root_key_if_needed(env).await?;
+    let ledger_canister_id = env
+        .get_network_descriptor()
+        .expect("No network descriptor.")
+        .ledger_canister_id()?;
+
+    let mut end = chain_length(agent, &ledger_canister_id).await?;
+    let lowest = end.saturating_sub(opts.max_blocks);
+    let mut transactions = vec![];
+    while end > lowest && transactions.len() < opts.limit {
+        let start = end.saturating_sub(BATCH_SIZE).max(lowest);
+        let blocks = get_blocks(agent, &ledger_canister_id, start, end - start).await?;
+        transactions.extend(blocks.into_iter().rev().filter(|block| {
+            block
+                .operation
+                .as_ref()
+                .map_or(false, |operation| operation.involves(&account))
+        }));
+        end = start;
+    }
+    transactions.truncate(opts.limit);
+
+    match output {
+        "json" => println!("{}", serde_json::to_string_pretty(&transactions)?),
+        _ => {
+            if transactions.is_empty() {
+                eprintln!("No transactions of {} in the searched blocks.", account);
+            }
+            for block in transactions {
+                println!("{}", block);
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,22 +1,20 @@
-use crate::commands::ledger::get_icpts_from_args;
+use crate::commands::ledger::{
+    execute_operation, get_icpts_from_args, get_subaccount_from_arg, print_operation_result,
+};
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::ledger::journal::{LedgerJournal, OperationKind, PendingOperation};
 use crate::lib::nns_types::account_identifier::AccountIdentifier;
 use crate::lib::nns_types::icpts::{ICPTs, TRANSACTION_FEE};
-use crate::lib::nns_types::{BlockHeight, Memo, SendArgs, LEDGER_CANISTER_ID};
-use crate::lib::root_key::fetch_root_key_if_needed;
-use crate::lib::waiter::waiter_with_timeout;
-use crate::util::clap::validators::{e8s_validator, icpts_amount_validator, memo_validator};
-use crate::util::expiry_duration;
+use crate::lib::nns_types::Memo;
+use crate::util::clap::validators::{
+    e8s_validator, icpts_amount_validator, memo_validator, subaccount_validator,
+};
 
 use anyhow::anyhow;
-use candid::{Decode, Encode};
 use clap::Clap;
-use ic_types::principal::Principal;
 use std::str::FromStr;
 
-const SEND_METHOD: &str = "send_dfx";
-
 /// Transfer ICP from the user to the destination AccountIdentifier
 #[derive(Clap)]
 pub struct TransferOpts {
@@ -44,6 +42,10 @@ pub struct TransferOpts {
     /// Transaction fee, default is 10000 e8s.
     #[clap(long, validator(icpts_amount_validator))]
     fee: Option<String>,
+
+    /// Subaccount to transfer from, as an index or as 64 hex digits.
+    #[clap(long, validator(subaccount_validator))]
+    from_subaccount: Option<String>,
 }
 
 pub async fn exec(env: &dyn Environment, opts: TransferOpts) -> DfxResult {
@@ -58,29 +60,28 @@ pub async fn exec(env: &dyn Environment, opts: TransferOpts) -> DfxResult {
 
     let to = AccountIdentifier::from_str(&opts.to).map_err(|err| anyhow!(err))?;
 
-    let agent = env
-        .get_agent()
-        .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?;
-
-    fetch_root_key_if_needed(env).await?;
-
-    let canister_id = Principal::from_text(LEDGER_CANISTER_ID)?;
-
-    let result = agent
-        .update(&canister_id, SEND_METHOD)
-        .with_arg(Encode!(&SendArgs {
-            memo,
-            amount,
-            fee,
-            from_subaccount: None,
-            to,
-            created_at_time: None,
-        })?)
-        .call_and_wait(waiter_with_timeout(expiry_duration()))
-        .await?;
-
-    let block_height = Decode!(&result, BlockHeight)?;
-    println!("Transfer sent at BlockHeight: {}", block_height);
-
-    Ok(())
+    let from_subaccount = opts
+        .from_subaccount
+        .as_deref()
+        .map(get_subaccount_from_arg)
+        .transpose()?;
+
+    let network = env
+        .get_network_descriptor()
+        .expect("No network descriptor.");
+    let operation = PendingOperation::new(
+        OperationKind::Transfer,
+        &network.name,
+        memo,
+        amount,
+        fee,
+        from_subaccount,
+        to,
+        None,
+    )?;
+    let mut journal = LedgerJournal::load(env)?;
+    journal.record(&operation)?;
+
+    let (block_height, _) = execute_operation(env, &mut journal, operation).await?;
+    print_operation_result(env, block_height, None)
 }
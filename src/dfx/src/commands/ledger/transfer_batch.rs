@@ -0,0 +1,301 @@
+use crate::commands::ledger::{execute_operation, get_subaccount_from_arg};
+use crate::lib::confirmation::ask_for_consent;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::ledger::journal::{
+    encode_subaccount, LedgerJournal, OperationKind, PendingOperation,
+};
+use crate::lib::nns_types::account_identifier::AccountIdentifier;
+use crate::lib::nns_types::icpts::{ICPTs, TRANSACTION_FEE};
+use crate::lib::nns_types::{BlockHeight, Memo};
+use crate::lib::output::{print_json, OutputFormat};
+use crate::util::clap::validators::{icpts_amount_validator, subaccount_validator};
+
+use anyhow::{anyhow, bail, Context};
+use clap::Clap;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Transfer ICP to every row of a CSV file with the columns account id, amount and memo.
+/// The result of each row is saved next to the file, so running the command again
+/// skips the rows that were transferred already.
+#[derive(Clap)]
+pub struct TransferBatchOpts {
+    /// The CSV file. Blank lines, lines starting with '#' and a header row are ignored.
+    file: String,
+
+    /// Transaction fee of each transfer, default is 10000 e8s.
+    #[clap(long, validator(icpts_amount_validator))]
+    fee: Option<String>,
+
+    /// Subaccount to transfer from, as an index or as 64 hex digits.
+    #[clap(long, validator(subaccount_validator))]
+    from_subaccount: Option<String>,
+
+    /// The file the result of each row is saved to. Defaults to the CSV file with a
+    /// `.results.json` extension appended.
+    #[clap(long)]
+    results: Option<String>,
+
+    /// Do not ask for confirmation before transferring.
+    #[clap(long)]
+    yes: bool,
+}
+
+struct Row {
+    line: usize,
+    to: AccountIdentifier,
+    amount: ICPTs,
+    memo: Memo,
+}
+
+impl Row {
+    fn matches(&self, result: &RowResult) -> bool {
+        self.to == result.to && self.amount == result.amount && self.memo == result.memo
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct RowResult {
+    /// The line of the CSV file the row is on.
+    line: usize,
+    to: AccountIdentifier,
+    amount: ICPTs,
+    memo: Memo,
+    block_height: BlockHeight,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct BatchResults {
+    completed: Vec<RowResult>,
+}
+
+fn parse_rows(path: &Path) -> DfxResult<Vec<Row>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Cannot read '{}'.", path.display()))?;
+
+    let mut rows: Vec<Row> = vec![];
+    let mut errors = vec![];
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let columns: Vec<&str> = line.split(',').map(str::trim).collect();
+        if columns.len() != 3 {
+            errors.push(format!(
+                "line {}: expected 3 columns (account id, amount, memo), found {}",
+                line_number,
+                columns.len()
+            ));
+            continue;
+        }
+        let to = AccountIdentifier::from_str(columns[0]);
+        let amount = ICPTs::from_str(columns[1]);
+        let memo = columns[2].parse::<u64>();
+        if rows.is_empty() && errors.is_empty() && to.is_err() && amount.is_err() && memo.is_err() {
+            // A header row.
+            continue;
+        }
+        match (to, amount, memo) {
+            (Ok(to), Ok(amount), Ok(memo)) => {
+                let row = Row {
+                    line: line_number,
+                    to,
+                    amount,
+                    memo: Memo(memo),
+                };
+                match rows.iter().find(|other| {
+                    other.to == row.to && other.amount == row.amount && other.memo == row.memo
+                }) {
+                    Some(other) => errors.push(format!(
+                        "line {}: duplicates line {}, use a different memo",
+                        line_number, other.line
+                    )),
+                    None => rows.push(row),
+                }
+            }
+            (to, amount, memo) => {
+                if let Err(err) = to {
+                    errors.push(format!("line {}: invalid account id: {}", line_number, err));
+                }
+                if let Err(err) = amount {
+                    errors.push(format!("line {}: invalid amount: {}", line_number, err));
+                }
+                if let Err(err) = memo {
+                    errors.push(format!("line {}: invalid memo: {}", line_number, err));
+                }
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        bail!(
+            "Invalid rows in '{}':\n  {}",
+            path.display(),
+            errors.join("\n  ")
+        );
+    }
+    Ok(rows)
+}
+
+fn load_results(path: &Path) -> DfxResult<BatchResults> {
+    if !path.exists() {
+        return Ok(BatchResults::default());
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Cannot read results at '{}'.", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Cannot parse results at '{}'.", path.display()))
+}
+
+fn save_results(path: &Path, results: &BatchResults) -> DfxResult {
+    let content = serde_json::to_string_pretty(results)?;
+    std::fs::write(path, content)
+        .with_context(|| format!("Cannot write results at '{}'.", path.display()))
+}
+
+pub async fn exec(env: &dyn Environment, opts: TransferBatchOpts) -> DfxResult {
+    let file = PathBuf::from(&opts.file);
+    let results_path = opts.results.map_or_else(
+        || PathBuf::from(format!("{}.results.json", opts.file)),
+        PathBuf::from,
+    );
+
+    let fee = opts.fee.map_or(Ok(TRANSACTION_FEE), |v| {
+        ICPTs::from_str(&v).map_err(|err| anyhow!(err))
+    })?;
+    let from_subaccount = opts
+        .from_subaccount
+        .as_deref()
+        .map(get_subaccount_from_arg)
+        .transpose()?;
+
+    let rows = parse_rows(&file)?;
+    let mut results = load_results(&results_path)?;
+    let mut completed = vec![];
+    let mut remaining = vec![];
+    for row in rows {
+        match results
+            .completed
+            .iter()
+            .find(|result| result.line == row.line)
+        {
+            Some(result) if row.matches(result) => completed.push(row),
+            Some(result) => bail!(
+                "Line {} of '{}' changed since its row was transferred at BlockHeight {}. \
+                 Restore it, or save the results of the new rows to another file with --results.",
+                row.line,
+                file.display(),
+                result.block_height
+            ),
+            None => remaining.push(row),
+        }
+    }
+
+    if !completed.is_empty() {
+        eprintln!(
+            "Skipping {} rows that were transferred already, see '{}'.",
+            completed.len(),
+            results_path.display()
+        );
+    }
+    if remaining.is_empty() {
+        eprintln!("Nothing to transfer.");
+        return print_batch_results(env, &results);
+    }
+
+    let mut total = ICPTs::ZERO;
+    for row in &remaining {
+        total = (total + row.amount).map_err(|err| anyhow!(err))?;
+    }
+    let fees = ICPTs::from_e8s(
+        fee.get_e8s()
+            .checked_mul(remaining.len() as u64)
+            .ok_or_else(|| anyhow!("The total of the fees overflows."))?,
+    );
+    let sum = (total + fees).map_err(|err| anyhow!(err))?;
+
+    eprintln!("Will transfer:");
+    eprintln!("  Transfers: {}", remaining.len());
+    eprintln!("  Amount:    {}", total);
+    eprintln!("  Fees:      {}", fees);
+    eprintln!("  Total:     {}", sum);
+
+    if !opts.yes {
+        ask_for_consent("the transfers")?;
+    }
+
+    let network = env
+        .get_network_descriptor()
+        .expect("No network descriptor.");
+    let mut journal = LedgerJournal::load(env)?;
+    for row in remaining {
+        // An interrupted run may have left the transfer of this row in the journal. Resuming it
+        // reuses its creation time, so the ledger rejects it if it went through already.
+        let pending = journal
+            .pending(&network.name)
+            .into_iter()
+            .find(|operation| {
+                operation.kind == OperationKind::Transfer
+                    && operation.to == row.to
+                    && operation.amount == row.amount
+                    && operation.memo == row.memo
+                    && operation.fee == fee
+                    && operation.from_subaccount == encode_subaccount(from_subaccount)
+            });
+        let operation = match pending {
+            Some(operation) => operation,
+            None => {
+                let operation = PendingOperation::new(
+                    OperationKind::Transfer,
+                    &network.name,
+                    row.memo,
+                    row.amount,
+                    fee,
+                    from_subaccount,
+                    row.to,
+                    None,
+                )?;
+                journal.record(&operation)?;
+                operation
+            }
+        };
+
+        eprintln!(
+            "Transferring {} to {} (line {}).",
+            row.amount, row.to, row.line
+        );
+        // If the transfer of a resumed operation went through already, the ledger rejects it
+        // as a duplicate and the block height of the earlier transfer is returned.
+        let (block_height, _) = execute_operation(env, &mut journal, operation)
+            .await
+            .with_context(|| {
+                format!(
+                    "Transfer of line {} failed. Run the command again to continue the batch.",
+                    row.line
+                )
+            })?;
+
+        results.completed.push(RowResult {
+            line: row.line,
+            to: row.to,
+            amount: row.amount,
+            memo: row.memo,
+            block_height,
+        });
+        save_results(&results_path, &results)?;
+    }
+
+    print_batch_results(env, &results)
+}
+
+/// In the json format, print the results of all the rows transferred so far.
+fn print_batch_results(env: &dyn Environment, results: &BatchResults) -> DfxResult {
+    match env.get_output_format() {
+        OutputFormat::Text => Ok(()),
+        OutputFormat::Json => print_json(results),
+    }
+}
@@ -1,4 +1,6 @@
-use crate::config::dfinity::{NetworkType, DEFAULT_IC_GATEWAY};
+use crate::config::dfinity::{
+    ConfigNetworkLedger, ConfigNetworkPinning, NetworkType, DEFAULT_IC_GATEWAY,
+};
 use crate::lib::environment::{AgentEnvironment, Environment};
 use crate::lib::error::{DfxError, DfxResult};
 use crate::lib::network::network_descriptor::NetworkDescriptor;
@@ -44,6 +46,9 @@ pub fn exec(env: &dyn Environment, opts: PingOpts) -> DfxResult {
                 providers: vec![url],
                 r#type: NetworkType::Ephemeral,
                 is_ic: network_name == "ic" || network_name == DEFAULT_IC_GATEWAY,
+                protected: false,
+                pinning: ConfigNetworkPinning::default(),
+                ledger: ConfigNetworkLedger::default(),
             };
             Ok(network_descriptor)
         })?;
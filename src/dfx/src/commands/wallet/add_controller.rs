@@ -1,9 +1,11 @@
 use crate::commands::wallet::wallet_update;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::output::print_result;
 
 use clap::Clap;
 use ic_types::Principal;
+use serde::Serialize;
 
 /// Add a wallet controller.
 #[derive(Clap)]
@@ -12,9 +14,18 @@ pub struct AddControllerOpts {
     controller: String,
 }
 
+#[derive(Serialize)]
+struct AddedController {
+    controller: String,
+}
+
 pub async fn exec(env: &dyn Environment, opts: AddControllerOpts) -> DfxResult {
     let controller = Principal::from_text(opts.controller)?;
     wallet_update(env, "add_controller", controller).await?;
-    println!("Added {} as a controller.", controller);
-    Ok(())
+    let result = AddedController {
+        controller: controller.to_text(),
+    };
+    print_result(env, &result, |result| {
+        println!("Added {} as a controller.", result.controller)
+    })
 }
@@ -1,9 +1,11 @@
 use crate::commands::wallet::wallet_update;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::output::print_result;
 
 use clap::Clap;
 use ic_types::Principal;
+use serde::Serialize;
 
 /// Authorize a wallet custodian.
 #[derive(Clap)]
@@ -12,9 +14,18 @@ pub struct AuthorizeOpts {
     custodian: String,
 }
 
+#[derive(Serialize)]
+struct AuthorizedCustodian {
+    custodian: String,
+}
+
 pub async fn exec(env: &dyn Environment, opts: AuthorizeOpts) -> DfxResult {
     let custodian = Principal::from_text(opts.custodian)?;
     wallet_update(env, "authorize", custodian).await?;
-    println!("Authorized {} as a custodian.", custodian);
-    Ok(())
+    let result = AuthorizedCustodian {
+        custodian: custodian.to_text(),
+    };
+    print_result(env, &result, |result| {
+        println!("Authorized {} as a custodian.", result.custodian)
+    })
 }
@@ -1,16 +1,25 @@
 use crate::commands::wallet::wallet_query;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::output::print_result;
 
 use clap::Clap;
 use ic_utils::interfaces::wallet::BalanceResult;
+use serde::Serialize;
 
 /// Get the cycle balance of the selected Identity's cycles wallet.
 #[derive(Clap)]
 pub struct WalletBalanceOpts {}
 
+#[derive(Serialize)]
+struct WalletBalance {
+    cycles: u64,
+}
+
 pub async fn exec(env: &dyn Environment, _opts: WalletBalanceOpts) -> DfxResult {
     let (balance,): (BalanceResult,) = wallet_query(env, "wallet_balance", ()).await?;
-    println!("{} cycles.", balance.amount);
-    Ok(())
+    let result = WalletBalance {
+        cycles: balance.amount,
+    };
+    print_result(env, &result, |result| println!("{} cycles.", result.cycles))
 }
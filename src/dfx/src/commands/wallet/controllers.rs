@@ -1,18 +1,29 @@
 use crate::commands::wallet::wallet_query;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::output::print_result;
 
 use clap::Clap;
 use ic_types::Principal;
+use serde::Serialize;
 
 /// List the wallet's controllers.
 #[derive(Clap)]
 pub struct ControllersOpts {}
 
+#[derive(Serialize)]
+struct WalletControllers {
+    controllers: Vec<String>,
+}
+
 pub async fn exec(env: &dyn Environment, _opts: ControllersOpts) -> DfxResult {
     let (controllers,): (Vec<Principal>,) = wallet_query(env, "get_controllers", ()).await?;
-    for controller in controllers.iter() {
-        println!("{}", controller);
-    }
-    Ok(())
+    let result = WalletControllers {
+        controllers: controllers.iter().map(Principal::to_text).collect(),
+    };
+    print_result(env, &result, |result| {
+        for controller in &result.controllers {
+            println!("{}", controller);
+        }
+    })
 }
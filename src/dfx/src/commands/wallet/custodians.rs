@@ -1,18 +1,29 @@
 use crate::commands::wallet::wallet_query;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::output::print_result;
 
 use clap::Clap;
 use ic_types::Principal;
+use serde::Serialize;
 
 /// List the wallet's custodians.
 #[derive(Clap)]
 pub struct CustodiansOpts {}
 
+#[derive(Serialize)]
+struct WalletCustodians {
+    custodians: Vec<String>,
+}
+
 pub async fn exec(env: &dyn Environment, _opts: CustodiansOpts) -> DfxResult {
     let (custodians,): (Vec<Principal>,) = wallet_query(env, "get_custodians", ()).await?;
-    for custodian in custodians.iter() {
-        println!("{}", custodian);
-    }
-    Ok(())
+    let result = WalletCustodians {
+        custodians: custodians.iter().map(Principal::to_text).collect(),
+    };
+    print_result(env, &result, |result| {
+        for custodian in &result.custodians {
+            println!("{}", custodian);
+        }
+    })
 }
@@ -1,9 +1,11 @@
 use crate::commands::wallet::wallet_update;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::output::print_result;
 
 use clap::Clap;
 use ic_types::Principal;
+use serde::Serialize;
 
 /// Deauthorize a wallet custodian.
 #[derive(Clap)]
@@ -12,9 +14,18 @@ pub struct DeauthorizeOpts {
     custodian: String,
 }
 
+#[derive(Serialize)]
+struct DeauthorizedCustodian {
+    custodian: String,
+}
+
 pub async fn exec(env: &dyn Environment, opts: DeauthorizeOpts) -> DfxResult {
     let custodian = Principal::from_text(opts.custodian.clone())?;
     wallet_update(env, "deauthorize", custodian).await?;
-    println!("Deauthorized {} as a custodian.", opts.custodian);
-    Ok(())
+    let result = DeauthorizedCustodian {
+        custodian: custodian.to_text(),
+    };
+    print_result(env, &result, |result| {
+        println!("Deauthorized {} as a custodian.", result.custodian)
+    })
 }
@@ -0,0 +1,188 @@
+use crate::commands::wallet::wallet_query;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::output::command_output_format;
+
+use candid::CandidType;
+use chrono::{TimeZone, Utc};
+use clap::Clap;
+use ic_types::Principal;
+use serde::{Deserialize, Serialize};
+
+/// List the events of the wallet's log: cycles sent and received, canisters created and called,
+/// and changes of the address book, including its custodians and controllers.
+#[derive(Clap)]
+pub struct EventsOpts {
+    /// The id of the first event to list.
+    #[clap(long)]
+    from: Option<u32>,
+
+    /// The id of the event to stop listing at.
+    #[clap(long)]
+    to: Option<u32>,
+
+    /// Specifies the format of the output. Defaults to the global `--output` format.
+    #[clap(long, possible_values(&["text", "json"]))]
+    output: Option<String>,
+}
+
+#[derive(CandidType)]
+struct GetEventsArgs {
+    from: Option<u32>,
+    to: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Role {
+    Contact,
+    Custodian,
+    Controller,
+}
+
+#[derive(Serialize, Deserialize)]
+enum EventKind {
+    CyclesSent {
+        to: Principal,
+        amount: u64,
+        refund: u64,
+    },
+    CyclesReceived {
+        from: Principal,
+        amount: u64,
+    },
+    AddressAdded {
+        id: Principal,
+        name: Option<String>,
+        role: Role,
+    },
+    AddressRemoved {
+        id: Principal,
+    },
+    CanisterCreated {
+        canister: Principal,
+        cycles: u64,
+    },
+    CanisterCalled {
+        canister: Principal,
+        method_name: String,
+        cycles: u64,
+    },
+    WalletDeployed {
+        canister: Principal,
+    },
+}
+
+impl EventKind {
+    /// The principal the event is about.
+    fn principal(&self) -> &Principal {
+        match self {
+            EventKind::CyclesSent { to, .. } => to,
+            EventKind::CyclesReceived { from, .. } => from,
+            EventKind::AddressAdded { id, .. } | EventKind::AddressRemoved { id } => id,
+            EventKind::CanisterCreated { canister, .. }
+            | EventKind::CanisterCalled { canister, .. }
+            | EventKind::WalletDeployed { canister } => canister,
+        }
+    }
+
+    fn describe(&self, principal: &str) -> String {
+        match self {
+            EventKind::CyclesSent { amount, refund, .. } => format!(
+                "Sent {} cycles to {}, {} refunded",
+                amount, principal, refund
+            ),
+            EventKind::CyclesReceived { amount, .. } => {
+                format!("Received {} cycles from {}", amount, principal)
+            }
+            EventKind::AddressAdded { name, role, .. } => match name {
+                Some(name) => format!("Added {} as {:?} named {}", principal, role, name),
+                None => format!("Added {} as {:?}", principal, role),
+            },
+            EventKind::AddressRemoved { .. } => format!("Removed {}", principal),
+            EventKind::CanisterCreated { cycles, .. } => {
+                format!("Created canister {} with {} cycles", principal, cycles)
+            }
+            EventKind::CanisterCalled {
+                method_name,
+                cycles,
+                ..
+            } => format!(
+                "Called {} method {} with {} cycles",
+                principal, method_name, cycles
+            ),
+            EventKind::WalletDeployed { .. } => format!("Deployed wallet {}", principal),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Event {
+    id: u32,
+    timestamp: u64,
+    kind: EventKind,
+}
+
+/// An event, with the name of the canister it is about if the project knows it.
+#[derive(Serialize)]
+struct NamedEvent {
+    id: u32,
+
+    /// In nanoseconds since the UNIX epoch.
+    timestamp: u64,
+
+    kind: EventKind,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    canister_name: Option<String>,
+}
+
+pub async fn exec(env: &dyn Environment, opts: EventsOpts) -> DfxResult {
+    let output = command_output_format(env, opts.output.as_deref())?;
+    let (events,): (Vec<Event>,) = wallet_query(
+        env,
+        "get_events",
+        Some(GetEventsArgs {
+            from: opts.from,
+            to: opts.to,
+        }),
+    )
+    .await?;
+
+    // Network descriptor will always be set.
+    let network = env.get_network_descriptor().unwrap();
+    let id_store = CanisterIdStore::for_network(network).ok();
+    let events: Vec<NamedEvent> = events
+        .into_iter()
+        .map(|event| {
+            let canister_name = id_store
+                .as_ref()
+                .and_then(|id_store| id_store.get_name(&event.kind.principal().to_text()))
+                .cloned();
+            NamedEvent {
+                id: event.id,
+                timestamp: event.timestamp,
+                kind: event.kind,
+                canister_name,
+            }
+        })
+        .collect();
+
+    if output == "json" {
+        println!("{}", serde_json::to_string_pretty(&events)?);
+        return Ok(());
+    }
+    for event in events {
+        let principal = match &event.canister_name {
+            Some(name) => format!("{} ({})", event.kind.principal(), name),
+            None => event.kind.principal().to_text(),
+        };
+        println!(
+            "{} {} {}",
+            event.id,
+            Utc.timestamp_nanos(event.timestamp as i64),
+            event.kind.describe(&principal)
+        );
+    }
+    Ok(())
+}
@@ -1,22 +1,44 @@
 use crate::commands::wallet::wallet_query;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::output::print_result;
 
 use clap::Clap;
 use ic_utils::interfaces::wallet::AddressEntry;
+use serde::Serialize;
 
 /// Print wallet's address book.
 #[derive(Clap)]
 pub struct AddressesOpts {}
 
+#[derive(Serialize)]
+struct Address {
+    id: String,
+    kind: String,
+    role: String,
+    name: Option<String>,
+}
+
 pub async fn exec(env: &dyn Environment, _opts: AddressesOpts) -> DfxResult {
     let (entries,): (Vec<AddressEntry>,) = wallet_query(env, "list_addresses", ()).await?;
-    for entry in entries {
-        let name = entry.name.unwrap_or_else(|| "No name set.".to_string());
-        println!(
-            "Id: {}, Kind: {:?}, Role: {:?}, Name: {}",
-            entry.id, entry.kind, entry.role, name
-        );
-    }
-    Ok(())
+    let addresses: Vec<Address> = entries
+        .into_iter()
+        .map(|entry| Address {
+            id: entry.id.to_text(),
+            kind: format!("{:?}", entry.kind),
+            role: format!("{:?}", entry.role),
+            name: entry.name,
+        })
+        .collect();
+    print_result(env, &addresses, |addresses| {
+        for address in addresses {
+            println!(
+                "Id: {}, Kind: {}, Role: {}, Name: {}",
+                address.id,
+                address.kind,
+                address.role,
+                address.name.as_deref().unwrap_or("No name set.")
+            );
+        }
+    })
 }
@@ -1,13 +1,14 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::identity::Identity;
+use crate::lib::operations::call::update_call;
 use crate::lib::provider::create_agent_environment;
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::lib::waiter::waiter_with_timeout;
 use crate::util::expiry_duration;
 
-use candid::utils::ArgumentDecoder;
-use candid::CandidType;
+use candid::utils::{decode_args, ArgumentDecoder};
+use candid::{CandidType, Encode};
 use clap::Clap;
 use ic_utils::call::SyncCall;
 use tokio::runtime::Runtime;
@@ -18,6 +19,7 @@ mod balance;
 mod controllers;
 mod custodians;
 mod deauthorize;
+mod events;
 mod list_addresses;
 mod name;
 mod remove_controller;
@@ -49,6 +51,7 @@ enum SubCommand {
     Controllers(controllers::ControllersOpts),
     Custodians(custodians::CustodiansOpts),
     Deauthorize(deauthorize::DeauthorizeOpts),
+    Events(events::EventsOpts),
     Name(name::NameOpts),
     RemoveController(remove_controller::RemoveControllerOpts),
     Send(send::SendOpts),
@@ -68,6 +71,7 @@ pub fn exec(env: &dyn Environment, opts: WalletOpts) -> DfxResult {
             SubCommand::Controllers(v) => controllers::exec(&agent_env, v).await,
             SubCommand::Custodians(v) => custodians::exec(&agent_env, v).await,
             SubCommand::Deauthorize(v) => deauthorize::exec(&agent_env, v).await,
+            SubCommand::Events(v) => events::exec(&agent_env, v).await,
             SubCommand::Name(v) => name::exec(&agent_env, v).await,
             SubCommand::RemoveController(v) => remove_controller::exec(&agent_env, v).await,
             SubCommand::Send(v) => send::exec(&agent_env, v).await,
@@ -110,11 +114,15 @@ where
         Identity::get_or_create_wallet_canister(env, network, &identity_name, false).await?;
 
     fetch_root_key_if_needed(env).await?;
-    let out: O = wallet
-        .update_(method)
-        .with_arg(arg)
-        .build()
-        .call_and_wait(waiter_with_timeout(expiry_duration()))
-        .await?;
-    Ok(out)
+    let wallet_id = *wallet.canister_id_();
+    let reply = update_call(
+        env,
+        wallet_id,
+        wallet_id,
+        method,
+        Encode!(&arg)?,
+        waiter_with_timeout(expiry_duration()),
+    )
+    .await?;
+    Ok(decode_args(&reply)?)
 }
@@ -1,20 +1,26 @@
 use crate::commands::wallet::wallet_query;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::output::print_result;
 
 use clap::Clap;
+use serde::Serialize;
 
 /// Get wallet name.
 #[derive(Clap)]
 pub struct NameOpts {}
 
+#[derive(Serialize)]
+struct WalletName {
+    name: Option<String>,
+}
+
 pub async fn exec(env: &dyn Environment, _opts: NameOpts) -> DfxResult {
-    let (maybe_name,): (Option<String>,) = wallet_query(env, "name", ()).await?;
-    match maybe_name {
+    let (name,): (Option<String>,) = wallet_query(env, "name", ()).await?;
+    print_result(env, &WalletName { name }, |result| match &result.name {
         Some(name) => println!("{}", name),
         None => println!(
             "Name hasn't been set. Call `dfx wallet set-name` to give this cycles wallet a name."
         ),
-    };
-    Ok(())
+    })
 }
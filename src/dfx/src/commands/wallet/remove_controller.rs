@@ -1,9 +1,11 @@
 use crate::commands::wallet::wallet_update;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::output::print_result;
 
 use clap::Clap;
 use ic_types::Principal;
+use serde::Serialize;
 
 /// Remove a wallet controller.
 #[derive(Clap)]
@@ -12,9 +14,18 @@ pub struct RemoveControllerOpts {
     controller: String,
 }
 
+#[derive(Serialize)]
+struct RemovedController {
+    controller: String,
+}
+
 pub async fn exec(env: &dyn Environment, opts: RemoveControllerOpts) -> DfxResult {
     let controller = Principal::from_text(opts.controller)?;
     wallet_update(env, "remove_controller", controller).await?;
-    println!("Removed {} as a controller.", controller);
-    Ok(())
+    let result = RemovedController {
+        controller: controller.to_text(),
+    };
+    print_result(env, &result, |result| {
+        println!("Removed {} as a controller.", result.controller)
+    })
 }
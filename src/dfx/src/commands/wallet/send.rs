@@ -1,12 +1,15 @@
 use crate::commands::wallet::wallet_update;
+use crate::lib::confirmation::{confirm_destructive_operation, DestructiveOperation};
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::output::{print_json, OutputFormat};
 use crate::util::clap::validators::cycle_amount_validator;
 
 use anyhow::anyhow;
 use candid::CandidType;
 use clap::Clap;
 use ic_types::Principal;
+use serde::Serialize;
 
 /// Send cycles to another cycles wallet.
 #[derive(Clap)]
@@ -18,6 +21,16 @@ pub struct SendOpts {
     /// Deducted from the wallet.
     #[clap(validator(cycle_amount_validator))]
     amount: String,
+
+    /// Skips the confirmation prompt on protected networks.
+    #[clap(long)]
+    yes: bool,
+}
+
+#[derive(Serialize)]
+struct SentCycles {
+    destination: String,
+    cycles: u64,
 }
 
 pub async fn exec(env: &dyn Environment, opts: SendOpts) -> DfxResult {
@@ -29,13 +42,31 @@ pub async fn exec(env: &dyn Environment, opts: SendOpts) -> DfxResult {
     let canister = Principal::from_text(opts.destination.clone())?;
     // amount has been validated by cycle_amount_validator
     let amount = opts.amount.parse::<u64>().unwrap();
+    confirm_destructive_operation(
+        env,
+        &DestructiveOperation {
+            action: "Send cycles from your wallet to canister",
+            canister: &opts.destination,
+            canister_id: Some(canister),
+            cycles: Some(amount),
+        },
+        opts.yes,
+    )?;
     let (res,): (Result<(), String>,) =
         wallet_update(env, "wallet_send", In { canister, amount }).await?;
-    Ok(res.map_err(|err| {
+    res.map_err(|err| {
         anyhow!(
             "Sending cycles to {} failed with: {}",
             opts.destination,
             err
         )
-    })?)
+    })?;
+
+    match env.get_output_format() {
+        OutputFormat::Text => Ok(()),
+        OutputFormat::Json => print_json(&SentCycles {
+            destination: canister.to_text(),
+            cycles: amount,
+        }),
+    }
 }
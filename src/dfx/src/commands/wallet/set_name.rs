@@ -1,8 +1,10 @@
 use crate::commands::wallet::wallet_update;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::output::print_result;
 
 use clap::Clap;
+use serde::Serialize;
 
 /// Set wallet name.
 #[derive(Clap)]
@@ -11,8 +13,15 @@ pub struct SetNameOpts {
     name: String,
 }
 
+#[derive(Serialize)]
+struct WalletName {
+    name: String,
+}
+
 pub async fn exec(env: &dyn Environment, opts: SetNameOpts) -> DfxResult {
     wallet_update(env, "name", opts.name.clone()).await?;
-    println!("Set name to {}.", opts.name);
-    Ok(())
+    let result = WalletName { name: opts.name };
+    print_result(env, &result, |result| {
+        println!("Set name to {}.", result.name)
+    })
 }
@@ -1,22 +1,29 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_utils::CallSender;
 use crate::lib::identity::Identity;
+use crate::lib::operations::canister::install_code;
+use crate::lib::output::print_result;
 use crate::lib::root_key::fetch_root_key_if_needed;
-use crate::lib::waiter::waiter_with_timeout;
 use crate::util::assets::wallet_wasm;
 use crate::util::expiry_duration;
 
 use anyhow::{anyhow, bail};
+use candid::Encode;
 use clap::Clap;
 use ic_agent::AgentError;
-use ic_utils::call::AsyncCall;
-use ic_utils::interfaces::management_canister::builders::InstallMode;
-use ic_utils::interfaces::ManagementCanister;
+use ic_utils::interfaces::management_canister::builders::{CanisterInstall, InstallMode};
+use serde::Serialize;
 
 /// Upgrade the wallet's Wasm module to the current Wasm bundled with DFX.
 #[derive(Clap)]
 pub struct UpgradeOpts {}
 
+#[derive(Serialize)]
+struct UpgradedWallet {
+    wallet: String,
+}
+
 pub async fn exec(env: &dyn Environment, _opts: UpgradeOpts) -> DfxResult {
     let identity_name = env
         .get_selected_identity()
@@ -49,23 +56,20 @@ pub async fn exec(env: &dyn Environment, _opts: UpgradeOpts) -> DfxResult {
 
     let wasm = wallet_wasm(env.get_logger())?;
 
-    let mgr = ManagementCanister::create(
-        env.get_agent()
-            .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?,
-    );
-
-    mgr.install_code(&canister_id, wasm.as_slice())
-        .with_mode(install_mode)
-        .call_and_wait(waiter_with_timeout(expiry_duration()))
-        .await?;
-
-    let wallet = Identity::build_wallet_canister(canister_id, env)?;
+    let install = CanisterInstall {
+        mode: install_mode,
+        canister_id,
+        wasm_module: wasm.clone(),
+        arg: Encode!()?,
+    };
+    install_code(env, install, expiry_duration(), &CallSender::SelectedId).await?;
 
-    wallet
-        .wallet_store_wallet_wasm(wasm)
-        .call_and_wait(waiter_with_timeout(expiry_duration()))
-        .await?;
+    Identity::store_wallet_wasm(env, canister_id, wasm).await?;
 
-    println!("Upgraded the wallet wasm module.");
-    Ok(())
+    let result = UpgradedWallet {
+        wallet: canister_id.to_text(),
+    };
+    print_result(env, &result, |_| {
+        println!("Upgraded the wallet wasm module.")
+    })
 }
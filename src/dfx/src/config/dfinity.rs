@@ -3,6 +3,7 @@ use crate::lib::error::{BuildError, DfxError, DfxResult};
 use crate::{error_invalid_config, error_invalid_data};
 
 use anyhow::anyhow;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::{BTreeMap, HashSet};
@@ -52,6 +53,14 @@ pub struct ConfigCanistersCanister {
     pub extras: BTreeMap<String, Value>,
 }
 
+/// The cycles balance a canister must keep. When it falls below `minimum`,
+/// `dfx canister top-up` brings it back to `target`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConfigCanistersCanisterCycles {
+    pub minimum: u64,
+    pub target: u64,
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct CanisterDeclarationsConfig {
     // Directory to place declarations for that canister
@@ -115,12 +124,48 @@ impl NetworkType {
     }
 }
 
+/// The identity and wallet a project expects to use on a network.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigNetworkPinning {
+    /// The name of the identity to use on this network, unless `--identity` is passed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+
+    /// The principal the identity in use must have on this network.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+
+    /// The id of the wallet canister to use on this network.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<String>,
+}
+
+/// The ledger and cycles minting canisters of a network, if they differ from the ones on the IC.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigNetworkLedger {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ledger_canister_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cycles_minting_canister_id: Option<String>,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ConfigNetworkProvider {
     pub providers: Vec<String>,
 
     #[serde(default = "NetworkType::persistent")]
     pub r#type: NetworkType,
+
+    /// Whether destructive operations on this network must be confirmed.
+    #[serde(default)]
+    pub protected: bool,
+
+    #[serde(flatten)]
+    pub pinning: ConfigNetworkPinning,
+
+    #[serde(flatten)]
+    pub ledger: ConfigNetworkLedger,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
@@ -129,6 +174,16 @@ pub struct ConfigLocalProvider {
 
     #[serde(default = "NetworkType::ephemeral")]
     pub r#type: NetworkType,
+
+    /// Whether destructive operations on this network must be confirmed.
+    #[serde(default)]
+    pub protected: bool,
+
+    #[serde(flatten)]
+    pub pinning: ConfigNetworkPinning,
+
+    #[serde(flatten)]
+    pub ledger: ConfigNetworkLedger,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -138,6 +193,22 @@ pub enum ConfigNetwork {
     ConfigLocalProvider(ConfigLocalProvider),
 }
 
+impl ConfigNetwork {
+    pub fn get_pinning(&self) -> &ConfigNetworkPinning {
+        match self {
+            ConfigNetwork::ConfigNetworkProvider(network_provider) => &network_provider.pinning,
+            ConfigNetwork::ConfigLocalProvider(local_provider) => &local_provider.pinning,
+        }
+    }
+
+    pub fn get_ledger(&self) -> &ConfigNetworkLedger {
+        match self {
+            ConfigNetwork::ConfigNetworkProvider(network_provider) => &network_provider.ledger,
+            ConfigNetwork::ConfigLocalProvider(local_provider) => &local_provider.ledger,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum Profile {
     // debug is for development only
@@ -248,11 +319,21 @@ impl ConfigInterface {
             ("local", None) => Some(ConfigNetwork::ConfigLocalProvider(ConfigLocalProvider {
                 bind: String::from(DEFAULT_LOCAL_BIND),
                 r#type: NetworkType::Ephemeral,
+                protected: false,
+                pinning: ConfigNetworkPinning::default(),
+                ledger: ConfigNetworkLedger::default(),
             })),
+            // The providers of the IC cannot be changed, but its identity and wallet can be pinned.
             ("ic", _) => Some(ConfigNetwork::ConfigNetworkProvider(
                 ConfigNetworkProvider {
                     providers: vec![DEFAULT_IC_GATEWAY.to_string()],
                     r#type: NetworkType::Persistent,
+                    protected: true,
+                    pinning: network
+                        .as_ref()
+                        .map(|network| network.get_pinning().clone())
+                        .unwrap_or_default(),
+                    ledger: ConfigNetworkLedger::default(),
                 },
             )),
             _ => network,
@@ -312,11 +393,47 @@ impl ConfigInterface {
         self.get_initialization_value(canister_name, "freezing_threshold")
     }
 
-    fn get_initialization_value(
+    /// The controllers of a canister, as identity names or principals.
+    pub fn get_controllers(&self, canister_name: &str) -> DfxResult<Option<Vec<String>>> {
+        self.get_initialization_value(canister_name, "controllers")
+    }
+
+    /// The cycles policy of a canister.
+    pub fn get_cycles_policy(
+        &self,
+        canister_name: &str,
+    ) -> DfxResult<Option<ConfigCanistersCanisterCycles>> {
+        let canister_config = self
+            .canisters
+            .as_ref()
+            .and_then(|canisters| canisters.get(canister_name))
+            .ok_or_else(|| anyhow!("Cannot find canister '{}'.", canister_name))?;
+
+        let policy = canister_config
+            .extras
+            .get("cycles")
+            .map(ConfigCanistersCanisterCycles::deserialize)
+            .transpose()
+            .map_err(|_| {
+                error_invalid_config!(
+                    "Field cycles of canister {} must have a minimum and a target.",
+                    canister_name
+                )
+            })?;
+        match policy {
+            Some(policy) if policy.target < policy.minimum => Err(error_invalid_config!(
+                "The cycles target of canister {} is below its minimum.",
+                canister_name
+            )),
+            policy => Ok(policy),
+        }
+    }
+
+    fn get_initialization_value<T: DeserializeOwned>(
         &self,
         canister_name: &str,
         field: &str,
-    ) -> DfxResult<Option<String>> {
+    ) -> DfxResult<Option<T>> {
         let canister_map = (&self.canisters)
             .as_ref()
             .ok_or_else(|| error_invalid_config!("No canisters in the configuration file."))?;
@@ -329,7 +446,7 @@ impl ConfigInterface {
             .extras
             .get("initialization_values")
             .and_then(|v| v.get(field))
-            .map(String::deserialize)
+            .map(T::deserialize)
             .transpose()
             .map_err(|_| error_invalid_config!("Field {} is of the wrong type", field))
     }
@@ -667,8 +784,116 @@ mod tests {
             ConfigNetwork::ConfigNetworkProvider(ConfigNetworkProvider {
                 providers: vec![String::from("https://1.2.3.4:5000")],
                 r#type: NetworkType::Ephemeral,
+                protected: false,
+                pinning: ConfigNetworkPinning::default(),
+                ledger: ConfigNetworkLedger::default(),
+            })
+        );
+    }
+
+    #[test]
+    fn canister_can_declare_cycles_policy() {
+        let config = Config::from_str(
+            r#"{
+            "canisters": {
+                "hello": {
+                    "cycles": { "minimum": 1000, "target": 5000 }
+                },
+                "world": {}
+            }
+        }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.get_config().get_cycles_policy("hello").unwrap(),
+            Some(ConfigCanistersCanisterCycles {
+                minimum: 1000,
+                target: 5000
+            })
+        );
+        assert_eq!(
+            config.get_config().get_cycles_policy("world").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn network_can_set_ledger_canister_ids() {
+        let config = Config::from_str(
+            r#"{
+            "networks": {
+                "local": {
+                    "bind": "localhost:8000",
+                    "ledger_canister_id": "rrkah-fqaaa-aaaaa-aaaaq-cai",
+                    "cycles_minting_canister_id": "ryjl3-tyaaa-aaaaa-aaaba-cai"
+                }
+            }
+        }"#,
+        )
+        .unwrap();
+
+        let local = config.get_config().get_network("local").unwrap();
+        assert_eq!(
+            local.get_ledger(),
+            &ConfigNetworkLedger {
+                ledger_canister_id: Some(String::from("rrkah-fqaaa-aaaaa-aaaaq-cai")),
+                cycles_minting_canister_id: Some(String::from("ryjl3-tyaaa-aaaaa-aaaba-cai")),
+            }
+        );
+        assert_eq!(
+            config.get_config().get_network("ic").unwrap().get_ledger(),
+            &ConfigNetworkLedger::default()
+        );
+    }
+
+    #[test]
+    fn network_can_pin_identity_and_wallet() {
+        let config = Config::from_str(
+            r#"{
+            "networks": {
+                "ic": {
+                    "providers": [ "https://example.com" ],
+                    "identity": "deployer",
+                    "principal": "tg7pu-gsqvw-mfm4w-ek2sf-w3bpr-pv33y-7a3mu-pvutu-aqo56-qmdil-jqe",
+                    "wallet": "rwlgt-iiaaa-aaaaa-aaaaa-cai"
+                },
+                "local": {
+                    "bind": "localhost:8000",
+                    "identity": "default"
+                }
+            }
+        }"#,
+        )
+        .unwrap();
+
+        let ic = config.get_config().get_network("ic").unwrap();
+        assert_eq!(
+            ic,
+            ConfigNetwork::ConfigNetworkProvider(ConfigNetworkProvider {
+                providers: vec![String::from(DEFAULT_IC_GATEWAY)],
+                r#type: NetworkType::Persistent,
+                protected: true,
+                pinning: ConfigNetworkPinning {
+                    identity: Some(String::from("deployer")),
+                    principal: Some(String::from(
+                        "tg7pu-gsqvw-mfm4w-ek2sf-w3bpr-pv33y-7a3mu-pvutu-aqo56-qmdil-jqe"
+                    )),
+                    wallet: Some(String::from("rwlgt-iiaaa-aaaaa-aaaaa-cai")),
+                },
+                ledger: ConfigNetworkLedger::default(),
             })
         );
+
+        let local = config.get_config().get_network("local").unwrap();
+        assert_eq!(
+            local.get_pinning(),
+            &ConfigNetworkPinning {
+                identity: Some(String::from("default")),
+                principal: None,
+                wallet: None,
+            }
+        );
     }
 
     #[test]
@@ -679,7 +904,8 @@ mod tests {
                 "test_project": {
                   "initialization_values": {
                     "compute_allocation" : "100",
-                    "memory_allocation": "8GB"
+                    "memory_allocation": "8GB",
+                    "controllers": [ "alice", "aaaaa-aa" ]
                   }
                 }
               }
@@ -700,6 +926,12 @@ mod tests {
             .unwrap();
         assert_eq!("8GB", memory_allocation);
 
+        let controllers = config_interface
+            .get_controllers("test_project")
+            .unwrap()
+            .unwrap();
+        assert_eq!(vec!["alice", "aaaaa-aa"], controllers);
+
         let config_no_values = Config::from_str(
             r#"{
               "canisters": {
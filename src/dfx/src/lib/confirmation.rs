@@ -0,0 +1,98 @@
+//! Confirmation of destructive operations on protected networks.
+//!
+//! The `ic` network, and any network with `"protected": true` in dfx.json, is protected.
+//! Before a destructive operation runs there, the user sees a summary of its effect and must
+//! type the name of the canister it targets. `--yes` skips the prompt for automation.
+//!
+//! Operations that spend funds, like ledger transfers, ask for a plain y/N instead.
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_utils::CallSender;
+
+use anyhow::{anyhow, bail};
+use ic_types::Principal;
+
+/// A summary of a destructive operation.
+pub struct DestructiveOperation<'a> {
+    /// What is about to happen, e.g. "Delete canister".
+    pub action: &'a str,
+
+    /// The canister name or id as the user gave it. This is what must be typed to confirm.
+    pub canister: &'a str,
+
+    pub canister_id: Option<Principal>,
+
+    /// The cycles moved or lost by the operation, if known.
+    pub cycles: Option<u64>,
+}
+
+/// The principal that sends the calls of a command.
+pub fn caller_principal(env: &dyn Environment, call_sender: &CallSender) -> Option<Principal> {
+    match call_sender {
+        CallSender::SelectedId => env.get_selected_identity_principal(),
+        CallSender::SelectedIdWallet(wallet_id) | CallSender::Wallet(wallet_id) => Some(*wallet_id),
+    }
+}
+
+/// Ask the user to confirm a destructive operation, if the network is protected.
+/// Returns an error if the user does not confirm.
+pub fn confirm_destructive_operation(
+    env: &dyn Environment,
+    operation: &DestructiveOperation<'_>,
+    yes: bool,
+) -> DfxResult {
+    let network = env
+        .get_network_descriptor()
+        .ok_or_else(|| anyhow!("No network descriptor."))?;
+    if yes || !network.is_protected() {
+        return Ok(());
+    }
+
+    eprintln!(
+        "{} on protected network '{}':",
+        operation.action, network.name
+    );
+    eprintln!("  Network:    {}", network.providers.join(", "));
+    match operation.canister_id {
+        Some(canister_id) if canister_id.to_text() != operation.canister => eprintln!(
+            "  Canister:   {} ({})",
+            operation.canister,
+            canister_id.to_text()
+        ),
+        _ => eprintln!("  Canister:   {}", operation.canister),
+    }
+    if let Some(principal) = env.get_selected_identity_principal() {
+        eprintln!("  Principal:  {}", principal);
+    }
+    if let Some(cycles) = operation.cycles {
+        eprintln!("  Cycles:     {}", cycles);
+    }
+
+    // Not using dialoguer because it doesn't support non terminal env like bats e2e
+    eprintln!(
+        "\nType the canister name '{}' to confirm, or pass --yes:",
+        operation.canister
+    );
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    if input.trim() != operation.canister {
+        bail!(
+            "Aborted: {} was not confirmed.",
+            operation.action.to_lowercase()
+        );
+    }
+    Ok(())
+}
+
+/// Ask the user to confirm with y or yes, after a summary of what is about to happen.
+/// Returns an error naming `what` if the user does not confirm.
+pub fn ask_for_consent(what: &str) -> DfxResult {
+    // Not using dialoguer because it doesn't support non terminal env like bats e2e
+    eprintln!("\nOkay? [y/N]");
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    if !["y", "yes"].contains(&input.to_lowercase().trim()) {
+        bail!("Aborted: {} were not confirmed.", what);
+    }
+    Ok(())
+}
@@ -2,12 +2,20 @@ use crate::config::cache::{Cache, DiskBasedCache};
 use crate::config::dfinity::Config;
 use crate::config::{cache, dfx_version};
 use crate::lib::error::DfxResult;
+use crate::lib::http_trace::TraceReplicaV2Transport;
+use crate::lib::identity::delegation::{DelegationReplicaV2Transport, SessionDelegation};
 use crate::lib::identity::identity_manager::IdentityManager;
 use crate::lib::network::network_descriptor::NetworkDescriptor;
+use crate::lib::output::OutputFormat;
 use crate::lib::progress_bar::ProgressBar;
+use crate::lib::sign::replay_transport::{
+    RecordReplicaV2Transport, ReplayReplicaV2Transport, ReplicaFixture,
+};
 
 use anyhow::{anyhow, Context};
-use ic_agent::{Agent, Identity};
+use ic_agent::agent::http_transport::ReqwestHttpReplicaV2Transport;
+use ic_agent::agent::ReplicaV2Transport;
+use ic_agent::{Agent, Identity, NonceFactory};
 use ic_types::Principal;
 use semver::Version;
 use slog::{Logger, Record};
@@ -36,6 +44,20 @@ pub trait Environment {
     /// Notably, it is _not_ the name of the default identity or selected identity
     fn get_identity_override(&self) -> &Option<String>;
 
+    /// Whether dfx may use an identity whose principal differs from the one pinned in dfx.json,
+    /// as set by `--allow-identity-mismatch`.
+    fn get_identity_mismatch_allowed(&self) -> bool;
+
+    /// The format of the results that commands print on stdout, as set by `--output`.
+    fn get_output_format(&self) -> OutputFormat;
+
+    /// The file that the traffic with the replica is traced to, as set by `--trace-http`.
+    fn get_trace_http(&self) -> &Option<PathBuf>;
+
+    /// The fixture that the traffic with the replica is recorded to or replayed from, as set by
+    /// `--record-replica` or `--replay-replica`.
+    fn get_replica_fixture(&self) -> Option<Arc<ReplicaFixture>>;
+
     // Explicit lifetimes are actually needed for mockall to work properly.
     #[allow(clippy::needless_lifetimes)]
     fn get_agent<'a>(&'a self) -> Option<&'a Agent>;
@@ -70,6 +92,11 @@ pub struct EnvironmentImpl {
     progress: bool,
 
     identity_override: Option<String>,
+    identity_mismatch_allowed: bool,
+
+    output_format: OutputFormat,
+    trace_http: Option<PathBuf>,
+    replica_fixture: Option<Arc<ReplicaFixture>>,
 }
 
 impl EnvironmentImpl {
@@ -125,6 +152,10 @@ impl EnvironmentImpl {
             logger: None,
             progress: true,
             identity_override: None,
+            identity_mismatch_allowed: false,
+            output_format: OutputFormat::Text,
+            trace_http: None,
+            replica_fixture: None,
         })
     }
 
@@ -142,6 +173,26 @@ impl EnvironmentImpl {
         self.identity_override = identity;
         self
     }
+
+    pub fn with_identity_mismatch_allowed(mut self, allowed: bool) -> Self {
+        self.identity_mismatch_allowed = allowed;
+        self
+    }
+
+    pub fn with_output_format(mut self, output_format: OutputFormat) -> Self {
+        self.output_format = output_format;
+        self
+    }
+
+    pub fn with_trace_http(mut self, trace_http: Option<PathBuf>) -> Self {
+        self.trace_http = trace_http;
+        self
+    }
+
+    pub fn with_replica_fixture(mut self, replica_fixture: Option<Arc<ReplicaFixture>>) -> Self {
+        self.replica_fixture = replica_fixture;
+        self
+    }
 }
 
 impl Environment for EnvironmentImpl {
@@ -179,6 +230,22 @@ impl Environment for EnvironmentImpl {
         &self.identity_override
     }
 
+    fn get_identity_mismatch_allowed(&self) -> bool {
+        self.identity_mismatch_allowed
+    }
+
+    fn get_output_format(&self) -> OutputFormat {
+        self.output_format
+    }
+
+    fn get_trace_http(&self) -> &Option<PathBuf> {
+        &self.trace_http
+    }
+
+    fn get_replica_fixture(&self) -> Option<Arc<ReplicaFixture>> {
+        self.replica_fixture.clone()
+    }
+
     fn get_agent(&self) -> Option<&Agent> {
         // create an AgentEnvironment explicitly, in order to specify network and agent.
         // See install, build for examples.
@@ -231,14 +298,38 @@ impl<'a> AgentEnvironment<'a> {
         network_descriptor: NetworkDescriptor,
         timeout: Duration,
     ) -> DfxResult<Self> {
-        let mut identity_manager = IdentityManager::new(backend)?;
+        let identity_manager = IdentityManager::for_network(backend, &network_descriptor)?;
+        AgentEnvironment::with_identity_manager(
+            backend,
+            network_descriptor,
+            timeout,
+            identity_manager,
+        )
+    }
+
+    /// Create an environment whose agent uses the identity selected in the identity manager.
+    pub fn with_identity_manager(
+        backend: &'a dyn Environment,
+        network_descriptor: NetworkDescriptor,
+        timeout: Duration,
+        mut identity_manager: IdentityManager,
+    ) -> DfxResult<Self> {
         let identity = identity_manager.instantiate_selected_identity()?;
+        identity_manager.verify_pinned_principal(backend, &network_descriptor)?;
+        let delegation = identity.session_delegation().cloned();
 
         let agent_url = network_descriptor.providers.first().unwrap();
         Ok(AgentEnvironment {
             backend,
-            agent: create_agent(backend.get_logger().clone(), agent_url, identity, timeout)
-                .expect("Failed to construct agent."),
+            agent: create_agent(
+                backend.get_logger().clone(),
+                agent_url,
+                identity,
+                delegation,
+                timeout,
+                backend.get_trace_http().as_deref(),
+                backend.get_replica_fixture(),
+            )?,
             network_descriptor,
             identity_manager,
         })
@@ -280,6 +371,22 @@ impl<'a> Environment for AgentEnvironment<'a> {
         self.backend.get_identity_override()
     }
 
+    fn get_identity_mismatch_allowed(&self) -> bool {
+        self.backend.get_identity_mismatch_allowed()
+    }
+
+    fn get_output_format(&self) -> OutputFormat {
+        self.backend.get_output_format()
+    }
+
+    fn get_trace_http(&self) -> &Option<PathBuf> {
+        self.backend.get_trace_http()
+    }
+
+    fn get_replica_fixture(&self) -> Option<Arc<ReplicaFixture>> {
+        self.backend.get_replica_fixture()
+    }
+
     fn get_agent(&self) -> Option<&Agent> {
         Some(&self.agent)
     }
@@ -447,20 +554,90 @@ fn create_agent(
     logger: Logger,
     url: &str,
     identity: Box<dyn Identity + Send + Sync>,
+    delegation: Option<SessionDelegation>,
     timeout: Duration,
-) -> Option<Agent> {
-    AgentClient::new(logger, url.to_string())
-        .ok()
-        .and_then(|executor| {
-            Agent::builder()
-                .with_transport(
-                    ic_agent::agent::http_transport::ReqwestHttpReplicaV2Transport::create(url)
-                        .unwrap()
-                        .with_password_manager(executor),
-                )
-                .with_boxed_identity(identity)
-                .with_ingress_expiry(Some(timeout))
-                .build()
-                .ok()
-        })
+    trace_http: Option<&Path>,
+    replica_fixture: Option<Arc<ReplicaFixture>>,
+) -> DfxResult<Agent> {
+    // The request ids of the calls must be the same when recording and when replaying.
+    let nonce_factory = replica_fixture
+        .as_ref()
+        .map(ReplicaFixture::nonce_factory)
+        .unwrap_or_else(NonceFactory::random);
+    match replica_fixture {
+        Some(fixture) if fixture.is_replay() => trace_agent(
+            ReplayReplicaV2Transport::new(fixture),
+            trace_http,
+            identity,
+            delegation,
+            timeout,
+            nonce_factory,
+        ),
+        fixture => {
+            let executor = AgentClient::new(logger, url.to_string())?;
+            let transport = ReqwestHttpReplicaV2Transport::create(url)
+                .map_err(|err| anyhow!("Failed to construct agent: {}", err))?
+                .with_password_manager(executor);
+            match fixture {
+                Some(fixture) => trace_agent(
+                    RecordReplicaV2Transport::new(transport, fixture),
+                    trace_http,
+                    identity,
+                    delegation,
+                    timeout,
+                    nonce_factory,
+                ),
+                None => trace_agent(
+                    transport,
+                    trace_http,
+                    identity,
+                    delegation,
+                    timeout,
+                    nonce_factory,
+                ),
+            }
+        }
+    }
+}
+
+fn trace_agent<T: 'static + ReplicaV2Transport + Send + Sync>(
+    transport: T,
+    trace_http: Option<&Path>,
+    identity: Box<dyn Identity + Send + Sync>,
+    delegation: Option<SessionDelegation>,
+    timeout: Duration,
+    nonce_factory: NonceFactory,
+) -> DfxResult<Agent> {
+    match trace_http {
+        Some(path) => build_agent(
+            TraceReplicaV2Transport::new(transport, path)?,
+            identity,
+            delegation,
+            timeout,
+            nonce_factory,
+        ),
+        None => build_agent(transport, identity, delegation, timeout, nonce_factory),
+    }
+}
+
+/// Build an agent on a transport. The delegation is attached last, so that the inner
+/// transports see the envelopes as they are sent.
+fn build_agent<T: 'static + ReplicaV2Transport + Send + Sync>(
+    transport: T,
+    identity: Box<dyn Identity + Send + Sync>,
+    delegation: Option<SessionDelegation>,
+    timeout: Duration,
+    nonce_factory: NonceFactory,
+) -> DfxResult<Agent> {
+    let builder = match delegation {
+        Some(delegation) => Agent::builder()
+            .with_transport(DelegationReplicaV2Transport::new(transport, delegation)),
+        None => Agent::builder().with_transport(transport),
+    };
+    builder
+        .with_boxed_identity(identity)
+        .with_ingress_expiry(Some(timeout))
+        .with_nonce_factory(nonce_factory)
+        .build()
+        .map_err(|err| anyhow!("Failed to construct agent: {}", err))
 }
@@ -33,4 +33,7 @@ pub enum IdentityError {
 
     #[error("Cannot read identity file at '{0}': {1}")]
     CannotReadIdentityFile(PathBuf, Box<DfxError>),
+
+    #[error("Identity '{0}' has principal {1}, but dfx.json expects {2} on the '{3}' network. Pass --allow-identity-mismatch to use it anyway.")]
+    PrincipalMismatch(String, String, String, String),
 }
@@ -0,0 +1,185 @@
+//! Golden files of canister call results, for `dfx canister call --record` and `--expect`.
+//!
+//! A golden file is the JSON of the result, converted with the Candid types of the method, so
+//! fields are named and sorted. Results are compared structurally, and differences are reported
+//! by their JSON pointer, e.g. `/0/items/2/name`.
+use crate::lib::error::DfxResult;
+
+use anyhow::{anyhow, bail};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+
+/// The configuration of `--expect`, e.g. `{ "ignore": ["/0/updated_at", "/0/items/*/timestamp"] }`.
+#[derive(Default, Deserialize)]
+pub struct ExpectConfig {
+    /// JSON pointers of the values that are not compared. A `*` segment matches any field
+    /// or index.
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+impl ExpectConfig {
+    pub fn load(path: &Path) -> DfxResult<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?;
+        serde_json::from_str(&content).map_err(|e| anyhow!("Invalid {}: {}", path.display(), e))
+    }
+}
+
+/// A value that differs between the golden file and the result.
+#[derive(Debug, PartialEq)]
+pub struct Difference {
+    pub path: String,
+    pub expected: Option<Value>,
+    pub actual: Option<Value>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        match (&self.expected, &self.actual) {
+            (Some(expected), Some(actual)) => {
+                write!(f, "{}: expected {}, found {}", path, expected, actual)
+            }
+            (Some(expected), None) => write!(f, "{}: missing, expected {}", path, expected),
+            (None, Some(actual)) => write!(f, "{}: unexpected {}", path, actual),
+            (None, None) => write!(f, "{}", path),
+        }
+    }
+}
+
+/// Write a result to a golden file.
+pub fn record(path: &Path, result: &Value) -> DfxResult {
+    let content = serde_json::to_string_pretty(result)?;
+    std::fs::write(path, content + "\n")
+        .map_err(|e| anyhow!("Cannot write {}: {}", path.display(), e))
+}
+
+/// Compare a result with a golden file, and fail with the differences.
+pub fn expect(path: &Path, result: &Value, config: &ExpectConfig) -> DfxResult {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?;
+    let expected: Value =
+        serde_json::from_str(&content).map_err(|e| anyhow!("Invalid {}: {}", path.display(), e))?;
+    let differences = diff(&expected, result, &config.ignore);
+    if !differences.is_empty() {
+        bail!(
+            "The result differs from {}:\n{}",
+            path.display(),
+            differences
+                .iter()
+                .map(|difference| format!("  {}", difference))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+    Ok(())
+}
+
+/// The differences between two values, except at the ignored paths.
+pub fn diff(expected: &Value, actual: &Value, ignore: &[String]) -> Vec<Difference> {
+    let ignore: Vec<Vec<&str>> = ignore
+        .iter()
+        .map(|pointer| pointer.split('/').skip(1).collect())
+        .collect();
+    let mut differences = vec![];
+    diff_at(
+        &mut vec![],
+        Some(expected),
+        Some(actual),
+        &ignore,
+        &mut differences,
+    );
+    differences
+}
+
+fn diff_at(
+    path: &mut Vec<String>,
+    expected: Option<&Value>,
+    actual: Option<&Value>,
+    ignore: &[Vec<&str>],
+    differences: &mut Vec<Difference>,
+) {
+    let is_ignored = ignore.iter().any(|pattern| {
+        pattern.len() == path.len()
+            && pattern
+                .iter()
+                .zip(path.iter())
+                .all(|(pattern, segment)| *pattern == "*" || *pattern == segment.as_str())
+    });
+    if is_ignored {
+        return;
+    }
+
+    match (expected, actual) {
+        (Some(Value::Object(expected)), Some(Value::Object(actual))) => {
+            let mut keys: Vec<&String> = expected.keys().chain(actual.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                path.push(key.replace('~', "~0").replace('/', "~1"));
+                diff_at(
+                    path,
+                    expected.get(key),
+                    actual.get(key),
+                    ignore,
+                    differences,
+                );
+                path.pop();
+            }
+        }
+        (Some(Value::Array(expected)), Some(Value::Array(actual))) => {
+            for i in 0..expected.len().max(actual.len()) {
+                path.push(i.to_string());
+                diff_at(path, expected.get(i), actual.get(i), ignore, differences);
+                path.pop();
+            }
+        }
+        (expected, actual) if expected != actual => differences.push(Difference {
+            path: path.iter().map(|segment| format!("/{}", segment)).collect(),
+            expected: expected.cloned(),
+            actual: actual.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_differences_by_path() {
+        let expected = json!([{ "name": "Alice", "tags": ["a", "b"], "age": "30" }]);
+        let actual = json!([{ "name": "Bob", "tags": ["a"], "age": "30", "extra": null }]);
+        let differences: Vec<String> = diff(&expected, &actual, &[])
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            differences,
+            vec![
+                "/0/extra: unexpected null",
+                "/0/name: expected \"Alice\", found \"Bob\"",
+                "/0/tags/1: missing, expected \"b\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_paths() {
+        let expected =
+            json!([{ "items": [{ "timestamp": "1" }, { "timestamp": "2" }], "at": "3" }]);
+        let actual = json!([{ "items": [{ "timestamp": "4" }, { "timestamp": "5" }], "at": "6" }]);
+        let ignore = vec!["/0/items/*/timestamp".to_string(), "/0/at".to_string()];
+        assert!(diff(&expected, &actual, &ignore).is_empty());
+        assert_eq!(diff(&expected, &actual, &ignore[1..]).len(), 2);
+    }
+}
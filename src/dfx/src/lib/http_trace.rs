@@ -0,0 +1,286 @@
+//! Tracing of the traffic between dfx and the replica, for the global `--trace-http <file>`.
+//!
+//! Every request is appended to the file as a line of JSON with its response, so that a trace
+//! can be attached to a bug report. Envelopes are decoded from CBOR: principals as text,
+//! arguments and replies as Candid, and other blobs as hex. Signatures are redacted.
+use crate::lib::error::DfxResult;
+
+use anyhow::anyhow;
+use candid::IDLArgs;
+use chrono::Utc;
+use ic_agent::agent::ReplicaV2Transport;
+use ic_agent::{AgentError, RequestId};
+use ic_types::Principal;
+use serde::Serialize;
+use serde_cbor::Value as Cbor;
+use serde_json::{json, Value};
+use std::convert::TryFrom;
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::Instant;
+
+const REDACTED: &str = "<redacted>";
+
+/// A request and its response, as a line of the trace.
+#[derive(Serialize)]
+struct TraceEntry {
+    time: String,
+    endpoint: String,
+    effective_canister_id: Option<String>,
+    request_id: Option<String>,
+    request: Option<Value>,
+    /// The HTTP status of a failed request. The transport does not expose the status of a
+    /// successful one, so it is left out rather than assumed.
+    status: Option<u16>,
+    response: Option<Value>,
+    error: Option<String>,
+    duration_ms: f64,
+}
+
+/// Writes every request of the inner transport and its response to a trace file.
+pub(crate) struct TraceReplicaV2Transport<T> {
+    inner: T,
+    file: Mutex<File>,
+}
+
+impl<T: ReplicaV2Transport> TraceReplicaV2Transport<T> {
+    /// Append the trace to a file. Several agents of a command can share it.
+    pub fn new(inner: T, path: &Path) -> DfxResult<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| anyhow!("Cannot open the HTTP trace {}: {}", path.display(), e))?;
+        Ok(Self {
+            inner,
+            file: Mutex::new(file),
+        })
+    }
+
+    fn start(
+        endpoint: String,
+        effective_canister_id: Option<Principal>,
+        envelope: Option<&[u8]>,
+        request_id: Option<&RequestId>,
+    ) -> (TraceEntry, Instant) {
+        let entry = TraceEntry {
+            time: Utc::now().to_rfc3339(),
+            endpoint,
+            effective_canister_id: effective_canister_id.map(|id| id.to_text()),
+            request_id: request_id.map(|id| format!("0x{}", hex::encode(id.as_slice()))),
+            request: envelope.map(decode_cbor),
+            status: None,
+            response: None,
+            error: None,
+            duration_ms: 0.0,
+        };
+        (entry, Instant::now())
+    }
+
+    fn finish<R>(
+        &self,
+        mut entry: TraceEntry,
+        start: Instant,
+        result: &Result<R, AgentError>,
+        response: impl FnOnce(&R) -> Value,
+    ) {
+        entry.duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+        match result {
+            Ok(reply) => {
+                entry.response = Some(response(reply));
+            }
+            Err(err) => {
+                if let AgentError::HttpError(payload) = err {
+                    entry.status = Some(payload.status);
+                }
+                entry.error = Some(err.to_string());
+            }
+        }
+        // A trace that cannot be written must not fail the request.
+        if let (Ok(line), Ok(mut file)) = (serde_json::to_string(&entry), self.file.lock()) {
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+}
+
+impl<T: ReplicaV2Transport + Send + Sync> ReplicaV2Transport for TraceReplicaV2Transport<T> {
+    fn read_state<'a>(
+        &'a self,
+        effective_canister_id: Principal,
+        envelope: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let (entry, start) = Self::start(
+                format!("/api/v2/canister/{}/read_state", effective_canister_id),
+                Some(effective_canister_id),
+                Some(&envelope),
+                None,
+            );
+            let result = self.inner.read_state(effective_canister_id, envelope).await;
+            // The certificate is a hash tree, only its size is traced.
+            self.finish(
+                entry,
+                start,
+                &result,
+                |reply| json!({ "certificate_size": reply.len() }),
+            );
+            result
+        })
+    }
+
+    fn call<'a>(
+        &'a self,
+        effective_canister_id: Principal,
+        envelope: Vec<u8>,
+        request_id: RequestId,
+    ) -> Pin<Box<dyn Future<Output = Result<(), AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let (entry, start) = Self::start(
+                format!("/api/v2/canister/{}/call", effective_canister_id),
+                Some(effective_canister_id),
+                Some(&envelope),
+                Some(&request_id),
+            );
+            let result = self
+                .inner
+                .call(effective_canister_id, envelope, request_id)
+                .await;
+            self.finish(entry, start, &result, |_| Value::Null);
+            result
+        })
+    }
+
+    fn query<'a>(
+        &'a self,
+        effective_canister_id: Principal,
+        envelope: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let (entry, start) = Self::start(
+                format!("/api/v2/canister/{}/query", effective_canister_id),
+                Some(effective_canister_id),
+                Some(&envelope),
+                None,
+            );
+            let result = self.inner.query(effective_canister_id, envelope).await;
+            self.finish(entry, start, &result, |reply| decode_cbor(reply));
+            result
+        })
+    }
+
+    fn status<'a>(
+        &'a self,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let (entry, start) = Self::start("/api/v2/status".to_string(), None, None, None);
+            let result = self.inner.status().await;
+            self.finish(entry, start, &result, |reply| decode_cbor(reply));
+            result
+        })
+    }
+}
+
+/// Decode an envelope or a response for the trace.
+fn decode_cbor(bytes: &[u8]) -> Value {
+    match serde_cbor::from_slice::<Cbor>(bytes) {
+        Ok(cbor) => cbor_to_json(&cbor, None),
+        Err(err) => json!({ "undecodable": err.to_string(), "bytes": hex::encode(bytes) }),
+    }
+}
+
+/// Convert CBOR to JSON, interpreting blobs by the name of the field they are in.
+fn cbor_to_json(cbor: &Cbor, field: Option<&str>) -> Value {
+    match cbor {
+        Cbor::Map(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        Cbor::Text(key) => key.clone(),
+                        key => format!("{:?}", key),
+                    };
+                    let value = match key.as_str() {
+                        "sender_sig" | "signature" => Value::String(REDACTED.to_string()),
+                        _ => cbor_to_json(value, Some(&key)),
+                    };
+                    (key, value)
+                })
+                .collect(),
+        ),
+        Cbor::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| cbor_to_json(value, field))
+                .collect(),
+        ),
+        Cbor::Bytes(bytes) => match field {
+            Some("sender") | Some("canister_id") => Principal::try_from(bytes)
+                .map(|principal| Value::String(principal.to_text()))
+                .unwrap_or_else(|_| Value::String(hex::encode(bytes))),
+            Some("arg") => IDLArgs::from_bytes(bytes)
+                .map(|args| Value::String(args.to_string()))
+                .unwrap_or_else(|_| Value::String(hex::encode(bytes))),
+            // Labels of read_state paths, e.g. `request_status`, are text.
+            Some("paths") if !bytes.is_empty() && bytes.iter().all(|b| b.is_ascii_graphic()) => {
+                Value::String(String::from_utf8_lossy(bytes).to_string())
+            }
+            _ => Value::String(hex::encode(bytes)),
+        },
+        Cbor::Text(text) => Value::String(text.clone()),
+        Cbor::Integer(n) => match u64::try_from(*n) {
+            Ok(n) => json!(n),
+            Err(_) => Value::String(n.to_string()),
+        },
+        Cbor::Float(f) => json!(f),
+        Cbor::Bool(b) => Value::Bool(*b),
+        Cbor::Tag(_, value) => cbor_to_json(value, field),
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn text(s: &str) -> Cbor {
+        Cbor::Text(s.to_string())
+    }
+
+    #[test]
+    fn decodes_envelopes_and_redacts_signatures() {
+        let arg = "(\"Alice\")"
+            .parse::<IDLArgs>()
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+        let mut content = BTreeMap::new();
+        content.insert(text("request_type"), text("query"));
+        content.insert(text("sender"), Cbor::Bytes(vec![4]));
+        content.insert(text("method_name"), text("greet"));
+        content.insert(text("arg"), Cbor::Bytes(arg));
+        content.insert(text("ingress_expiry"), Cbor::Integer(1_000));
+        let mut envelope = BTreeMap::new();
+        envelope.insert(text("content"), Cbor::Map(content));
+        envelope.insert(text("sender_sig"), Cbor::Bytes(vec![1, 2, 3]));
+
+        let json = cbor_to_json(&Cbor::Map(envelope), None);
+        assert_eq!(
+            json,
+            json!({
+                "content": {
+                    "request_type": "query",
+                    "sender": "2vxsx-fae",
+                    "method_name": "greet",
+                    "arg": "(\"Alice\")",
+                    "ingress_expiry": 1000,
+                },
+                "sender_sig": REDACTED,
+            })
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Session keys signed off by an identity.
+//!
+//! `dfx identity delegate` generates a fresh Ed25519 session key in the identity directory and
+//! has the identity's own key sign a delegation to it. Until the delegation expires, the identity
+//! is loaded from the session key instead, and every envelope sent to the replica carries the
+//! delegation. For HSM identities this means no PIN prompt and no token round trip per request.
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_manager::{generate_key, IdentityManager};
+use crate::lib::identity::Identity;
+
+use anyhow::{anyhow, Context};
+use ic_agent::agent::ReplicaV2Transport;
+use ic_agent::identity::BasicIdentity;
+use ic_agent::{AgentError, RequestId, Signature};
+use ic_types::Principal;
+use serde::{Deserialize, Serialize};
+use serde_cbor::Value;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const SESSION_PEM: &str = "session.pem";
+pub const DELEGATION_JSON: &str = "delegation.json";
+
+const DELEGATION_DOMAIN_SEPARATOR: &[u8] = b"\x1Aic-request-auth-delegation";
+
+/// The delegation as it is hashed and signed, following the interface specification.
+#[derive(Serialize)]
+struct Delegation<'a> {
+    #[serde(with = "serde_bytes")]
+    pubkey: &'a [u8],
+
+    expiration: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    targets: Option<&'a Vec<Principal>>,
+}
+
+/// A delegation from an identity's key to a session key, as cached in `delegation.json`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionDelegation {
+    /// The DER encoded public key of the delegating identity.
+    #[serde(with = "hex")]
+    pub public_key: Vec<u8>,
+
+    /// The DER encoded public key of the session key.
+    #[serde(with = "hex")]
+    pub session_public_key: Vec<u8>,
+
+    /// Expiration of the delegation, in nanoseconds since the UNIX epoch.
+    pub expiration: u64,
+
+    /// The canisters the session key may call. Any canister if absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub targets: Option<Vec<Principal>>,
+
+    #[serde(with = "hex")]
+    pub signature: Vec<u8>,
+}
+
+impl SessionDelegation {
+    pub fn principal(&self) -> Principal {
+        Principal::self_authenticating(&self.public_key)
+    }
+
+    pub fn expiration_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_nanos(self.expiration)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expiration_time() <= SystemTime::now()
+    }
+
+    /// The `sender_delegation` field of a request envelope.
+    fn to_cbor(&self) -> Value {
+        let mut delegation = BTreeMap::new();
+        delegation.insert(
+            Value::Text("pubkey".to_string()),
+            Value::Bytes(self.session_public_key.clone()),
+        );
+        delegation.insert(
+            Value::Text("expiration".to_string()),
+            Value::Integer(self.expiration as i128),
+        );
+        if let Some(targets) = &self.targets {
+            delegation.insert(
+                Value::Text("targets".to_string()),
+                Value::Array(
+                    targets
+                        .iter()
+                        .map(|target| Value::Bytes(target.as_slice().to_vec()))
+                        .collect(),
+                ),
+            );
+        }
+
+        let mut signed_delegation = BTreeMap::new();
+        signed_delegation.insert(
+            Value::Text("delegation".to_string()),
+            Value::Map(delegation),
+        );
+        signed_delegation.insert(
+            Value::Text("signature".to_string()),
+            Value::Bytes(self.signature.clone()),
+        );
+        Value::Array(vec![Value::Map(signed_delegation)])
+    }
+}
+
+/// Signs with the session key, on behalf of the delegating identity.
+pub struct SessionIdentity {
+    principal: Principal,
+    session: BasicIdentity,
+}
+
+impl ic_agent::Identity for SessionIdentity {
+    fn sender(&self) -> Result<Principal, String> {
+        Ok(self.principal)
+    }
+
+    fn sign(&self, blob: &[u8]) -> Result<Signature, String> {
+        self.session.sign(blob)
+    }
+}
+
+/// Create a session key and a delegation to it, replacing any previous ones.
+pub fn create(
+    manager: &IdentityManager,
+    name: &str,
+    expire_after: Duration,
+    targets: Option<Vec<Principal>>,
+) -> DfxResult<SessionDelegation> {
+    use ic_agent::identity::Identity as _;
+
+    let identity = Identity::load_root(manager, name)?;
+    let dir = manager.get_identity_dir_path(name);
+    revoke(&dir)?;
+
+    let session_pem_path = dir.join(SESSION_PEM);
+    generate_key(&session_pem_path)?;
+    let session = BasicIdentity::from_pem_file(&session_pem_path)?;
+    let session_public_key = session
+        .sign(&[])
+        .map_err(|err| anyhow!(err))?
+        .public_key
+        .ok_or_else(|| anyhow!("The session key has no public key."))?;
+
+    let expiration = SystemTime::now()
+        .checked_add(expire_after)
+        .ok_or_else(|| anyhow!("Time wrapped around."))?
+        .duration_since(UNIX_EPOCH)?
+        .as_nanos() as u64;
+
+    let request_id = ic_agent::to_request_id(&Delegation {
+        pubkey: &session_public_key,
+        expiration,
+        targets: targets.as_ref(),
+    })?;
+    let message = [DELEGATION_DOMAIN_SEPARATOR, request_id.as_slice()].concat();
+    let signature = identity.sign(&message).map_err(|err| anyhow!(err))?;
+
+    let delegation = SessionDelegation {
+        public_key: signature
+            .public_key
+            .ok_or_else(|| anyhow!("Identity '{}' cannot delegate.", name))?,
+        session_public_key,
+        expiration,
+        targets,
+        signature: signature
+            .signature
+            .ok_or_else(|| anyhow!("Identity '{}' cannot delegate.", name))?,
+    };
+
+    let delegation_path = dir.join(DELEGATION_JSON);
+    std::fs::write(&delegation_path, serde_json::to_string_pretty(&delegation)?).context(
+        format!(
+            "Cannot write delegation file at '{}'.",
+            delegation_path.display()
+        ),
+    )?;
+    Ok(delegation)
+}
+
+/// Load the cached delegation of an identity, if there is one that did not expire yet.
+pub fn load(dir: &Path) -> DfxResult<Option<(SessionDelegation, SessionIdentity)>> {
+    let delegation_path = dir.join(DELEGATION_JSON);
+    let session_pem_path = dir.join(SESSION_PEM);
+    if !delegation_path.exists() || !session_pem_path.exists() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(&delegation_path).context(format!(
+        "Cannot read delegation file at '{}'.",
+        delegation_path.display()
+    ))?;
+    let delegation: SessionDelegation = serde_json::from_str(&content)?;
+    if delegation.is_expired() {
+        return Ok(None);
+    }
+
+    let session = BasicIdentity::from_pem_file(&session_pem_path)?;
+    let identity = SessionIdentity {
+        principal: delegation.principal(),
+        session,
+    };
+    Ok(Some((delegation, identity)))
+}
+
+/// Delete the session key and delegation of an identity.
+/// Returns whether there was anything to delete.
+pub fn revoke(dir: &Path) -> DfxResult<bool> {
+    let mut revoked = false;
+    for file in &[SESSION_PEM, DELEGATION_JSON] {
+        let path = dir.join(file);
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .context(format!("Cannot remove file at '{}'.", path.display()))?;
+            revoked = true;
+        }
+    }
+    Ok(revoked)
+}
+
+/// Adds the delegation to every envelope signed with the session key.
+///
+/// The agent only knows about the session key, so envelopes leave it with the session public
+/// key as `sender_pubkey`. The replica authenticates the sender by the delegating key instead,
+/// and checks the session key's signature through the delegation.
+pub(crate) struct DelegationReplicaV2Transport<T> {
+    inner: T,
+    delegation: SessionDelegation,
+}
+
+impl<T: ReplicaV2Transport> DelegationReplicaV2Transport<T> {
+    pub fn new(inner: T, delegation: SessionDelegation) -> Self {
+        Self { inner, delegation }
+    }
+
+    fn attach_delegation(&self, envelope: Vec<u8>) -> Result<Vec<u8>, AgentError> {
+        let mut envelope: Value = serde_cbor::from_slice(&envelope)
+            .map_err(|x| AgentError::MessageError(x.to_string()))?;
+        if let Value::Map(fields) = &mut envelope {
+            let sender_pubkey = Value::Text("sender_pubkey".to_string());
+            if fields.get(&sender_pubkey)
+                == Some(&Value::Bytes(self.delegation.session_public_key.clone()))
+            {
+                fields.insert(
+                    sender_pubkey,
+                    Value::Bytes(self.delegation.public_key.clone()),
+                );
+                fields.insert(
+                    Value::Text("sender_delegation".to_string()),
+                    self.delegation.to_cbor(),
+                );
+            }
+        }
+
+        let mut serialized = Vec::new();
+        let mut serializer = serde_cbor::Serializer::new(&mut serialized);
+        serializer
+            .self_describe()
+            .map_err(|x| AgentError::MessageError(x.to_string()))?;
+        envelope
+            .serialize(&mut serializer)
+            .map_err(|x| AgentError::MessageError(x.to_string()))?;
+        Ok(serialized)
+    }
+}
+
+impl<T: ReplicaV2Transport + Send + Sync> ReplicaV2Transport for DelegationReplicaV2Transport<T> {
+    fn read_state<'a>(
+        &'a self,
+        effective_canister_id: Principal,
+        envelope: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, AgentError>> + Send + 'a>> {
+        match self.attach_delegation(envelope) {
+            Ok(envelope) => self.inner.read_state(effective_canister_id, envelope),
+            Err(err) => Box::pin(async move { Err(err) }),
+        }
+    }
+
+    fn call<'a>(
+        &'a self,
+        effective_canister_id: Principal,
+        envelope: Vec<u8>,
+        request_id: RequestId,
+    ) -> Pin<Box<dyn Future<Output = Result<(), AgentError>> + Send + 'a>> {
+        match self.attach_delegation(envelope) {
+            Ok(envelope) => self.inner.call(effective_canister_id, envelope, request_id),
+            Err(err) => Box::pin(async move { Err(err) }),
+        }
+    }
+
+    fn query<'a>(
+        &'a self,
+        effective_canister_id: Principal,
+        envelope: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, AgentError>> + Send + 'a>> {
+        match self.attach_delegation(envelope) {
+            Ok(envelope) => self.inner.query(effective_canister_id, envelope),
+            Err(err) => Box::pin(async move { Err(err) }),
+        }
+    }
+
+    fn status<'a>(
+        &'a self,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, AgentError>> + Send + 'a>> {
+        self.inner.status()
+    }
+}
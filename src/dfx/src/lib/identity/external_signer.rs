@@ -0,0 +1,180 @@
+//! An identity that delegates signing to an external program.
+//!
+//! The program is started for every operation. It receives exactly one JSON object on
+//! its standard input and must answer with exactly one JSON object on its standard
+//! output. Byte strings are hex encoded in both directions:
+//!
+//! ```text
+//! -> {"version":1,"operation":"public_key"}
+//! <- {"public_key":"302a300506032b6570032100..."}
+//!
+//! -> {"version":1,"operation":"sign","message":"0a69632d72657175657374..."}
+//! <- {"public_key":"302a300506032b6570032100...","signature":"7f3e..."}
+//! ```
+//!
+//! The public key must be DER encoded, as expected by the Internet Computer. A program
+//! that cannot fulfill a request answers with `{"error":"<message>"}` instead. Anything
+//! it writes to its standard error is shown to the user.
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_manager::ExternalIdentityConfiguration;
+
+use anyhow::{anyhow, bail, Context};
+use ic_agent::Signature;
+use ic_types::Principal;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const EXTERNAL_SIGNER_PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+enum SignerOperation {
+    PublicKey,
+    Sign { message: String },
+}
+
+#[derive(Serialize)]
+struct SignerRequest {
+    version: u32,
+
+    #[serde(flatten)]
+    operation: SignerOperation,
+}
+
+#[derive(Deserialize)]
+struct SignerResponse {
+    public_key: Option<String>,
+    signature: Option<String>,
+    error: Option<String>,
+}
+
+pub struct ExternalSignerIdentity {
+    configuration: ExternalIdentityConfiguration,
+
+    /// The DER encoded public key, as reported by the signer when the identity was loaded.
+    public_key: Vec<u8>,
+
+    principal: Principal,
+}
+
+impl ExternalSignerIdentity {
+    pub fn new(configuration: ExternalIdentityConfiguration) -> DfxResult<Self> {
+        let response = invoke(&configuration, SignerOperation::PublicKey)?;
+        let public_key = decode_field(&configuration, "public_key", response.public_key)?;
+        let principal = Principal::self_authenticating(&public_key);
+
+        Ok(Self {
+            configuration,
+            public_key,
+            principal,
+        })
+    }
+
+    fn sign_message(&self, blob: &[u8]) -> DfxResult<Signature> {
+        let response = invoke(
+            &self.configuration,
+            SignerOperation::Sign {
+                message: hex::encode(blob),
+            },
+        )?;
+        if let Some(public_key) = response.public_key.as_deref() {
+            if hex::decode(public_key).ok().as_deref() != Some(self.public_key.as_slice()) {
+                bail!(
+                    "External signer '{}' signed with a different public key than it reported.",
+                    self.configuration.command
+                );
+            }
+        }
+        let signature = decode_field(&self.configuration, "signature", response.signature)?;
+
+        Ok(Signature {
+            public_key: Some(self.public_key.clone()),
+            signature: Some(signature),
+        })
+    }
+}
+
+impl ic_agent::Identity for ExternalSignerIdentity {
+    fn sender(&self) -> Result<Principal, String> {
+        Ok(self.principal)
+    }
+
+    fn sign(&self, blob: &[u8]) -> Result<Signature, String> {
+        self.sign_message(blob).map_err(|err| format!("{:#}", err))
+    }
+}
+
+fn invoke(
+    configuration: &ExternalIdentityConfiguration,
+    operation: SignerOperation,
+) -> DfxResult<SignerResponse> {
+    let request = serde_json::to_vec(&SignerRequest {
+        version: EXTERNAL_SIGNER_PROTOCOL_VERSION,
+        operation,
+    })?;
+
+    let mut child = Command::new(&configuration.command)
+        .args(&configuration.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .context(format!(
+            "Cannot start external signer '{}'.",
+            configuration.command
+        ))?;
+
+    {
+        // Dropping stdin at the end of this block closes the pipe, so the signer sees EOF.
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("Cannot write to external signer stdin."))?;
+        stdin.write_all(&request)?;
+        stdin.write_all(b"\n")?;
+    }
+
+    let output = child.wait_with_output().context(format!(
+        "Failed while waiting for external signer '{}'.",
+        configuration.command
+    ))?;
+    if !output.status.success() {
+        bail!(
+            "External signer '{}' failed with {}.",
+            configuration.command,
+            output.status
+        );
+    }
+
+    let response: SignerResponse = serde_json::from_slice(&output.stdout).context(format!(
+        "Cannot parse the response of external signer '{}'.",
+        configuration.command
+    ))?;
+    if let Some(error) = response.error {
+        bail!(
+            "External signer '{}' returned an error: {}",
+            configuration.command,
+            error
+        );
+    }
+    Ok(response)
+}
+
+fn decode_field(
+    configuration: &ExternalIdentityConfiguration,
+    name: &str,
+    value: Option<String>,
+) -> DfxResult<Vec<u8>> {
+    let value = value.ok_or_else(|| {
+        anyhow!(
+            "External signer '{}' did not return a {}.",
+            configuration.command,
+            name
+        )
+    })?;
+    hex::decode(&value).context(format!(
+        "External signer '{}' returned a {} that is not valid hex.",
+        configuration.command, name
+    ))
+}
@@ -0,0 +1,219 @@
+//! Token and key discovery for hardware identities, through PKCS#11.
+//!
+//! Slots are numbered the way `ic-identity-hsm` numbers them: by their position in the list
+//! of slots that have a token present. That index is what `HardwareIdentityConfiguration.slot`
+//! refers to.
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_manager::get_dfx_hsm_pin;
+
+use anyhow::{anyhow, bail, Context};
+use pkcs11::types::{
+    CKA_CLASS, CKA_EC_PARAMS, CKA_ID, CKA_KEY_TYPE, CKA_LABEL, CKA_PRIVATE, CKA_SENSITIVE,
+    CKA_SIGN, CKA_TOKEN, CKA_VERIFY, CKF_RW_SESSION, CKF_SERIAL_SESSION, CKK_EC,
+    CKM_EC_KEY_PAIR_GEN, CKO_PUBLIC_KEY, CKU_USER, CK_ATTRIBUTE, CK_ATTRIBUTE_TYPE, CK_MECHANISM,
+    CK_OBJECT_HANDLE, CK_SESSION_HANDLE, CK_SLOT_ID, CK_TRUE, CK_ULONG,
+};
+use pkcs11::Ctx;
+use serde::Serialize;
+use std::path::Path;
+
+/// DER encoding of the prime256v1 (secp256r1) curve OID, the curve HSM identities sign with.
+const EC_PARAMS_PRIME256V1: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+
+/// How many objects to fetch from the token at a time while searching.
+const FIND_OBJECTS_BATCH: CK_ULONG = 16;
+
+#[derive(Serialize)]
+pub struct SlotDescription {
+    /// The index to use as `slot` in the identity configuration.
+    pub index: usize,
+    pub slot_id: CK_SLOT_ID,
+    pub token_label: String,
+    pub manufacturer: String,
+    pub model: String,
+    pub serial_number: String,
+}
+
+#[derive(Serialize)]
+pub struct KeyDescription {
+    /// The key id, as hex digits.
+    pub key_id: String,
+    pub label: String,
+}
+
+fn open_context(pkcs11_lib_path: &str) -> DfxResult<Ctx> {
+    if !Path::new(pkcs11_lib_path).exists() {
+        bail!("Cannot find PKCS#11 library at '{}'.", pkcs11_lib_path);
+    }
+    Ctx::new_and_initialize(pkcs11_lib_path).context(format!(
+        "Cannot initialize PKCS#11 library at '{}'.",
+        pkcs11_lib_path
+    ))
+}
+
+fn get_slot_id(ctx: &Ctx, slot: usize) -> DfxResult<CK_SLOT_ID> {
+    let slots = ctx.get_slot_list(true)?;
+    slots.get(slot).copied().ok_or_else(|| {
+        anyhow!(
+            "There is no token in slot {}. Found {} slot(s) with a token.",
+            slot,
+            slots.len()
+        )
+    })
+}
+
+/// Open a session on the token in the given slot, logging in with `DFX_HSM_PIN` if it is set,
+/// or unconditionally when `require_login` is set.
+fn open_session(
+    ctx: &Ctx,
+    slot_id: CK_SLOT_ID,
+    require_login: bool,
+) -> DfxResult<CK_SESSION_HANDLE> {
+    let session = ctx.open_session(slot_id, CKF_SERIAL_SESSION | CKF_RW_SESSION, None, None)?;
+    match get_dfx_hsm_pin() {
+        Ok(pin) => ctx.login(session, CKU_USER, Some(&pin))?,
+        Err(err) if require_login => bail!(err),
+        Err(_) => {}
+    }
+    Ok(session)
+}
+
+fn blank_padded(field: &[u8]) -> String {
+    String::from_utf8_lossy(field).trim_end().to_string()
+}
+
+/// List the slots that have a token present.
+pub fn list_slots(pkcs11_lib_path: &str) -> DfxResult<Vec<SlotDescription>> {
+    let ctx = open_context(pkcs11_lib_path)?;
+    ctx.get_slot_list(true)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, slot_id)| {
+            let token = ctx.get_token_info(slot_id)?;
+            Ok(SlotDescription {
+                index,
+                slot_id,
+                token_label: blank_padded(&token.label),
+                manufacturer: blank_padded(&token.manufacturerID),
+                model: blank_padded(&token.model),
+                serial_number: blank_padded(&token.serialNumber),
+            })
+        })
+        .collect()
+}
+
+fn get_attribute(
+    ctx: &Ctx,
+    session: CK_SESSION_HANDLE,
+    object: CK_OBJECT_HANDLE,
+    attribute: CK_ATTRIBUTE_TYPE,
+) -> DfxResult<Vec<u8>> {
+    // The first call reports the length of the value, the second one fills it in.
+    let mut template = vec![CK_ATTRIBUTE::new(attribute)];
+    let (_, template) = ctx.get_attribute_value(session, object, &mut template)?;
+    let mut value = vec![0_u8; template[0].ulValueLen as usize];
+    let mut template = vec![CK_ATTRIBUTE::new(attribute).with_bytes(value.as_mut_slice())];
+    ctx.get_attribute_value(session, object, &mut template)?;
+    Ok(value)
+}
+
+fn find_objects(
+    ctx: &Ctx,
+    session: CK_SESSION_HANDLE,
+    template: &[CK_ATTRIBUTE],
+) -> DfxResult<Vec<CK_OBJECT_HANDLE>> {
+    ctx.find_objects_init(session, &template.to_vec())?;
+    let mut objects = Vec::new();
+    loop {
+        let batch = ctx.find_objects(session, FIND_OBJECTS_BATCH)?;
+        if batch.is_empty() {
+            break;
+        }
+        objects.extend(batch);
+    }
+    ctx.find_objects_final(session)?;
+    Ok(objects)
+}
+
+/// List the EC public keys on the token in the given slot.
+pub fn list_keys(pkcs11_lib_path: &str, slot: usize) -> DfxResult<Vec<KeyDescription>> {
+    let ctx = open_context(pkcs11_lib_path)?;
+    let session = open_session(&ctx, get_slot_id(&ctx, slot)?, false)?;
+
+    let template = [
+        CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&CKO_PUBLIC_KEY),
+        CK_ATTRIBUTE::new(CKA_KEY_TYPE).with_ck_ulong(&CKK_EC),
+    ];
+    let keys = find_objects(&ctx, session, &template)?
+        .into_iter()
+        .map(|object| {
+            Ok(KeyDescription {
+                key_id: hex::encode(get_attribute(&ctx, session, object, CKA_ID)?),
+                label: String::from_utf8_lossy(&get_attribute(&ctx, session, object, CKA_LABEL)?)
+                    .to_string(),
+            })
+        })
+        .collect::<DfxResult<Vec<_>>>()?;
+
+    ctx.close_session(session)?;
+    Ok(keys)
+}
+
+/// Generate a prime256v1 key pair on the token in the given slot.
+pub fn generate_key_pair(
+    pkcs11_lib_path: &str,
+    slot: usize,
+    key_id: &str,
+    label: &str,
+) -> DfxResult {
+    let ctx = open_context(pkcs11_lib_path)?;
+    let session = open_session(&ctx, get_slot_id(&ctx, slot)?, true)?;
+
+    let id = hex::decode(key_id)?;
+    let label = label.to_string();
+    let existing = find_objects(
+        &ctx,
+        session,
+        &[
+            CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&CKO_PUBLIC_KEY),
+            CK_ATTRIBUTE::new(CKA_ID).with_bytes(&id),
+        ],
+    )?;
+    if !existing.is_empty() {
+        bail!(
+            "There already is a key with id {} in slot {}.",
+            key_id,
+            slot
+        );
+    }
+
+    let mechanism = CK_MECHANISM {
+        mechanism: CKM_EC_KEY_PAIR_GEN,
+        pParameter: std::ptr::null_mut(),
+        ulParameterLen: 0,
+    };
+    let public_key_template = vec![
+        CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE),
+        CK_ATTRIBUTE::new(CKA_VERIFY).with_bool(&CK_TRUE),
+        CK_ATTRIBUTE::new(CKA_EC_PARAMS).with_bytes(EC_PARAMS_PRIME256V1),
+        CK_ATTRIBUTE::new(CKA_ID).with_bytes(&id),
+        CK_ATTRIBUTE::new(CKA_LABEL).with_string(&label),
+    ];
+    let private_key_template = vec![
+        CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE),
+        CK_ATTRIBUTE::new(CKA_PRIVATE).with_bool(&CK_TRUE),
+        CK_ATTRIBUTE::new(CKA_SENSITIVE).with_bool(&CK_TRUE),
+        CK_ATTRIBUTE::new(CKA_SIGN).with_bool(&CK_TRUE),
+        CK_ATTRIBUTE::new(CKA_ID).with_bytes(&id),
+        CK_ATTRIBUTE::new(CKA_LABEL).with_string(&label),
+    ];
+    ctx.generate_key_pair(
+        session,
+        &mechanism,
+        &public_key_template,
+        &private_key_template,
+    )?;
+
+    ctx.close_session(session)?;
+    Ok(())
+}
@@ -2,8 +2,9 @@ use crate::lib::config::get_config_dfx_dir_path;
 use crate::lib::environment::Environment;
 use crate::lib::error::{DfxError, DfxResult, IdentityError};
 use crate::lib::identity::{
-    Identity as DfxIdentity, ANONYMOUS_IDENTITY_NAME, IDENTITY_JSON, IDENTITY_PEM,
+    delegation, Identity as DfxIdentity, ANONYMOUS_IDENTITY_NAME, IDENTITY_JSON, IDENTITY_PEM,
 };
+use crate::lib::network::network_descriptor::NetworkDescriptor;
 
 use anyhow::{anyhow, bail, Context};
 use ic_agent::identity::BasicIdentity;
@@ -33,6 +34,9 @@ fn default_identity() -> String {
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct IdentityConfiguration {
     pub hsm: Option<HardwareIdentityConfiguration>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external: Option<ExternalIdentityConfiguration>,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -40,14 +44,31 @@ pub struct HardwareIdentityConfiguration {
     /// The file path to the opensc-pkcs11 library e.g. "/usr/local/lib/opensc-pkcs11.so"
     pub pkcs11_lib_path: String,
 
+    /// The index of the slot holding the token, among the slots that have a token present.
+    #[serde(default)]
+    pub slot: usize,
+
     /// A sequence of pairs of hex digits
     pub key_id: String,
 }
 
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExternalIdentityConfiguration {
+    /// The program that performs the signing, e.g. "/usr/local/bin/audit-signer"
+    pub command: String,
+
+    /// Arguments passed to the program on every invocation.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
 pub enum IdentityCreationParameters {
     Pem(),
     PemFile(PathBuf),
     Hardware(HardwareIdentityConfiguration),
+    /// Generate a key pair on the token first.
+    NewHardwareKey(HardwareIdentityConfiguration),
+    External(ExternalIdentityConfiguration),
 }
 
 #[derive(Clone, Debug)]
@@ -91,10 +112,65 @@ impl IdentityManager {
         Ok(mgr)
     }
 
+    /// Create an IdentityManager that honors the identity and principal pinned for the network
+    /// in dfx.json.
+    pub fn for_network(env: &dyn Environment, network: &NetworkDescriptor) -> DfxResult<Self> {
+        let mut mgr = IdentityManager::new(env)?;
+        if env.get_identity_override().is_none() {
+            if let Some(identity) = &network.pinning.identity {
+                mgr.require_identity_exists(identity)?;
+                mgr.selected_identity = identity.clone();
+            }
+        }
+        Ok(mgr)
+    }
+
+    /// Refuse to use an identity whose principal is not the one pinned for the network,
+    /// unless the mismatch was explicitly allowed.
+    pub fn verify_pinned_principal(
+        &self,
+        env: &dyn Environment,
+        network: &NetworkDescriptor,
+    ) -> DfxResult {
+        let (expected, actual) =
+            match (&network.pinning.principal, self.selected_identity_principal) {
+                (Some(expected), Some(actual)) => (expected, actual),
+                _ => return Ok(()),
+            };
+        let expected_principal = Principal::from_text(expected).context(format!(
+            "Invalid principal '{}' pinned for the '{}' network in dfx.json.",
+            expected, network.name
+        ))?;
+        if expected_principal == actual {
+            return Ok(());
+        }
+
+        let err = IdentityError::PrincipalMismatch(
+            self.selected_identity.clone(),
+            actual.to_text(),
+            expected.clone(),
+            network.name.clone(),
+        );
+        if env.get_identity_mismatch_allowed() {
+            slog::warn!(env.get_logger(), "{}", err);
+            Ok(())
+        } else {
+            Err(DfxError::new(err))
+        }
+    }
+
     pub fn get_selected_identity_principal(&self) -> Option<Principal> {
         self.selected_identity_principal
     }
 
+    /// Select the identity for this manager only, without changing the default identity.
+    pub fn select_identity(&mut self, name: &str) -> DfxResult {
+        self.require_identity_exists(name)?;
+        self.selected_identity = name.to_string();
+        self.selected_identity_principal = None;
+        Ok(())
+    }
+
     /// Create an Identity instance for use with an Agent
     pub fn instantiate_selected_identity(&mut self) -> DfxResult<Box<DfxIdentity>> {
         let name = self.selected_identity.clone();
@@ -173,6 +249,7 @@ impl IdentityManager {
         remove_identity_file(&self.get_identity_pem_path(name))?;
 
         let dir = self.get_identity_dir_path(name);
+        delegation::revoke(&dir)?;
         std::fs::remove_dir(&dir).context(format!(
             "Cannot remove identity directory at '{}'.",
             dir.display()
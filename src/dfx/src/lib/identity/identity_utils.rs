@@ -1,10 +1,12 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_manager::IdentityManager;
 use crate::lib::identity::Identity;
 
+use anyhow::anyhow;
 use ic_types::principal::Principal;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum CallSender {
     SelectedId,
     SelectedIdWallet(Principal),
@@ -40,3 +42,39 @@ pub async fn call_sender(
     };
     Ok(sender)
 }
+
+/// The principals acting for the user in a call: the selected identity, and the wallet if the
+/// call goes through one.
+pub fn caller_principals(env: &dyn Environment, call_sender: &CallSender) -> Vec<Principal> {
+    let mut callers = vec![];
+    if let Some(principal) = env.get_selected_identity_principal() {
+        callers.push(principal);
+    }
+    if let CallSender::SelectedIdWallet(wallet_id) | CallSender::Wallet(wallet_id) = call_sender {
+        callers.push(*wallet_id);
+    }
+    callers
+}
+
+/// Resolve a controller given as a principal or as an identity name.
+pub fn controller_principal(env: &dyn Environment, controller: &str) -> DfxResult<Principal> {
+    use ic_agent::identity::Identity;
+
+    match Principal::from_text(controller) {
+        Ok(principal) => Ok(principal),
+        Err(_) => {
+            let current_id = env.get_selected_identity().unwrap();
+            if current_id == controller {
+                Ok(env.get_selected_identity_principal().unwrap())
+            } else {
+                let manager = match env.get_network_descriptor() {
+                    Some(network) => IdentityManager::for_network(env, network)?,
+                    None => IdentityManager::new(env)?,
+                };
+                manager
+                    .instantiate_identity_from_name(controller)
+                    .and_then(|identity| identity.sender().map_err(|err| anyhow!(err)))
+            }
+        }
+    }
+}
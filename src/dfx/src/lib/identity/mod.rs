@@ -6,18 +6,21 @@ use crate::config::dfinity::NetworkType;
 use crate::lib::config::get_config_dfx_dir_path;
 use crate::lib::environment::Environment;
 use crate::lib::error::{DfxError, DfxResult, IdentityError};
+use crate::lib::identity::identity_utils::CallSender;
 use crate::lib::network::network_descriptor::NetworkDescriptor;
+use crate::lib::operations::call::update_call;
+use crate::lib::operations::canister::{install_code, provisional_create_canister};
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::lib::waiter::waiter_with_timeout;
 
 use anyhow::{anyhow, bail, Context};
+use candid::{CandidType, Encode};
 use ic_agent::identity::{AnonymousIdentity, BasicIdentity, Secp256k1Identity};
 use ic_agent::Signature;
 use ic_identity_hsm::HardwareIdentity;
 use ic_types::Principal;
-use ic_utils::call::AsyncCall;
-use ic_utils::interfaces::management_canister::builders::InstallMode;
-use ic_utils::interfaces::{ManagementCanister, Wallet};
+use ic_utils::interfaces::management_canister::builders::{CanisterInstall, InstallMode};
+use ic_utils::interfaces::Wallet;
 use ic_utils::Canister;
 use serde::{Deserialize, Serialize};
 use slog::info;
@@ -25,20 +28,24 @@ use std::collections::BTreeMap;
 use std::io::Read;
 use std::path::PathBuf;
 
+pub mod delegation;
+pub mod external_signer;
+pub mod hsm;
 pub mod identity_manager;
 pub mod identity_utils;
 use crate::util::assets::wallet_wasm;
 use crate::util::expiry_duration;
+use delegation::SessionDelegation;
+use external_signer::ExternalSignerIdentity;
 pub use identity_manager::{
-    HardwareIdentityConfiguration, IdentityConfiguration, IdentityCreationParameters,
-    IdentityManager,
+    ExternalIdentityConfiguration, HardwareIdentityConfiguration, IdentityConfiguration,
+    IdentityCreationParameters, IdentityManager,
 };
 
 pub const ANONYMOUS_IDENTITY_NAME: &str = "anonymous";
 pub const IDENTITY_PEM: &str = "identity.pem";
 pub const IDENTITY_JSON: &str = "identity.json";
 const WALLET_CONFIG_FILENAME: &str = "wallets.json";
-const HSM_SLOT_INDEX: usize = 0;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct WalletNetworkMap {
@@ -60,6 +67,9 @@ pub struct Identity {
 
     /// The root directory for this identity.
     pub dir: PathBuf,
+
+    /// The delegation to the session key that signs in place of this identity, if any.
+    delegation: Option<SessionDelegation>,
 }
 
 impl Identity {
@@ -94,6 +104,31 @@ impl Identity {
                 create(identity_dir)?;
                 let identity_configuration = IdentityConfiguration {
                     hsm: Some(parameters),
+                    ..Default::default()
+                };
+                let json_file = manager.get_identity_json_path(name);
+                identity_manager::write_identity_configuration(&json_file, &identity_configuration)
+            }
+            IdentityCreationParameters::NewHardwareKey(parameters) => {
+                hsm::generate_key_pair(
+                    &parameters.pkcs11_lib_path,
+                    parameters.slot,
+                    &parameters.key_id,
+                    name,
+                )?;
+                Identity::create(
+                    manager,
+                    name,
+                    IdentityCreationParameters::Hardware(parameters),
+                )
+            }
+            IdentityCreationParameters::External(parameters) => {
+                // Make sure the signer works before recording it.
+                ExternalSignerIdentity::new(parameters.clone())?;
+                create(identity_dir)?;
+                let identity_configuration = IdentityConfiguration {
+                    external: Some(parameters),
+                    ..Default::default()
                 };
                 let json_file = manager.get_identity_json_path(name);
                 identity_manager::write_identity_configuration(&json_file, &identity_configuration)
@@ -106,6 +141,7 @@ impl Identity {
             name: ANONYMOUS_IDENTITY_NAME.to_string(),
             inner: Box::new(AnonymousIdentity {}),
             dir: PathBuf::new(),
+            delegation: None,
         }
     }
 
@@ -123,6 +159,7 @@ impl Identity {
             name: name.to_string(),
             inner,
             dir: manager.get_identity_dir_path(name),
+            delegation: None,
         })
     }
 
@@ -140,6 +177,7 @@ impl Identity {
             name: name.to_string(),
             inner,
             dir: manager.get_identity_dir_path(name),
+            delegation: None,
         })
     }
 
@@ -151,7 +189,7 @@ impl Identity {
         let inner = Box::new(
             HardwareIdentity::new(
                 hsm.pkcs11_lib_path,
-                HSM_SLOT_INDEX,
+                hsm.slot,
                 &hsm.key_id,
                 identity_manager::get_dfx_hsm_pin,
             )
@@ -161,18 +199,55 @@ impl Identity {
             name: name.to_string(),
             inner,
             dir: manager.get_identity_dir_path(name),
+            delegation: None,
         })
     }
 
+    fn load_external_identity(
+        manager: &IdentityManager,
+        name: &str,
+        external: ExternalIdentityConfiguration,
+    ) -> DfxResult<Self> {
+        let inner = Box::new(ExternalSignerIdentity::new(external)?);
+        Ok(Self {
+            name: name.to_string(),
+            inner,
+            dir: manager.get_identity_dir_path(name),
+            delegation: None,
+        })
+    }
+
+    /// Load an identity, using its cached session key if it has an unexpired delegation.
     pub fn load(manager: &IdentityManager, name: &str) -> DfxResult<Self> {
+        let dir = manager.get_identity_dir_path(name);
+        if let Some((delegation, session)) = delegation::load(&dir)? {
+            return Ok(Self {
+                name: name.to_string(),
+                inner: Box::new(session),
+                dir,
+                delegation: Some(delegation),
+            });
+        }
+        Identity::load_root(manager, name)
+    }
+
+    /// Load an identity with its own key, ignoring any session key.
+    fn load_root(manager: &IdentityManager, name: &str) -> DfxResult<Self> {
         let json_path = manager.get_identity_json_path(name);
         if json_path.exists() {
-            let hsm = identity_manager::read_identity_configuration(&json_path)?
-                .hsm
-                .ok_or_else(|| {
-                    anyhow!("No HardwareIdentityConfiguration for IdentityConfiguration.")
-                })?;
-            Identity::load_hardware_identity(manager, name, hsm)
+            match identity_manager::read_identity_configuration(&json_path)? {
+                IdentityConfiguration { hsm: Some(hsm), .. } => {
+                    Identity::load_hardware_identity(manager, name, hsm)
+                }
+                IdentityConfiguration {
+                    external: Some(external),
+                    ..
+                } => Identity::load_external_identity(manager, name, external),
+                _ => Err(anyhow!(
+                    "No hardware or external signer configuration in '{}'.",
+                    json_path.display()
+                )),
+            }
         } else {
             Identity::load_secp256k1_identity(manager, name)
                 .or_else(|_| Identity::load_basic_identity(manager, name))
@@ -185,6 +260,11 @@ impl Identity {
         &self.name
     }
 
+    /// Get the delegation this identity signs with, if it uses a session key.
+    pub fn session_delegation(&self) -> Option<&SessionDelegation> {
+        self.delegation.as_ref()
+    }
+
     fn get_wallet_config_file(
         env: &dyn Environment,
         network: &NetworkDescriptor,
@@ -322,6 +402,30 @@ impl Identity {
         Ok(())
     }
 
+    /// Store the Wasm module of the wallet in the wallet, which installs it in the canisters
+    /// that the wallet creates.
+    pub async fn store_wallet_wasm(
+        env: &dyn Environment,
+        wallet_id: Principal,
+        wasm: Vec<u8>,
+    ) -> DfxResult {
+        #[derive(CandidType, Deserialize)]
+        struct In {
+            #[serde(with = "serde_bytes")]
+            wasm_module: Vec<u8>,
+        }
+        update_call(
+            env,
+            wallet_id,
+            wallet_id,
+            "wallet_store_wallet_wasm",
+            Encode!(&In { wasm_module: wasm })?,
+            waiter_with_timeout(expiry_duration()),
+        )
+        .await?;
+        Ok(())
+    }
+
     pub async fn create_wallet(
         env: &dyn Environment,
         network: &NetworkDescriptor,
@@ -331,10 +435,6 @@ impl Identity {
         match Identity::wallet_canister_id(env, network, name) {
             Err(_) => {
                 fetch_root_key_if_needed(env).await?;
-                let mgr = ManagementCanister::create(
-                    env.get_agent()
-                        .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?,
-                );
                 info!(
                     env.get_logger(),
                     "Creating a wallet canister on the {} network.", network.name
@@ -344,26 +444,18 @@ impl Identity {
 
                 let canister_id = match some_canister_id {
                     Some(id) => id,
-                    None => {
-                        mgr.create_canister()
-                            .as_provisional_create_with_amount(None)
-                            .call_and_wait(waiter_with_timeout(expiry_duration()))
-                            .await?
-                            .0
-                    }
+                    None => provisional_create_canister(env, None, None, expiry_duration()).await?,
                 };
 
-                mgr.install_code(&canister_id, wasm.as_slice())
-                    .with_mode(InstallMode::Install)
-                    .call_and_wait(waiter_with_timeout(expiry_duration()))
-                    .await?;
-
-                let wallet = Identity::build_wallet_canister(canister_id, env)?;
+                let install = CanisterInstall {
+                    mode: InstallMode::Install,
+                    canister_id,
+                    wasm_module: wasm.clone(),
+                    arg: Encode!()?,
+                };
+                install_code(env, install, expiry_duration(), &CallSender::SelectedId).await?;
 
-                wallet
-                    .wallet_store_wallet_wasm(wasm)
-                    .call_and_wait(waiter_with_timeout(expiry_duration()))
-                    .await?;
+                Identity::store_wallet_wasm(env, canister_id, wasm).await?;
 
                 Identity::set_wallet_id(env, network, name, canister_id)?;
 
@@ -419,6 +511,22 @@ impl Identity {
         network: &NetworkDescriptor,
         name: &str,
     ) -> DfxResult<Principal> {
+        // A wallet pinned in dfx.json belongs to the pinned identity, or to any identity if
+        // none is pinned.
+        if let Some(wallet) = &network.pinning.wallet {
+            if network
+                .pinning
+                .identity
+                .as_deref()
+                .map_or(true, |pinned| pinned == name)
+            {
+                return Principal::from_text(wallet).context(format!(
+                    "Invalid wallet '{}' pinned for the '{}' network in dfx.json.",
+                    wallet, network.name
+                ));
+            }
+        }
+
         let wallet_path = Identity::get_wallet_config_file(env, network, name)?;
         if !wallet_path.exists() {
             return Err(anyhow!(
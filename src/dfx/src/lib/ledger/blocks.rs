@@ -0,0 +1,512 @@
+//! Reading the blocks of the ledger, from the ledger canister or from its archives.
+use crate::lib::error::DfxResult;
+use crate::lib::nns_types::account_identifier::AccountIdentifier;
+use crate::lib::nns_types::icpts::ICPTs;
+use crate::lib::nns_types::{BlockHeight, Memo};
+
+use anyhow::{anyhow, bail};
+use candid::types::reference::Func;
+use candid::{CandidType, Decode, Encode};
+use chrono::{TimeZone, Utc};
+use ic_agent::Agent;
+use ic_types::Principal;
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use std::fmt;
+
+const QUERY_BLOCKS_METHOD: &str = "query_blocks";
+
+/// How far the creation time of a transaction may be ahead of the time of its block.
+const PERMITTED_DRIFT_NANOS: u64 = 60_000_000_000;
+
+#[derive(CandidType)]
+struct GetBlocksArgs {
+    start: BlockHeight,
+    length: u64,
+}
+
+#[derive(Deserialize)]
+struct CandidTimeStamp {
+    timestamp_nanos: u64,
+}
+
+#[derive(Deserialize)]
+enum CandidOperation {
+    Mint {
+        to: ByteBuf,
+        amount: ICPTs,
+    },
+    Burn {
+        from: ByteBuf,
+        amount: ICPTs,
+    },
+    Transfer {
+        from: ByteBuf,
+        to: ByteBuf,
+        amount: ICPTs,
+        fee: ICPTs,
+    },
+    Approve {
+        from: ByteBuf,
+        spender: ByteBuf,
+        fee: ICPTs,
+    },
+}
+
+#[derive(Deserialize)]
+struct CandidTransaction {
+    memo: Memo,
+    operation: Option<CandidOperation>,
+    created_at_time: CandidTimeStamp,
+}
+
+#[derive(Deserialize)]
+struct CandidBlock {
+    transaction: CandidTransaction,
+    timestamp: CandidTimeStamp,
+}
+
+#[derive(Deserialize)]
+struct ArchivedBlocksRange {
+    start: BlockHeight,
+    length: u64,
+    callback: Func,
+}
+
+#[derive(Deserialize)]
+struct QueryBlocksResponse {
+    chain_length: u64,
+    blocks: Vec<CandidBlock>,
+    first_block_index: BlockHeight,
+    archived_blocks: Vec<ArchivedBlocksRange>,
+}
+
+#[derive(Deserialize)]
+struct BlockRange {
+    blocks: Vec<CandidBlock>,
+}
+
+#[derive(Deserialize)]
+enum GetBlocksError {
+    BadFirstBlockIndex {
+        requested_index: BlockHeight,
+        first_valid_index: BlockHeight,
+    },
+    Other {
+        error_code: u64,
+        error_message: String,
+    },
+}
+
+#[derive(Deserialize)]
+enum ArchiveResult {
+    Ok(BlockRange),
+    Err(GetBlocksError),
+}
+
+/// What a block of the ledger did.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum LedgerOperation {
+    Mint {
+        to: AccountIdentifier,
+        amount: ICPTs,
+    },
+    Burn {
+        from: AccountIdentifier,
+        amount: ICPTs,
+    },
+    Transfer {
+        from: AccountIdentifier,
+        to: AccountIdentifier,
+        amount: ICPTs,
+        fee: ICPTs,
+    },
+    Approve {
+        from: AccountIdentifier,
+        spender: AccountIdentifier,
+        fee: ICPTs,
+    },
+}
+
+impl LedgerOperation {
+    /// Whether the operation moves funds from or to an account.
+    pub fn involves(&self, account: &AccountIdentifier) -> bool {
+        match self {
+            LedgerOperation::Mint { to, .. } => to == account,
+            LedgerOperation::Burn { from, .. } => from == account,
+            LedgerOperation::Transfer { from, to, .. } => from == account || to == account,
+            LedgerOperation::Approve { from, spender, .. } => from == account || spender == account,
+        }
+    }
+}
+
+impl fmt::Display for LedgerOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerOperation::Mint { to, amount } => write!(f, "Mint {} to {}", amount, to),
+            LedgerOperation::Burn { from, amount } => write!(f, "Burn {} from {}", amount, from),
+            LedgerOperation::Transfer {
+                from,
+                to,
+                amount,
+                fee,
+            } => write!(
+                f,
+                "Transfer {} from {} to {} (fee {})",
+                amount, from, to, fee
+            ),
+            LedgerOperation::Approve { from, spender, fee } => write!(
+                f,
+                "Approve {} to spend from {} (fee {})",
+                spender, from, fee
+            ),
+        }
+    }
+}
+
+/// A block of the ledger, decoded.
+#[derive(Serialize)]
+pub struct LedgerBlock {
+    pub height: BlockHeight,
+
+    /// In nanoseconds since the UNIX epoch.
+    pub timestamp: u64,
+
+    pub memo: Memo,
+
+    /// The creation time the sender set on the transaction, in nanoseconds since the UNIX epoch.
+    pub created_at_time: u64,
+
+    pub operation: Option<LedgerOperation>,
+}
+
+impl fmt::Display for LedgerBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "BlockHeight: {}", self.height)?;
+        writeln!(
+            f,
+            "  Timestamp:  {}",
+            Utc.timestamp_nanos(self.timestamp as i64)
+        )?;
+        writeln!(
+            f,
+            "  Created at: {}",
+            Utc.timestamp_nanos(self.created_at_time as i64)
+        )?;
+        writeln!(f, "  Memo:       {}", self.memo.0)?;
+        match &self.operation {
+            Some(operation) => write!(f, "  Operation:  {}", operation),
+            None => write!(f, "  Operation:  none"),
+        }
+    }
+}
+
+fn account(bytes: &[u8]) -> DfxResult<AccountIdentifier> {
+    AccountIdentifier::from_slice(bytes).map_err(|err| anyhow!(err))
+}
+
+fn decode_block(height: BlockHeight, block: CandidBlock) -> DfxResult<LedgerBlock> {
+    let operation = match block.transaction.operation {
+        None => None,
+        Some(CandidOperation::Mint { to, amount }) => Some(LedgerOperation::Mint {
+            to: account(&to)?,
+            amount,
+        }),
+        Some(CandidOperation::Burn { from, amount }) => Some(LedgerOperation::Burn {
+            from: account(&from)?,
+            amount,
+        }),
+        Some(CandidOperation::Transfer {
+            from,
+            to,
+            amount,
+            fee,
+        }) => Some(LedgerOperation::Transfer {
+            from: account(&from)?,
+            to: account(&to)?,
+            amount,
+            fee,
+        }),
+        Some(CandidOperation::Approve { from, spender, fee }) => Some(LedgerOperation::Approve {
+            from: account(&from)?,
+            spender: account(&spender)?,
+            fee,
+        }),
+    };
+    Ok(LedgerBlock {
+        height,
+        timestamp: block.timestamp.timestamp_nanos,
+        memo: block.transaction.memo,
+        created_at_time: block.transaction.created_at_time.timestamp_nanos,
+        operation,
+    })
+}
+
+async fn query_blocks(
+    agent: &Agent,
+    ledger_canister_id: &Principal,
+    start: BlockHeight,
+    length: u64,
+) -> DfxResult<QueryBlocksResponse> {
+    let result = agent
+        .query(ledger_canister_id, QUERY_BLOCKS_METHOD)
+        .with_arg(Encode!(&GetBlocksArgs { start, length })?)
+        .call()
+        .await?;
+    Ok(Decode!(&result, QueryBlocksResponse)?)
+}
+
+/// The number of blocks of the ledger.
+pub async fn chain_length(agent: &Agent, ledger_canister_id: &Principal) -> DfxResult<u64> {
+    Ok(query_blocks(agent, ledger_canister_id, 0, 0)
+        .await?
+        .chain_length)
+}
+
+/// Fetch the blocks `start..start + length` that exist, oldest first.
+/// Blocks that were moved to an archive canister are fetched from there.
+pub async fn get_blocks(
+    agent: &Agent,
+    ledger_canister_id: &Principal,
+    start: BlockHeight,
+    length: u64,
+) -> DfxResult<Vec<LedgerBlock>> {
+    let response = query_blocks(agent, ledger_canister_id, start, length).await?;
+
+    let mut blocks = vec![];
+    for range in response.archived_blocks {
+        let result = agent
+            .query(&range.callback.principal, &range.callback.method)
+            .with_arg(Encode!(&GetBlocksArgs {
+                start: range.start,
+                length: range.length,
+            })?)
+            .call()
+            .await?;
+        match Decode!(&result, ArchiveResult)? {
+            ArchiveResult::Ok(range_blocks) => {
+                for (index, block) in range_blocks.blocks.into_iter().enumerate() {
+                    blocks.push(decode_block(range.start + index as u64, block)?);
+                }
+            }
+            ArchiveResult::Err(GetBlocksError::BadFirstBlockIndex {
+                requested_index,
+                first_valid_index,
+            }) => bail!(
+                "The archive {} cannot return block {}, its first block is {}.",
+                range.callback.principal,
+                requested_index,
+                first_valid_index
+            ),
+            ArchiveResult::Err(GetBlocksError::Other {
+                error_code,
+                error_message,
+            }) => bail!(
+                "The archive {} failed with error {}: {}",
+                range.callback.principal,
+                error_code,
+                error_message
+            ),
+        }
+    }
+    for (index, block) in response.blocks.into_iter().enumerate() {
+        blocks.push(decode_block(
+            response.first_block_index + index as u64,
+            block,
+        )?);
+    }
+    blocks.sort_by_key(|block| block.height);
+    Ok(blocks)
+}
+
+/// Search the blocks recorded since `created_at_time` for the transaction that was created at
+/// that time, with the given memo, and that transferred `amount` to `to`.
+/// Returns the height of its block if it is in the last `max_blocks` blocks.
+pub async fn find_transfer(
+    agent: &Agent,
+    ledger_canister_id: &Principal,
+    created_at_time: u64,
+    memo: Memo,
+    to: &AccountIdentifier,
+    amount: ICPTs,
+    max_blocks: u64,
+) -> DfxResult<Option<BlockHeight>> {
+    const BATCH_SIZE: u64 = 1000;
+    let mut end = chain_length(agent, ledger_canister_id).await?;
+    let lowest = end.saturating_sub(max_blocks);
+    while end > lowest {
+        let start = end.saturating_sub(BATCH_SIZE).max(lowest);
+        let blocks = get_blocks(agent, ledger_canister_id, start, end - start).await?;
+        for block in blocks.iter().rev() {
+            let is_transfer = match &block.operation {
+                Some(LedgerOperation::Transfer {
+                    to: block_to,
+                    amount: block_amount,
+                    ..
+                }) => block_to == to && *block_amount == amount,
+                _ => false,
+            };
+            if is_transfer && block.created_at_time == created_at_time && block.memo == memo {
+                return Ok(Some(block.height));
+            }
+        }
+        // The ledger accepts creation times that are slightly ahead of its clock, but not more.
+        if blocks.first().map_or(false, |block| {
+            block.timestamp.saturating_add(PERMITTED_DRIFT_NANOS) < created_at_time
+        }) {
+            break;
+        }
+        end = start;
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::IDLArgs;
+
+    fn account_of(id: u8) -> AccountIdentifier {
+        AccountIdentifier::new(Principal::from_slice(&[id]), None)
+    }
+
+    fn blob(account: &AccountIdentifier) -> String {
+        let bytes: Vec<String> = account
+            .to_vec()
+            .iter()
+            .map(|byte| format!("{} : nat8", byte))
+            .collect();
+        format!("vec {{ {} }}", bytes.join("; "))
+    }
+
+    fn block(operation: Option<CandidOperation>) -> CandidBlock {
+        CandidBlock {
+            transaction: CandidTransaction {
+                memo: Memo(7),
+                operation,
+                created_at_time: CandidTimeStamp {
+                    timestamp_nanos: 1_000,
+                },
+            },
+            timestamp: CandidTimeStamp {
+                timestamp_nanos: 2_000,
+            },
+        }
+    }
+
+    #[test]
+    fn decodes_candid_blocks() {
+        let (from, to) = (account_of(1), account_of(2));
+        let text = format!(
+            "(record {{ \
+               transaction = record {{ \
+                 memo = 7 : nat64; \
+                 operation = opt variant {{ Transfer = record {{ \
+                   from = {}; to = {}; \
+                   amount = record {{ e8s = 150000000 : nat64 }}; \
+                   fee = record {{ e8s = 10000 : nat64 }} \
+                 }} }}; \
+                 created_at_time = record {{ timestamp_nanos = 1000 : nat64 }} \
+               }}; \
+               timestamp = record {{ timestamp_nanos = 2000 : nat64 }} \
+             }})",
+            blob(&from),
+            blob(&to)
+        );
+        let bytes = text.parse::<IDLArgs>().unwrap().to_bytes().unwrap();
+        let block = decode_block(5, Decode!(&bytes, CandidBlock).unwrap()).unwrap();
+
+        assert_eq!(block.height, 5);
+        assert_eq!(block.memo, Memo(7));
+        assert_eq!(block.created_at_time, 1_000);
+        assert_eq!(block.timestamp, 2_000);
+        match block.operation {
+            Some(LedgerOperation::Transfer {
+                from: block_from,
+                to: block_to,
+                amount,
+                fee,
+            }) => {
+                assert_eq!(block_from, from);
+                assert_eq!(block_to, to);
+                assert_eq!(amount, ICPTs::from_e8s(150_000_000));
+                assert_eq!(fee, ICPTs::from_e8s(10_000));
+            }
+            _ => panic!("Expected a transfer."),
+        }
+    }
+
+    #[test]
+    fn decodes_every_operation() {
+        let (a, b) = (account_of(1), account_of(2));
+        let bytes = |account: &AccountIdentifier| ByteBuf::from(account.to_vec());
+        let amount = ICPTs::from_e8s(100);
+
+        let mint = decode_block(
+            0,
+            block(Some(CandidOperation::Mint {
+                to: bytes(&a),
+                amount,
+            })),
+        )
+        .unwrap()
+        .operation
+        .unwrap();
+        assert!(mint.involves(&a) && !mint.involves(&b));
+
+        let burn = decode_block(
+            0,
+            block(Some(CandidOperation::Burn {
+                from: bytes(&b),
+                amount,
+            })),
+        )
+        .unwrap()
+        .operation
+        .unwrap();
+        assert!(burn.involves(&b) && !burn.involves(&a));
+
+        let approve = decode_block(
+            0,
+            block(Some(CandidOperation::Approve {
+                from: bytes(&a),
+                spender: bytes(&b),
+                fee: amount,
+            })),
+        )
+        .unwrap()
+        .operation
+        .unwrap();
+        assert!(approve.involves(&a) && approve.involves(&b));
+        assert_eq!(
+            approve.to_string(),
+            format!("Approve {} to spend from {} (fee 0.00000100 ICP)", b, a)
+        );
+
+        assert!(decode_block(0, block(None)).unwrap().operation.is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_accounts() {
+        let result = decode_block(
+            0,
+            block(Some(CandidOperation::Mint {
+                to: ByteBuf::from(vec![0; 31]),
+                amount: ICPTs::from_e8s(1),
+            })),
+        );
+        assert!(result.is_err());
+
+        let mut to = account_of(1).to_vec();
+        to[0] ^= 1;
+        let result = decode_block(
+            0,
+            block(Some(CandidOperation::Mint {
+                to: ByteBuf::from(to),
+                amount: ICPTs::from_e8s(1),
+            })),
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//! The conversion rate of ICP to cycles, as set in the cycles minting canister.
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::nns_types::icpts::{ICPTs, ICP_SUBDIVIDABLE_BY, TRANSACTION_FEE};
+
+use anyhow::anyhow;
+use candid::{Decode, Encode};
+use chrono::{TimeZone, Utc};
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::fmt;
+
+const CONVERSION_RATE_METHOD: &str = "get_icp_xdr_conversion_rate";
+
+/// One XDR is worth one trillion cycles.
+const CYCLES_PER_XDR: u128 = 1_000_000_000_000;
+
+/// A permyriad is a ten-thousandth.
+const PERMYRIAD: u128 = 10_000;
+
+#[derive(Deserialize)]
+struct IcpXdrConversionRate {
+    timestamp_seconds: u64,
+    xdr_permyriad_per_icp: u64,
+}
+
+#[derive(Deserialize)]
+struct IcpXdrConversionRateCertifiedResponse {
+    data: IcpXdrConversionRate,
+}
+
+pub struct ConversionRate {
+    pub timestamp_seconds: u64,
+    pub xdr_permyriad_per_icp: u64,
+}
+
+impl ConversionRate {
+    /// The cycles minted from one e8 of ICP.
+    fn cycles_per_e8s(&self) -> u128 {
+        self.cycles_per_icp() / ICP_SUBDIVIDABLE_BY as u128
+    }
+
+    pub fn cycles_per_icp(&self) -> u128 {
+        self.xdr_permyriad_per_icp as u128 * CYCLES_PER_XDR / PERMYRIAD
+    }
+
+    /// The cycles minted when `amount` is sent to the cycles minting canister.
+    /// The cycles minting canister pays the transaction fee of burning the ICP out of it.
+    pub fn cycles_for(&self, amount: ICPTs) -> u128 {
+        amount.get_e8s().saturating_sub(TRANSACTION_FEE.get_e8s()) as u128 * self.cycles_per_e8s()
+    }
+
+    /// The ICP to send to the cycles minting canister to mint at least `cycles`.
+    pub fn icpts_for(&self, cycles: u128) -> DfxResult<ICPTs> {
+        let cycles_per_e8s = self.cycles_per_e8s();
+        if cycles_per_e8s == 0 {
+            return Err(anyhow!(
+                "The conversion rate of the cycles minting canister is 0."
+            ));
+        }
+        let e8s = (cycles + cycles_per_e8s - 1) / cycles_per_e8s;
+        let e8s = u64::try_from(e8s)
+            .ok()
+            .and_then(|e8s| e8s.checked_add(TRANSACTION_FEE.get_e8s()))
+            .ok_or_else(|| anyhow!("{} cycles cost more ICP than there are.", cycles))?;
+        Ok(ICPTs::from_e8s(e8s))
+    }
+}
+
+impl fmt::Display for ConversionRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "1 ICP = {} cycles ({}.{:04} XDR), as of {}",
+            self.cycles_per_icp(),
+            self.xdr_permyriad_per_icp / 10_000,
+            self.xdr_permyriad_per_icp % 10_000,
+            Utc.timestamp(self.timestamp_seconds as i64, 0)
+        )
+    }
+}
+
+/// Query the conversion rate of the cycles minting canister of the network.
+pub async fn get_conversion_rate(env: &dyn Environment) -> DfxResult<ConversionRate> {
+    let agent = env
+        .get_agent()
+        .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?;
+    let cycle_minter_id = env
+        .get_network_descriptor()
+        .expect("No network descriptor.")
+        .cycles_minting_canister_id()?;
+
+    let result = agent
+        .query(&cycle_minter_id, CONVERSION_RATE_METHOD)
+        .with_arg(Encode!()?)
+        .call()
+        .await?;
+    let rate = Decode!(&result, IcpXdrConversionRateCertifiedResponse)?.data;
+    Ok(ConversionRate {
+        timestamp_seconds: rate.timestamp_seconds,
+        xdr_permyriad_per_icp: rate.xdr_permyriad_per_icp,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icpts_for_cycles_include_the_burn_fee() {
+        // 1 ICP = 25 XDR = 25T cycles, so 1 e8 = 250_000 cycles.
+        let rate = ConversionRate {
+            timestamp_seconds: 0,
+            xdr_permyriad_per_icp: 250_000,
+        };
+        assert_eq!(rate.cycles_per_icp(), 25_000_000_000_000);
+
+        let amount = rate.icpts_for(1_000_000_000_000).unwrap();
+        assert_eq!(amount.get_e8s(), 4_000_000 + TRANSACTION_FEE.get_e8s());
+        assert_eq!(rate.cycles_for(amount), 1_000_000_000_000);
+
+        // Partial e8s are rounded up.
+        let amount = rate.icpts_for(1).unwrap();
+        assert_eq!(amount.get_e8s(), 1 + TRANSACTION_FEE.get_e8s());
+    }
+}
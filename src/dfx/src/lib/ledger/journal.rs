@@ -0,0 +1,234 @@
+//! A journal of the ledger operations of an identity that did not complete yet.
+//!
+//! Every operation is written to the journal before its transfer is sent, and again once the
+//! transfer is recorded at a block height. It is removed when it completes, that is after the
+//! transfer for plain transfers, or after notifying the cycles minting canister for top-ups and
+//! canister creations, or when the ledger rejects its transfer. Whatever is left in the journal
+//! can be finished with `dfx ledger resume`, or dropped with `dfx ledger pending --discard`.
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_manager::IdentityManager;
+use crate::lib::nns_types::account_identifier::{AccountIdentifier, Subaccount};
+use crate::lib::nns_types::icpts::ICPTs;
+use crate::lib::nns_types::{BlockHeight, Memo};
+
+use anyhow::{anyhow, Context};
+use ic_types::principal::Principal;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const LEDGER_JOURNAL_JSON: &str = "ledger_journal.json";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OperationKind {
+    Transfer,
+    TopUp,
+    CreateCanister,
+}
+
+impl fmt::Display for OperationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OperationKind::Transfer => write!(f, "transfer"),
+            OperationKind::TopUp => write!(f, "top-up"),
+            OperationKind::CreateCanister => write!(f, "create-canister"),
+        }
+    }
+}
+
+/// The notification of the cycles minting canister that follows a transfer to it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingNotification {
+    pub to_canister: Principal,
+
+    /// The subaccount the transfer was sent to, as hex.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_subaccount: Option<String>,
+
+    pub max_fee: ICPTs,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingOperation {
+    pub kind: OperationKind,
+
+    /// The name of the network the operation runs on.
+    pub network: String,
+
+    pub memo: Memo,
+    pub amount: ICPTs,
+    pub fee: ICPTs,
+
+    /// The subaccount the transfer is sent from, as hex.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_subaccount: Option<String>,
+
+    pub to: AccountIdentifier,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify: Option<PendingNotification>,
+
+    /// In nanoseconds since the UNIX epoch. The ledger deduplicates transfers by this time,
+    /// so it also identifies the operation.
+    pub created_at_time: u64,
+
+    /// Set once the transfer is recorded by the ledger.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_height: Option<BlockHeight>,
+}
+
+impl PendingOperation {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        kind: OperationKind,
+        network: &str,
+        memo: Memo,
+        amount: ICPTs,
+        fee: ICPTs,
+        from_subaccount: Option<Subaccount>,
+        to: AccountIdentifier,
+        notify: Option<PendingNotification>,
+    ) -> DfxResult<Self> {
+        Ok(PendingOperation {
+            kind,
+            network: network.to_string(),
+            memo,
+            amount,
+            fee,
+            from_subaccount: encode_subaccount(from_subaccount),
+            to,
+            notify,
+            created_at_time: SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+            block_height: None,
+        })
+    }
+
+    pub fn from_subaccount(&self) -> DfxResult<Option<Subaccount>> {
+        decode_subaccount(&self.from_subaccount)
+    }
+
+    pub fn state(&self) -> &'static str {
+        match (self.block_height, &self.notify) {
+            (None, _) => "not sent",
+            (Some(_), Some(_)) => "sent, not notified",
+            (Some(_), None) => "sent",
+        }
+    }
+}
+
+impl PendingNotification {
+    pub fn to_subaccount(&self) -> DfxResult<Option<Subaccount>> {
+        decode_subaccount(&self.to_subaccount)
+    }
+}
+
+pub fn encode_subaccount(subaccount: Option<Subaccount>) -> Option<String> {
+    subaccount.map(|subaccount| hex::encode(subaccount.0))
+}
+
+fn decode_subaccount(subaccount: &Option<String>) -> DfxResult<Option<Subaccount>> {
+    subaccount
+        .as_deref()
+        .map(|subaccount| {
+            let bytes = hex::decode(subaccount)?;
+            Ok(Subaccount::try_from(bytes.as_slice())?)
+        })
+        .transpose()
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct JournalFile {
+    operations: Vec<PendingOperation>,
+}
+
+pub struct LedgerJournal {
+    path: PathBuf,
+    operations: Vec<PendingOperation>,
+}
+
+impl LedgerJournal {
+    /// Load the journal of the selected identity, which is the one pinned for the network
+    /// unless `--identity` is given.
+    pub fn load(env: &dyn Environment) -> DfxResult<Self> {
+        let network = env
+            .get_network_descriptor()
+            .ok_or_else(|| anyhow!("No network descriptor."))?;
+        let identity_manager = IdentityManager::for_network(env, network)?;
+        let path = identity_manager
+            .get_identity_dir_path(identity_manager.get_selected_identity_name())
+            .join(LEDGER_JOURNAL_JSON);
+
+        let operations = if path.exists() {
+            let content = std::fs::read_to_string(&path).context(format!(
+                "Cannot read ledger journal at '{}'.",
+                path.display()
+            ))?;
+            serde_json::from_str::<JournalFile>(&content)
+                .context(format!(
+                    "Cannot parse ledger journal at '{}'.",
+                    path.display()
+                ))?
+                .operations
+        } else {
+            vec![]
+        };
+        Ok(LedgerJournal { path, operations })
+    }
+
+    /// The incomplete operations on a network, oldest first.
+    pub fn pending(&self, network: &str) -> Vec<PendingOperation> {
+        self.operations
+            .iter()
+            .filter(|operation| operation.network == network)
+            .cloned()
+            .collect()
+    }
+
+    /// Add an operation to the journal, or update it.
+    pub fn record(&mut self, operation: &PendingOperation) -> DfxResult {
+        match self
+            .operations
+            .iter_mut()
+            .find(|x| x.created_at_time == operation.created_at_time)
+        {
+            Some(existing) => *existing = operation.clone(),
+            None => self.operations.push(operation.clone()),
+        }
+        self.save()
+    }
+
+    /// Remove a completed operation from the journal.
+    pub fn complete(&mut self, operation: &PendingOperation) -> DfxResult {
+        self.operations
+            .retain(|x| x.created_at_time != operation.created_at_time);
+        self.save()
+    }
+
+    /// Remove an operation from the journal without finishing it.
+    /// Returns the operation, if there was one with that id.
+    pub fn discard(&mut self, id: u64) -> DfxResult<Option<PendingOperation>> {
+        let index = self.operations.iter().position(|x| x.created_at_time == id);
+        let operation = index.map(|index| self.operations.remove(index));
+        if operation.is_some() {
+            self.save()?;
+        }
+        Ok(operation)
+    }
+
+    fn save(&self) -> DfxResult {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(&JournalFile {
+            operations: self.operations.clone(),
+        })?;
+        std::fs::write(&self.path, content).context(format!(
+            "Cannot write ledger journal at '{}'.",
+            self.path.display()
+        ))
+    }
+}
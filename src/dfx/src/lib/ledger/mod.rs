@@ -0,0 +1,3 @@
+pub mod blocks;
+pub mod conversion;
+pub mod journal;
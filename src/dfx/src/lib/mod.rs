@@ -1,12 +1,16 @@
 pub mod builders;
 pub mod canister_info;
 pub mod config;
+pub mod confirmation;
 pub mod dist;
 pub mod environment;
 pub mod error;
+pub mod golden;
+pub mod http_trace;
 pub mod ic_attributes;
 pub mod identity;
 pub mod installers;
+pub mod ledger;
 pub mod locations;
 pub mod logger;
 pub mod manifest;
@@ -15,6 +19,7 @@ pub mod named_canister;
 pub mod network;
 pub mod nns_types;
 pub mod operations;
+pub mod output;
 pub mod package_arguments;
 pub mod progress_bar;
 pub mod provider;
@@ -1,2 +1,3 @@
 pub mod canister;
 pub mod canister_id_store;
+pub mod status_history;
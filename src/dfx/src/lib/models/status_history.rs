@@ -0,0 +1,144 @@
+//! Previous cycles balances of canisters, kept to estimate how fast they burn cycles.
+//!
+//! Every `dfx canister status` records a reading in `.dfx/<network>/status/<canister id>.json`.
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+
+use anyhow::{anyhow, Context};
+use ic_types::Principal;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The number of readings kept per canister.
+const MAX_READINGS: usize = 100;
+
+/// Readings closer together than this are too noisy to estimate a burn rate.
+const MIN_ESTIMATE_SECONDS: u64 = 60;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CyclesReading {
+    pub timestamp_seconds: u64,
+    pub cycles: u64,
+}
+
+pub struct StatusHistory {
+    path: PathBuf,
+    readings: Vec<CyclesReading>,
+}
+
+impl StatusHistory {
+    pub fn load(env: &dyn Environment, canister_id: &Principal) -> DfxResult<Self> {
+        let network = env
+            .get_network_descriptor()
+            .ok_or_else(|| anyhow!("No network descriptor."))?;
+        let path = env
+            .get_config_or_anyhow()?
+            .get_temp_path()
+            .join(&network.name)
+            .join("status")
+            .join(format!("{}.json", canister_id));
+
+        let readings = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Cannot read '{}'.", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Cannot parse '{}'.", path.display()))?
+        } else {
+            vec![]
+        };
+        Ok(StatusHistory { path, readings })
+    }
+
+    /// Record the current balance of the canister.
+    pub fn record(&mut self, cycles: u64) -> DfxResult {
+        self.readings.push(CyclesReading {
+            timestamp_seconds: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            cycles,
+        });
+        if self.readings.len() > MAX_READINGS {
+            self.readings.drain(..self.readings.len() - MAX_READINGS);
+        }
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&self.readings)?)
+            .with_context(|| format!("Cannot write '{}'.", self.path.display()))
+    }
+
+    /// The cycles burned per day, estimated from the readings since the last deposit.
+    pub fn burn_per_day(&self) -> Option<f64> {
+        let latest = self.readings.last()?;
+        // Walk back while the balance only went down; an increase means cycles were deposited.
+        let mut earliest = latest;
+        for reading in self.readings.iter().rev().skip(1) {
+            if reading.cycles < earliest.cycles {
+                break;
+            }
+            earliest = reading;
+        }
+
+        let seconds = latest
+            .timestamp_seconds
+            .checked_sub(earliest.timestamp_seconds)?;
+        if seconds < MIN_ESTIMATE_SECONDS {
+            return None;
+        }
+        let burned = (earliest.cycles - latest.cycles) as f64;
+        Some(burned * SECONDS_PER_DAY / seconds as f64)
+    }
+}
+
+/// The days until a canister burning `burn_per_day` reaches its freezing threshold, that is
+/// until it has only the cycles to stay idle for `freezing_threshold` seconds left.
+pub fn days_until_frozen(cycles: u64, burn_per_day: f64, freezing_threshold: u64) -> Option<f64> {
+    if burn_per_day <= 0.0 {
+        return None;
+    }
+    let days = cycles as f64 / burn_per_day - freezing_threshold as f64 / SECONDS_PER_DAY;
+    Some(days.max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(readings: &[(u64, u64)]) -> StatusHistory {
+        StatusHistory {
+            path: PathBuf::new(),
+            readings: readings
+                .iter()
+                .map(|(timestamp_seconds, cycles)| CyclesReading {
+                    timestamp_seconds: *timestamp_seconds,
+                    cycles: *cycles,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn burn_per_day_ignores_readings_before_a_deposit() {
+        assert_eq!(history(&[(0, 1000)]).burn_per_day(), None);
+        assert_eq!(history(&[(0, 1000), (30, 900)]).burn_per_day(), None);
+        assert_eq!(
+            history(&[(0, 2000), (43_200, 1500), (86_400, 1000)]).burn_per_day(),
+            Some(1000.0)
+        );
+        // The deposit between the first and the second reading hides the burn before it.
+        assert_eq!(
+            history(&[(0, 100), (86_400, 5000), (129_600, 4000)]).burn_per_day(),
+            Some(2000.0)
+        );
+    }
+
+    #[test]
+    fn days_until_frozen_subtracts_the_freezing_threshold() {
+        assert_eq!(days_until_frozen(1000, 0.0, 0), None);
+        assert_eq!(days_until_frozen(1000, 100.0, 0), Some(10.0));
+        assert_eq!(days_until_frozen(1000, 100.0, 2 * 86_400), Some(8.0));
+        assert_eq!(days_until_frozen(1000, 100.0, 20 * 86_400), Some(0.0));
+    }
+}
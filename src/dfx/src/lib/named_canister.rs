@@ -3,17 +3,18 @@
 //! Contains the Candid UI canister for now
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_utils::CallSender;
 use crate::lib::models::canister_id_store::CanisterIdStore;
 use crate::lib::network::network_descriptor::NetworkDescriptor;
+use crate::lib::operations::canister::{install_code, provisional_create_canister};
 use crate::lib::root_key::fetch_root_key_if_needed;
-use crate::lib::waiter::waiter_with_timeout;
 use crate::util;
 use crate::util::expiry_duration;
 
 use anyhow::anyhow;
+use candid::Encode;
 use ic_types::Principal;
-use ic_utils::interfaces::management_canister::builders::InstallMode;
-use ic_utils::interfaces::ManagementCanister;
+use ic_utils::interfaces::management_canister::builders::{CanisterInstall, InstallMode};
 use slog::info;
 use std::io::Read;
 
@@ -32,10 +33,6 @@ pub async fn install_ui_canister(
         ));
     }
     fetch_root_key_if_needed(env).await?;
-    let mgr = ManagementCanister::create(
-        env.get_agent()
-            .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?,
-    );
     info!(
         env.get_logger(),
         "Creating UI canister on the {} network.", network.name
@@ -50,18 +47,15 @@ pub async fn install_ui_canister(
     }
     let canister_id = match some_canister_id {
         Some(id) => id,
-        None => {
-            mgr.create_canister()
-                .as_provisional_create_with_amount(None)
-                .call_and_wait(waiter_with_timeout(expiry_duration()))
-                .await?
-                .0
-        }
+        None => provisional_create_canister(env, None, None, expiry_duration()).await?,
+    };
+    let install = CanisterInstall {
+        mode: InstallMode::Install,
+        canister_id,
+        wasm_module: wasm,
+        arg: Encode!()?,
     };
-    mgr.install_code(&canister_id, wasm.as_slice())
-        .with_mode(InstallMode::Install)
-        .call_and_wait(waiter_with_timeout(expiry_duration()))
-        .await?;
+    install_code(env, install, expiry_duration(), &CallSender::SelectedId).await?;
     id_store.add(UI_CANISTER, canister_id.to_text())?;
     info!(
         env.get_logger(),
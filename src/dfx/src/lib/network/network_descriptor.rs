@@ -1,4 +1,8 @@
-use crate::config::dfinity::NetworkType;
+use crate::config::dfinity::{ConfigNetworkLedger, ConfigNetworkPinning, NetworkType};
+use crate::lib::error::DfxResult;
+use crate::lib::nns_types::{CYCLE_MINTER_CANISTER_ID, LEDGER_CANISTER_ID};
+
+use ic_types::Principal;
 
 #[derive(Clone, Debug)]
 pub struct NetworkDescriptor {
@@ -6,4 +10,35 @@ pub struct NetworkDescriptor {
     pub providers: Vec<String>,
     pub r#type: NetworkType,
     pub is_ic: bool,
+    pub protected: bool,
+    pub pinning: ConfigNetworkPinning,
+    pub ledger: ConfigNetworkLedger,
+}
+
+impl NetworkDescriptor {
+    /// Whether destructive operations on this network must be confirmed by the user.
+    pub fn is_protected(&self) -> bool {
+        self.is_ic || self.protected
+    }
+
+    /// The ledger canister of this network, the one of the IC unless dfx.json says otherwise.
+    pub fn ledger_canister_id(&self) -> DfxResult<Principal> {
+        let id = self
+            .ledger
+            .ledger_canister_id
+            .as_deref()
+            .unwrap_or(LEDGER_CANISTER_ID);
+        Ok(Principal::from_text(id)?)
+    }
+
+    /// The cycles minting canister of this network, the one of the IC unless dfx.json says
+    /// otherwise.
+    pub fn cycles_minting_canister_id(&self) -> DfxResult<Principal> {
+        let id = self
+            .ledger
+            .cycles_minting_canister_id
+            .as_deref()
+            .unwrap_or(CYCLE_MINTER_CANISTER_ID);
+        Ok(Principal::from_text(id)?)
+    }
 }
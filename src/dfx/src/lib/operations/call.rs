@@ -0,0 +1,97 @@
+//! Update calls that expire at the time given by `ingress_expiry`.
+//!
+//! The builders of ic-utils set their own ingress expiry, so the calls made with them get a new
+//! request id every time, which `--replay-replica` cannot find in the recorded certificates.
+//! The calls of dfx to the management canister and to wallets are made with these functions.
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_utils::CallSender;
+use crate::util::ingress_expiry;
+
+use anyhow::anyhow;
+use candid::{CandidType, Decode, Deserialize, Encode};
+use garcon::Delay;
+use ic_types::Principal;
+use ic_utils::interfaces::wallet::CallResult;
+
+/// The argument of `wallet_call`.
+#[derive(CandidType, Deserialize)]
+pub struct CallIn {
+    pub canister: Principal,
+    pub method_name: String,
+    #[serde(with = "serde_bytes")]
+    pub args: Vec<u8>,
+    pub cycles: u64,
+}
+
+/// Call a method and wait for its reply.
+pub async fn update_call(
+    env: &dyn Environment,
+    canister_id: Principal,
+    effective_canister_id: Principal,
+    method: &str,
+    arg: Vec<u8>,
+    waiter: Delay,
+) -> DfxResult<Vec<u8>> {
+    let agent = env
+        .get_agent()
+        .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?;
+    let reply = agent
+        .update(&canister_id, method)
+        .with_effective_canister_id(effective_canister_id)
+        .with_arg(arg)
+        .expire_at(ingress_expiry(env))
+        .call_and_wait(waiter)
+        .await?;
+    Ok(reply)
+}
+
+/// Call a method through a wallet, which attaches the cycles to the call.
+/// Returns the reply of the called method.
+pub async fn wallet_call(
+    env: &dyn Environment,
+    wallet_id: Principal,
+    call: &CallIn,
+    waiter: Delay,
+) -> DfxResult<Vec<u8>> {
+    let reply = update_call(
+        env,
+        wallet_id,
+        wallet_id,
+        "wallet_call",
+        Encode!(call)?,
+        waiter,
+    )
+    .await?;
+    let result = Decode!(&reply, Result<CallResult, String>)?;
+    Ok(result.map_err(|err| anyhow!(err))?.r#return)
+}
+
+/// Call a method as the sender of the command: directly with the selected identity, or
+/// through a wallet, which attaches the cycles to the call.
+#[allow(clippy::too_many_arguments)]
+pub async fn call_as(
+    env: &dyn Environment,
+    call_sender: &CallSender,
+    canister_id: Principal,
+    effective_canister_id: Principal,
+    method: &str,
+    arg: Vec<u8>,
+    cycles: u64,
+    waiter: Delay,
+) -> DfxResult<Vec<u8>> {
+    match call_sender {
+        CallSender::SelectedId => {
+            update_call(env, canister_id, effective_canister_id, method, arg, waiter).await
+        }
+        CallSender::Wallet(wallet_id) | CallSender::SelectedIdWallet(wallet_id) => {
+            let call = CallIn {
+                canister: canister_id,
+                method_name: method.to_string(),
+                args: arg,
+                cycles,
+            };
+            wallet_call(env, *wallet_id, &call, waiter).await
+        }
+    }
+}
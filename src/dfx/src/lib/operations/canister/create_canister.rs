@@ -2,14 +2,17 @@ use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::ic_attributes::CanisterSettings;
 use crate::lib::identity::identity_utils::CallSender;
-use crate::lib::identity::Identity;
 use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::operations::call::update_call;
+use crate::lib::operations::canister::{management_settings, provisional_create_canister};
 use crate::lib::provider::get_network_context;
 use crate::lib::waiter::waiter_with_timeout;
 
 use anyhow::{anyhow, bail};
-use ic_agent::AgentError;
-use ic_utils::interfaces::ManagementCanister;
+use candid::{CandidType, Decode, Deserialize, Encode, Nat};
+use ic_types::Principal;
+use ic_utils::interfaces::management_canister::builders::CanisterSettings as ManagementCanisterSettings;
+use semver::Version;
 use slog::info;
 use std::format;
 use std::time::Duration;
@@ -55,57 +58,19 @@ pub async fn create_canister(
             Ok(())
         }
         None => {
-            let agent = env
-                .get_agent()
-                .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?;
-            let mgr = ManagementCanister::create(agent);
             let cid = match call_sender {
                 CallSender::SelectedId => {
                     // amount has been validated by cycle_amount_validator
                     let cycles = with_cycles.and_then(|amount| amount.parse::<u64>().ok());
-                    let mut builder = mgr
-                        .create_canister()
-                        .as_provisional_create_with_amount(cycles);
-                    if let Some(controllers) = settings.controllers {
-                        for controller in controllers {
-                            builder = builder.with_controller(controller);
-                        }
-                    };
-                    builder
-                        .with_optional_compute_allocation(settings.compute_allocation)
-                        .with_optional_memory_allocation(settings.memory_allocation)
-                        .with_optional_freezing_threshold(settings.freezing_threshold)
-                        .call_and_wait(waiter_with_timeout(timeout))
-                        .await?
-                        .0
+                    provisional_create_canister(env, cycles, Some(settings), timeout).await?
                 }
                 CallSender::Wallet(wallet_id) | CallSender::SelectedIdWallet(wallet_id) => {
-                    let wallet = Identity::build_wallet_canister(*wallet_id, env)?;
                     // amount has been validated by cycle_amount_validator
                     let cycles = with_cycles.map_or(
                         CANISTER_CREATE_FEE + CANISTER_INITIAL_CYCLE_BALANCE,
                         |amount| amount.parse::<u64>().unwrap(),
                     );
-                    match wallet
-                        .wallet_create_canister(
-                            cycles,
-                            settings.controllers,
-                            settings.compute_allocation,
-                            settings.memory_allocation,
-                            settings.freezing_threshold,
-                            waiter_with_timeout(timeout),
-                        )
-                        .await
-                    {
-                        Ok(result) => Ok(result.canister_id),
-                        Err(AgentError::WalletUpgradeRequired(s)) => {
-                            bail!(format!(
-                                "{}\nTo upgrade, run dfx wallet upgrade.",
-                                AgentError::WalletUpgradeRequired(s)
-                            ));
-                        }
-                        Err(other) => Err(other),
-                    }?
+                    wallet_create_canister(env, *wallet_id, cycles, settings, timeout).await?
                 }
             };
             let canister_id = cid.to_text();
@@ -122,3 +87,88 @@ pub async fn create_canister(
 
     Ok(())
 }
+
+/// Create a canister with cycles from a wallet. Wallets older than API version 0.2.0 take a
+/// single controller instead of a list.
+async fn wallet_create_canister(
+    env: &dyn Environment,
+    wallet_id: Principal,
+    cycles: u64,
+    settings: CanisterSettings,
+    timeout: Duration,
+) -> DfxResult<Principal> {
+    #[derive(CandidType)]
+    struct In {
+        cycles: u64,
+        settings: ManagementCanisterSettings,
+    }
+    #[derive(CandidType)]
+    struct SettingsV1 {
+        controller: Option<Principal>,
+        compute_allocation: Option<Nat>,
+        memory_allocation: Option<Nat>,
+        freezing_threshold: Option<Nat>,
+    }
+    #[derive(CandidType)]
+    struct InV1 {
+        cycles: u64,
+        settings: SettingsV1,
+    }
+    #[derive(CandidType, Deserialize)]
+    struct Out {
+        canister_id: Principal,
+    }
+
+    let settings = management_settings(settings);
+    let arg = if wallet_supports_multiple_controllers(env, wallet_id).await? {
+        Encode!(&In { cycles, settings })?
+    } else {
+        let controller = match settings.controllers.as_deref() {
+            None => None,
+            Some([controller]) => Some(*controller),
+            Some(_) => bail!(
+                "The wallet cannot create a canister with more than one controller.\nTo upgrade, run dfx wallet upgrade."
+            ),
+        };
+        Encode!(&InV1 {
+            cycles,
+            settings: SettingsV1 {
+                controller,
+                compute_allocation: settings.compute_allocation,
+                memory_allocation: settings.memory_allocation,
+                freezing_threshold: settings.freezing_threshold,
+            },
+        })?
+    };
+    let reply = update_call(
+        env,
+        wallet_id,
+        wallet_id,
+        "wallet_create_canister",
+        arg,
+        waiter_with_timeout(timeout),
+    )
+    .await?;
+    let result = Decode!(&reply, Result<Out, String>)?;
+    Ok(result.map_err(|err| anyhow!(err))?.canister_id)
+}
+
+async fn wallet_supports_multiple_controllers(
+    env: &dyn Environment,
+    wallet_id: Principal,
+) -> DfxResult<bool> {
+    let agent = env
+        .get_agent()
+        .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?;
+    // Wallets older than version 0.2.0 have no wallet_api_version method.
+    let version = match agent
+        .query(&wallet_id, "wallet_api_version")
+        .with_arg(Encode!()?)
+        .call()
+        .await
+    {
+        Ok(reply) => Decode!(&reply, String)?,
+        Err(_) => return Ok(false),
+    };
+    Ok(Version::parse(&version)? >= Version::new(0, 2, 0))
+}
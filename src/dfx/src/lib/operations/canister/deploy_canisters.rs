@@ -7,7 +7,7 @@ use crate::lib::ic_attributes::CanisterSettings;
 use crate::lib::identity::identity_utils::CallSender;
 use crate::lib::models::canister::CanisterPool;
 use crate::lib::models::canister_id_store::CanisterIdStore;
-use crate::lib::operations::canister::{create_canister, install_canister};
+use crate::lib::operations::canister::{create_canister, install_canister, reconcile_settings};
 use crate::util::{blob_from_arguments, get_candid_init_type};
 
 use anyhow::{anyhow, bail};
@@ -55,6 +55,8 @@ pub async fn deploy_canisters(
     )
     .await?;
 
+    reconcile_canister_settings(env, &canister_names, timeout, call_sender, &config).await?;
+
     build_canisters(env, &canister_names, &config)?;
 
     install_canisters(
@@ -149,6 +151,30 @@ async fn register_canisters(
     Ok(())
 }
 
+/// Settings in dfx.json may have changed since the canisters were created.
+async fn reconcile_canister_settings(
+    env: &dyn Environment,
+    canister_names: &[String],
+    timeout: Duration,
+    call_sender: &CallSender,
+    config: &Config,
+) -> DfxResult {
+    let canister_id_store = CanisterIdStore::for_env(env)?;
+    for canister_name in canister_names {
+        let canister_id = canister_id_store.get(canister_name)?;
+        reconcile_settings(
+            env,
+            config.get_config(),
+            canister_name,
+            canister_id,
+            timeout,
+            call_sender,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
 fn build_canisters(env: &dyn Environment, canister_names: &[String], config: &Config) -> DfxResult {
     info!(env.get_logger(), "Building canisters...");
     let build_mode_check = false;
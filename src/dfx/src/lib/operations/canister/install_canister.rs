@@ -2,17 +2,17 @@ use crate::lib::canister_info::CanisterInfo;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::identity::identity_utils::CallSender;
-use crate::lib::identity::Identity;
 use crate::lib::installers::assets::post_install_store_assets;
 use crate::lib::named_canister;
+use crate::lib::operations::call::{wallet_call, CallIn};
+use crate::lib::operations::canister::install_code;
+use crate::lib::output::print_progress;
 use crate::lib::waiter::waiter_with_timeout;
 
 use anyhow::Context;
+use candid::Encode;
 use ic_agent::Agent;
-use ic_utils::call::AsyncCall;
 use ic_utils::interfaces::management_canister::builders::{CanisterInstall, InstallMode};
-use ic_utils::interfaces::ManagementCanister;
-use ic_utils::Canister;
 use openssl::sha::Sha256;
 use slog::info;
 use std::time::Duration;
@@ -33,7 +33,6 @@ pub async fn install_canister(
         named_canister::install_ui_canister(env, &network, None).await?;
     }
 
-    let mgr = ManagementCanister::create(agent);
     let log = env.get_logger();
     let canister_id = canister_info.get_canister_id().context(format!(
         "Cannot find build output for canister '{}'. Did you forget to run `dfx build`?",
@@ -62,63 +61,42 @@ pub async fn install_canister(
     if mode == InstallMode::Upgrade
         && wasm_module_already_installed(&wasm_module, installed_module_hash.as_deref())
     {
-        println!(
-            "Module hash {} is already installed.",
-            hex::encode(installed_module_hash.unwrap())
+        print_progress(
+            env,
+            &format!(
+                "Module hash {} is already installed.",
+                hex::encode(installed_module_hash.unwrap())
+            ),
         );
     } else {
-        match call_sender {
-            CallSender::SelectedId => {
-                let install_builder = mgr
-                    .install_code(&canister_id, &wasm_module)
-                    .with_raw_arg(args.to_vec())
-                    .with_mode(mode);
-                install_builder
-                    .build()?
-                    .call_and_wait(waiter_with_timeout(timeout))
-                    .await?;
-            }
-            CallSender::Wallet(wallet_id) | CallSender::SelectedIdWallet(wallet_id) => {
-                let wallet = Identity::build_wallet_canister(*wallet_id, env)?;
-                let install_args = CanisterInstall {
-                    mode,
-                    canister_id,
-                    wasm_module,
-                    arg: args.to_vec(),
-                };
-                wallet
-                    .call_forward(
-                        mgr.update_("install_code").with_arg(install_args).build(),
-                        0,
-                    )?
-                    .call_and_wait(waiter_with_timeout(timeout))
-                    .await?;
-            }
-        }
+        let install = CanisterInstall {
+            mode,
+            canister_id,
+            wasm_module,
+            arg: args.to_vec(),
+        };
+        install_code(env, install, timeout, call_sender).await?;
     }
 
     if canister_info.get_type() == "assets" {
         match call_sender {
             CallSender::Wallet(wallet_id) | CallSender::SelectedIdWallet(wallet_id) => {
-                let wallet = Identity::build_wallet_canister(*wallet_id, env)?;
                 let identity_name = env.get_selected_identity().expect("No selected identity.");
                 info!(
                     log,
                     "Authorizing our identity ({}) to the asset canister...", identity_name
                 );
-                let canister = Canister::builder()
-                    .with_agent(agent)
-                    .with_canister_id(canister_id)
-                    .build()
-                    .unwrap();
                 let self_id = env
                     .get_selected_identity_principal()
                     .expect("Selected identity not instantiated.");
                 // Before storing assets, make sure the DFX principal is in there first.
-                wallet
-                    .call_forward(canister.update_("authorize").with_arg(self_id).build(), 0)?
-                    .call_and_wait(waiter_with_timeout(timeout))
-                    .await?;
+                let call = CallIn {
+                    canister: canister_id,
+                    method_name: "authorize".to_string(),
+                    args: Encode!(&self_id)?,
+                    cycles: 0,
+                };
+                wallet_call(env, *wallet_id, &call, waiter_with_timeout(timeout)).await?;
             }
             _ => (),
         };
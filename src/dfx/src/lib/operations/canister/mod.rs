@@ -1,28 +1,29 @@
 mod create_canister;
 mod deploy_canisters;
 mod install_canister;
+mod reconcile_settings;
+mod top_up;
 
 pub use create_canister::create_canister;
 pub use deploy_canisters::deploy_canisters;
 pub use install_canister::install_canister;
+pub use reconcile_settings::{reconcile_settings, settings_drift, SettingDrift};
+pub use top_up::{plan_top_ups, top_up_from_wallet, TopUp, TopUpResult};
 
 use crate::lib::canister_info::CanisterInfo;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::ic_attributes::CanisterSettings as DfxCanisterSettings;
 use crate::lib::identity::identity_utils::CallSender;
-use crate::lib::identity::Identity;
+use crate::lib::operations::call::call_as;
 use crate::lib::waiter::waiter_with_timeout;
 
-use anyhow::anyhow;
-use candid::utils::ArgumentDecoder;
-use candid::CandidType;
+use candid::utils::{decode_args, ArgumentDecoder};
+use candid::{CandidType, Deserialize, Encode};
 use ic_types::principal::Principal as CanisterId;
 use ic_types::Principal;
-use ic_utils::call::AsyncCall;
-use ic_utils::interfaces::management_canister::builders::CanisterSettings;
+use ic_utils::interfaces::management_canister::builders::{CanisterInstall, CanisterSettings};
 use ic_utils::interfaces::management_canister::{MgmtMethod, StatusCallResult};
-use ic_utils::interfaces::ManagementCanister;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -39,31 +40,18 @@ where
     A: CandidType + Sync + Send,
     O: for<'de> ArgumentDecoder<'de> + Sync + Send,
 {
-    let agent = env
-        .get_agent()
-        .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?;
-    let mgr = ManagementCanister::create(agent);
-
-    let out = match call_sender {
-        CallSender::SelectedId => {
-            mgr.update_(method)
-                .with_arg(arg)
-                .with_effective_canister_id(destination_canister)
-                .build()
-                .call_and_wait(waiter_with_timeout(timeout))
-                .await?
-        }
-        CallSender::Wallet(wallet_id) | CallSender::SelectedIdWallet(wallet_id) => {
-            let wallet = Identity::build_wallet_canister(*wallet_id, env)?;
-            let out: O = wallet
-                .call_forward(mgr.update_(method).with_arg(arg).build(), cycles)?
-                .call_and_wait(waiter_with_timeout(timeout))
-                .await?;
-            out
-        }
-    };
-
-    Ok(out)
+    let reply = call_as(
+        env,
+        call_sender,
+        Principal::management_canister(),
+        destination_canister,
+        method,
+        Encode!(&arg)?,
+        cycles,
+        waiter_with_timeout(timeout),
+    )
+    .await?;
+    Ok(decode_args(&reply)?)
 }
 
 pub async fn get_canister_status(
@@ -156,21 +144,7 @@ pub async fn update_settings(
         MgmtMethod::UpdateSettings.as_ref(),
         In {
             canister_id,
-            settings: CanisterSettings {
-                controllers: settings.controllers,
-                compute_allocation: settings
-                    .compute_allocation
-                    .map(u8::from)
-                    .map(candid::Nat::from),
-                memory_allocation: settings
-                    .memory_allocation
-                    .map(u64::from)
-                    .map(candid::Nat::from),
-                freezing_threshold: settings
-                    .freezing_threshold
-                    .map(u64::from)
-                    .map(candid::Nat::from),
-            },
+            settings: management_settings(settings),
         },
         timeout,
         call_sender,
@@ -253,6 +227,76 @@ pub async fn deposit_cycles(
     Ok(())
 }
 
+/// Create a canister with cycles out of thin air, which only local replicas allow.
+pub async fn provisional_create_canister(
+    env: &dyn Environment,
+    amount: Option<u64>,
+    settings: Option<DfxCanisterSettings>,
+    timeout: Duration,
+) -> DfxResult<Principal> {
+    #[derive(CandidType)]
+    struct In {
+        amount: Option<candid::Nat>,
+        settings: Option<CanisterSettings>,
+    }
+    #[derive(CandidType, Deserialize)]
+    struct Out {
+        canister_id: Principal,
+    }
+    let (out,): (Out,) = do_management_call(
+        env,
+        Principal::management_canister(),
+        MgmtMethod::ProvisionalCreateCanisterWithCycles.as_ref(),
+        In {
+            amount: amount.map(candid::Nat::from),
+            settings: settings.map(management_settings),
+        },
+        timeout,
+        &CallSender::SelectedId,
+        0,
+    )
+    .await?;
+    Ok(out.canister_id)
+}
+
+pub async fn install_code(
+    env: &dyn Environment,
+    install: CanisterInstall,
+    timeout: Duration,
+    call_sender: &CallSender,
+) -> DfxResult {
+    let _: () = do_management_call(
+        env,
+        install.canister_id,
+        MgmtMethod::InstallCode.as_ref(),
+        install,
+        timeout,
+        call_sender,
+        0,
+    )
+    .await?;
+    Ok(())
+}
+
+/// The settings of a canister, as the management canister takes them.
+pub fn management_settings(settings: DfxCanisterSettings) -> CanisterSettings {
+    CanisterSettings {
+        controllers: settings.controllers,
+        compute_allocation: settings
+            .compute_allocation
+            .map(u8::from)
+            .map(candid::Nat::from),
+        memory_allocation: settings
+            .memory_allocation
+            .map(u64::from)
+            .map(candid::Nat::from),
+        freezing_threshold: settings
+            .freezing_threshold
+            .map(u64::from)
+            .map(candid::Nat::from),
+    }
+}
+
 pub fn get_local_cid_and_candid_path(
     env: &dyn Environment,
     canister_name: &str,
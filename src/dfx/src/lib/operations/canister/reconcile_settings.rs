@@ -0,0 +1,178 @@
+use crate::config::dfinity::ConfigInterface;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::ic_attributes::{
+    get_compute_allocation, get_freezing_threshold, get_memory_allocation, CanisterSettings,
+};
+use crate::lib::identity::identity_utils::{caller_principals, controller_principal, CallSender};
+use crate::lib::operations::canister::{get_canister_status, update_settings};
+
+use anyhow::bail;
+use candid::Nat;
+use ic_types::Principal;
+use serde::Serialize;
+use slog::info;
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+/// A setting whose value in dfx.json differs from the live value of the canister.
+#[derive(Serialize)]
+pub struct SettingDrift {
+    pub setting: &'static str,
+    pub configured: String,
+    pub live: String,
+}
+
+fn configured_settings(
+    env: &dyn Environment,
+    config_interface: &ConfigInterface,
+    canister_name: &str,
+) -> DfxResult<CanisterSettings> {
+    let controllers = config_interface
+        .get_controllers(canister_name)?
+        .map(|controllers| {
+            controllers
+                .iter()
+                .map(|controller| controller_principal(env, controller))
+                .collect::<DfxResult<Vec<_>>>()
+        })
+        .transpose()?;
+    Ok(CanisterSettings {
+        controllers,
+        compute_allocation: get_compute_allocation(None, config_interface, canister_name)?,
+        memory_allocation: get_memory_allocation(None, config_interface, canister_name)?,
+        freezing_threshold: get_freezing_threshold(None, config_interface, canister_name)?,
+    })
+}
+
+fn principal_set(principals: &[Principal]) -> BTreeSet<String> {
+    principals.iter().map(Principal::to_text).collect()
+}
+
+/// Compare the settings configured in dfx.json with the live settings of a canister.
+/// Returns the settings that drifted, and the update that reconciles them.
+/// Settings that are not configured are never reported.
+pub async fn settings_drift(
+    env: &dyn Environment,
+    config_interface: &ConfigInterface,
+    canister_name: &str,
+    canister_id: Principal,
+    timeout: Duration,
+    call_sender: &CallSender,
+) -> DfxResult<(Vec<SettingDrift>, CanisterSettings)> {
+    let mut update = configured_settings(env, config_interface, canister_name)?;
+    let mut drift = vec![];
+    if update.controllers.is_none()
+        && update.compute_allocation.is_none()
+        && update.memory_allocation.is_none()
+        && update.freezing_threshold.is_none()
+    {
+        return Ok((drift, update));
+    }
+
+    let live = get_canister_status(env, canister_id, timeout, call_sender)
+        .await?
+        .settings;
+
+    if let Some(controllers) = &update.controllers {
+        let configured = principal_set(controllers);
+        let live = principal_set(&live.controllers);
+        if configured == live {
+            update.controllers = None;
+        } else {
+            drift.push(SettingDrift {
+                setting: "controllers",
+                configured: configured.into_iter().collect::<Vec<_>>().join(" "),
+                live: live.into_iter().collect::<Vec<_>>().join(" "),
+            });
+        }
+    }
+
+    let mut compare = |setting: &'static str, configured: Option<u64>, live: &Nat| -> bool {
+        match configured {
+            Some(configured) if Nat::from(configured) != *live => {
+                drift.push(SettingDrift {
+                    setting,
+                    configured: configured.to_string(),
+                    live: live.to_string(),
+                });
+                true
+            }
+            _ => false,
+        }
+    };
+    if !compare(
+        "compute_allocation",
+        update.compute_allocation.map(|x| u8::from(x) as u64),
+        &live.compute_allocation,
+    ) {
+        update.compute_allocation = None;
+    }
+    if !compare(
+        "memory_allocation",
+        update.memory_allocation.map(u64::from),
+        &live.memory_allocation,
+    ) {
+        update.memory_allocation = None;
+    }
+    if !compare(
+        "freezing_threshold",
+        update.freezing_threshold.map(u64::from),
+        &live.freezing_threshold,
+    ) {
+        update.freezing_threshold = None;
+    }
+
+    Ok((drift, update))
+}
+
+/// Apply the settings configured in dfx.json to a canister, where they drifted.
+pub async fn reconcile_settings(
+    env: &dyn Environment,
+    config_interface: &ConfigInterface,
+    canister_name: &str,
+    canister_id: Principal,
+    timeout: Duration,
+    call_sender: &CallSender,
+) -> DfxResult {
+    let log = env.get_logger();
+    let (drift, update) = settings_drift(
+        env,
+        config_interface,
+        canister_name,
+        canister_id,
+        timeout,
+        call_sender,
+    )
+    .await?;
+    if drift.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(controllers) = &update.controllers {
+        let callers = caller_principals(env, call_sender);
+        if !callers.iter().any(|caller| controllers.contains(caller)) {
+            bail!(
+                "The controllers of canister {} in dfx.json include neither {}. Add one of them to keep control of the canister.",
+                canister_name,
+                callers
+                    .iter()
+                    .map(Principal::to_text)
+                    .collect::<Vec<_>>()
+                    .join(" nor ")
+            );
+        }
+    }
+
+    for setting in &drift {
+        info!(
+            log,
+            "Updating {} of canister {}: {} -> {}",
+            setting.setting,
+            canister_name,
+            setting.live,
+            setting.configured
+        );
+    }
+    update_settings(env, canister_id, update, timeout, call_sender).await
+}
@@ -0,0 +1,143 @@
+use crate::config::dfinity::{ConfigCanistersCanisterCycles, ConfigInterface};
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::identity::identity_utils::CallSender;
+use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::operations::canister::{deposit_cycles, get_canister_status};
+
+use ic_types::Principal;
+use num_traits::cast::ToPrimitive;
+use serde::Serialize;
+use slog::{info, warn};
+use std::time::Duration;
+
+/// A canister whose cycles balance is below the minimum of its cycles policy.
+pub struct TopUp {
+    pub canister_name: String,
+    pub canister_id: Principal,
+    pub balance: u64,
+    pub policy: ConfigCanistersCanisterCycles,
+}
+
+impl TopUp {
+    /// The cycles that bring the balance back to the target.
+    pub fn shortfall(&self) -> u64 {
+        self.policy.target.saturating_sub(self.balance)
+    }
+}
+
+/// A top-up, for the json output of the commands that make them.
+#[derive(Serialize)]
+pub struct TopUpResult {
+    pub canister: String,
+    pub id: String,
+    pub balance: u64,
+    pub cycles: u64,
+    pub target: u64,
+    pub dry_run: bool,
+}
+
+impl TopUpResult {
+    pub fn new(top_up: &TopUp, dry_run: bool) -> Self {
+        TopUpResult {
+            canister: top_up.canister_name.clone(),
+            id: top_up.canister_id.to_text(),
+            balance: top_up.balance,
+            cycles: top_up.shortfall(),
+            target: top_up.policy.target,
+            dry_run,
+        }
+    }
+}
+
+/// Find the canisters whose cycles balance is below the minimum of their policy in dfx.json.
+/// Canisters without a policy, or that were not created yet, are skipped.
+pub async fn plan_top_ups(
+    env: &dyn Environment,
+    config_interface: &ConfigInterface,
+    canister_names: &[String],
+    timeout: Duration,
+    call_sender: &CallSender,
+) -> DfxResult<Vec<TopUp>> {
+    let log = env.get_logger();
+    let canister_id_store = CanisterIdStore::for_env(env)?;
+
+    let mut top_ups = vec![];
+    for canister_name in canister_names {
+        let policy = match config_interface.get_cycles_policy(canister_name)? {
+            Some(policy) => policy,
+            None => continue,
+        };
+        let canister_id = match canister_id_store.find(canister_name) {
+            Some(canister_id) => canister_id,
+            None => {
+                warn!(
+                    log,
+                    "Canister {} has a cycles policy but was not created yet.", canister_name
+                );
+                continue;
+            }
+        };
+
+        let status = get_canister_status(env, canister_id, timeout, call_sender).await?;
+        let balance = status.cycles.0.to_u64().unwrap_or(u64::MAX);
+        if balance >= policy.minimum {
+            info!(
+                log,
+                "Canister {} has {} cycles, at least its minimum of {}.",
+                canister_name,
+                balance,
+                policy.minimum
+            );
+            continue;
+        }
+        top_ups.push(TopUp {
+            canister_name: canister_name.clone(),
+            canister_id,
+            balance,
+            policy,
+        });
+    }
+    Ok(top_ups)
+}
+
+/// Deposit the shortfall of each canister from the wallet.
+pub async fn top_up_from_wallet(
+    env: &dyn Environment,
+    top_ups: &[TopUp],
+    timeout: Duration,
+    call_sender: &CallSender,
+    dry_run: bool,
+) -> DfxResult {
+    let log = env.get_logger();
+    for top_up in top_ups {
+        if dry_run {
+            info!(
+                log,
+                "Would deposit {} cycles onto {}, which has {} cycles, to reach its target of {}.",
+                top_up.shortfall(),
+                top_up.canister_name,
+                top_up.balance,
+                top_up.policy.target
+            );
+            continue;
+        }
+        info!(
+            log,
+            "Depositing {} cycles onto {}, which has {} cycles, to reach its target of {}.",
+            top_up.shortfall(),
+            top_up.canister_name,
+            top_up.balance,
+            top_up.policy.target
+        );
+        deposit_cycles(
+            env,
+            top_up.canister_id,
+            timeout,
+            call_sender,
+            top_up.shortfall(),
+        )
+        .await?;
+    }
+    Ok(())
+}
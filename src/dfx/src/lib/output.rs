@@ -0,0 +1,109 @@
+//! Results of commands, as set by the global `--output` option.
+//!
+//! In the `text` format commands print their results for humans. In the `json` format they
+//! print a single JSON document on stdout instead, so that scripts do not have to scrape
+//! the text. Logs and progress always go to stderr.
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::models::canister_id_store::CanisterIdStore;
+
+use anyhow::bail;
+use serde::Serialize;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Text
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => bail!("Invalid output format '{}', expected text or json.", s),
+        }
+    }
+}
+
+/// A canister of the project and its id.
+#[derive(Serialize)]
+pub struct NamedCanisterId {
+    pub canister: String,
+    pub id: String,
+}
+
+/// Look up the ids of canisters of the project on the network of the environment.
+pub fn named_canister_ids(
+    env: &dyn Environment,
+    canister_names: &[String],
+) -> DfxResult<Vec<NamedCanisterId>> {
+    let canister_id_store = CanisterIdStore::for_env(env)?;
+    canister_names
+        .iter()
+        .map(|canister_name| {
+            Ok(NamedCanisterId {
+                canister: canister_name.clone(),
+                id: canister_id_store.get(canister_name)?.to_text(),
+            })
+        })
+        .collect()
+}
+
+/// Print a line that is a result in the text format, but only progress in the json format,
+/// where it goes to stderr.
+pub fn print_progress(env: &dyn Environment, line: &str) {
+    match env.get_output_format() {
+        OutputFormat::Text => println!("{}", line),
+        OutputFormat::Json => eprintln!("{}", line),
+    }
+}
+
+/// Print the result of a command on stdout. In the json format the result is serialized,
+/// otherwise `print_text` prints it.
+pub fn print_result<T, F>(env: &dyn Environment, result: &T, print_text: F) -> DfxResult
+where
+    T: Serialize + ?Sized,
+    F: FnOnce(&T),
+{
+    match env.get_output_format() {
+        OutputFormat::Text => {
+            print_text(result);
+            Ok(())
+        }
+        OutputFormat::Json => print_json(result),
+    }
+}
+
+/// The format of a command with its own `--output` option, which may offer more formats than the
+/// global one, e.g. `table`. It defaults to the global format. The global `--output json`
+/// promises JSON on stdout, so asking the command for another format is an error.
+pub fn command_output_format<'a>(
+    env: &dyn Environment,
+    output: Option<&'a str>,
+) -> DfxResult<&'a str> {
+    match (env.get_output_format(), output) {
+        (OutputFormat::Json, Some(output)) if output != "json" => bail!(
+            "The command's `--output {}` conflicts with the global `--output json`.",
+            output
+        ),
+        (OutputFormat::Json, _) => Ok("json"),
+        (OutputFormat::Text, Some(output)) => Ok(output),
+        (OutputFormat::Text, None) => Ok("text"),
+    }
+}
+
+/// Print a result as JSON on stdout, for commands that print their text as they go.
+pub fn print_json<T: Serialize + ?Sized>(result: &T) -> DfxResult {
+    println!("{}", serde_json::to_string_pretty(result)?);
+    Ok(())
+}
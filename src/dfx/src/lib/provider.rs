@@ -1,4 +1,7 @@
-use crate::config::dfinity::{Config, ConfigNetwork, NetworkType, DEFAULT_IC_GATEWAY};
+use crate::config::dfinity::{
+    Config, ConfigNetwork, ConfigNetworkLedger, ConfigNetworkPinning, NetworkType,
+    DEFAULT_IC_GATEWAY,
+};
 use crate::lib::environment::{AgentEnvironment, Environment};
 use crate::lib::error::DfxResult;
 use crate::lib::network::network_descriptor::NetworkDescriptor;
@@ -58,6 +61,9 @@ pub fn get_network_descriptor<'a>(
                 providers: provider_urls,
                 r#type: network_provider.r#type,
                 is_ic: network_name == "ic" || network_name == DEFAULT_IC_GATEWAY,
+                protected: network_provider.protected,
+                pinning: network_provider.pinning.clone(),
+                ledger: network_provider.ledger.clone(),
             })
         }
         Some(ConfigNetwork::ConfigLocalProvider(local_provider)) => {
@@ -71,6 +77,9 @@ pub fn get_network_descriptor<'a>(
                 providers: provider_urls,
                 r#type: local_provider.r#type,
                 is_ic: false,
+                protected: local_provider.protected,
+                pinning: local_provider.pinning.clone(),
+                ledger: local_provider.ledger.clone(),
             })
         }
         None => {
@@ -88,6 +97,9 @@ pub fn get_network_descriptor<'a>(
                     providers: vec![url],
                     r#type: NetworkType::Ephemeral,
                     is_ic: network_name == "ic" || network_name == DEFAULT_IC_GATEWAY,
+                    protected: false,
+                    pinning: ConfigNetworkPinning::default(),
+                    ledger: ConfigNetworkLedger::default(),
                 })
             } else {
                 Err(anyhow!("ComputeNetworkNotFound({})", network_name))
@@ -1,2 +1,3 @@
+pub mod replay_transport;
 pub mod sign_transport;
 pub mod signed_message;
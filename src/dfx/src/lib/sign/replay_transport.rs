@@ -0,0 +1,486 @@
+//! Recording of the interactions with the replica, and their replay without a network, for the
+//! global `--record-replica <file>` and `--replay-replica <file>`.
+//!
+//! Requests are matched on their type, canister, method and argument, or on the paths of a
+//! read_state. Identical requests are replayed in the order they were recorded, and the last one
+//! is repeated.
+//!
+//! The agent verifies the certificates of read_state responses, so they are replayed as they were
+//! recorded. For the request status of an update call to be found in them, the call must have the
+//! request id it had when it was recorded: the agents of a command draw their nonces from a
+//! counter of the fixture, and update calls expire at the time that is saved in the fixture,
+//! which `util::ingress_expiry` returns. The builders of ic-utils set their own expiry, so dfx
+//! makes its update calls with the agent, or with `lib::operations::call`.
+use crate::lib::error::DfxResult;
+use crate::util::expiry_duration;
+
+use anyhow::anyhow;
+use ic_agent::agent::ReplicaV2Transport;
+use ic_agent::{AgentError, NonceFactory, RequestId};
+use ic_types::Principal;
+use serde::{Deserialize, Serialize};
+use serde_cbor::Value;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A request, by the fields it is matched on.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+struct Request {
+    request_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    canister_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    method_name: Option<String>,
+    /// The argument, in hex.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    arg: Option<String>,
+    /// The labels of the paths of a read_state, in hex.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    paths: Option<Vec<Vec<String>>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Response {
+    /// The CBOR body of the response to a query or a read_state, in hex.
+    Body(String),
+    /// An update call that the replica accepted.
+    Accepted,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Interaction {
+    request: Request,
+    response: Response,
+}
+
+/// The content of a fixture file.
+#[derive(Default, Deserialize, Serialize)]
+struct Fixture {
+    /// When the update calls expire, in nanoseconds since the UNIX epoch.
+    ingress_expiry: u64,
+    /// The CBOR body of the status of the replica, in hex.
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    interactions: Vec<Interaction>,
+}
+
+struct FixtureState {
+    fixture: Fixture,
+    /// The interactions that were replayed.
+    replayed: Vec<bool>,
+    /// The nonce of the last request.
+    nonce: u64,
+}
+
+/// A fixture that the agents of a command record to, or replay from.
+pub struct ReplicaFixture {
+    path: PathBuf,
+    replay: bool,
+    state: Mutex<FixtureState>,
+}
+
+impl ReplicaFixture {
+    /// The fixture of the `--record-replica` or `--replay-replica` option, if any.
+    pub fn from_options(
+        record: Option<PathBuf>,
+        replay: Option<PathBuf>,
+    ) -> DfxResult<Option<Arc<Self>>> {
+        let fixture = match (record, replay) {
+            (Some(path), _) => {
+                let expiry = SystemTime::now() + expiry_duration();
+                let fixture = Fixture {
+                    ingress_expiry: expiry.duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+                    ..Fixture::default()
+                };
+                ReplicaFixture {
+                    path,
+                    replay: false,
+                    state: Mutex::new(FixtureState {
+                        fixture,
+                        replayed: vec![],
+                        nonce: 0,
+                    }),
+                }
+            }
+            (None, Some(path)) => {
+                let content = std::fs::read_to_string(&path)
+                    .map_err(|e| anyhow!("Cannot read the fixture {}: {}", path.display(), e))?;
+                let fixture: Fixture = serde_json::from_str(&content)
+                    .map_err(|e| anyhow!("Invalid fixture {}: {}", path.display(), e))?;
+                let state = FixtureState {
+                    replayed: vec![false; fixture.interactions.len()],
+                    fixture,
+                    nonce: 0,
+                };
+                ReplicaFixture {
+                    path,
+                    replay: true,
+                    state: Mutex::new(state),
+                }
+            }
+            (None, None) => return Ok(None),
+        };
+        Ok(Some(Arc::new(fixture)))
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.replay
+    }
+
+    /// The time the update calls expire at, the same when recording and when replaying.
+    /// A recording must be done before then, or the replica rejects the calls.
+    pub fn ingress_expiry(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_nanos(self.state().fixture.ingress_expiry)
+    }
+
+    /// The nonces of the requests of a command, in the order the agents send them.
+    pub fn nonce_factory(fixture: &Arc<Self>) -> NonceFactory {
+        let fixture = fixture.clone();
+        NonceFactory::from_iterator(Box::new(std::iter::repeat_with(move || {
+            let mut state = fixture.state();
+            state.nonce += 1;
+            state.nonce.to_le_bytes().to_vec()
+        })))
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, FixtureState> {
+        self.state.lock().unwrap()
+    }
+
+    fn save(&self, state: &FixtureState) -> Result<(), AgentError> {
+        // The fixture is written after every interaction, as a command may exit at any point.
+        let json = serde_json::to_string_pretty(&state.fixture)
+            .map_err(|x| AgentError::MessageError(x.to_string()))?;
+        std::fs::write(&self.path, json + "\n").map_err(|x| {
+            AgentError::MessageError(format!(
+                "Cannot write the fixture {}: {}",
+                self.path.display(),
+                x
+            ))
+        })
+    }
+
+    fn record(&self, request: Request, response: Response) -> Result<(), AgentError> {
+        let mut state = self.state();
+        state
+            .fixture
+            .interactions
+            .push(Interaction { request, response });
+        self.save(&state)
+    }
+
+    fn record_status(&self, body: &[u8]) -> Result<(), AgentError> {
+        let mut state = self.state();
+        state.fixture.status = Some(hex::encode(body));
+        self.save(&state)
+    }
+
+    /// The response to the first request that matches and was not replayed yet, or else to the
+    /// last one that matches.
+    fn replay(&self, request: &Request) -> Result<Response, AgentError> {
+        let mut state = self.state();
+        let matches: Vec<usize> = state
+            .fixture
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| interaction.request == *request)
+            .map(|(index, _)| index)
+            .collect();
+        let index = matches
+            .iter()
+            .copied()
+            .find(|index| !state.replayed[*index])
+            .or_else(|| matches.last().copied())
+            .ok_or_else(|| {
+                AgentError::MessageError(format!(
+                    "The fixture {} has no response to {}.",
+                    self.path.display(),
+                    describe(request)
+                ))
+            })?;
+        state.replayed[index] = true;
+        Ok(state.fixture.interactions[index].response.clone())
+    }
+
+    fn replay_status(&self) -> Result<Vec<u8>, AgentError> {
+        let state = self.state();
+        let status = state.fixture.status.as_ref().ok_or_else(|| {
+            AgentError::MessageError(format!(
+                "The fixture {} has no status of the replica.",
+                self.path.display()
+            ))
+        })?;
+        hex::decode(status).map_err(|x| AgentError::MessageError(x.to_string()))
+    }
+}
+
+/// Records the interactions of the inner transport to a fixture.
+pub(crate) struct RecordReplicaV2Transport<T> {
+    inner: T,
+    fixture: Arc<ReplicaFixture>,
+}
+
+impl<T: ReplicaV2Transport> RecordReplicaV2Transport<T> {
+    pub fn new(inner: T, fixture: Arc<ReplicaFixture>) -> Self {
+        Self { inner, fixture }
+    }
+}
+
+impl<T: ReplicaV2Transport + Send + Sync> ReplicaV2Transport for RecordReplicaV2Transport<T> {
+    fn read_state<'a>(
+        &'a self,
+        effective_canister_id: Principal,
+        envelope: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let request = request(&envelope)?;
+            let body = self
+                .inner
+                .read_state(effective_canister_id, envelope)
+                .await?;
+            self.fixture
+                .record(request, Response::Body(hex::encode(&body)))?;
+            Ok(body)
+        })
+    }
+
+    fn call<'a>(
+        &'a self,
+        effective_canister_id: Principal,
+        envelope: Vec<u8>,
+        request_id: RequestId,
+    ) -> Pin<Box<dyn Future<Output = Result<(), AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let request = request(&envelope)?;
+            self.inner
+                .call(effective_canister_id, envelope, request_id)
+                .await?;
+            self.fixture.record(request, Response::Accepted)
+        })
+    }
+
+    fn query<'a>(
+        &'a self,
+        effective_canister_id: Principal,
+        envelope: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let request = request(&envelope)?;
+            let body = self.inner.query(effective_canister_id, envelope).await?;
+            self.fixture
+                .record(request, Response::Body(hex::encode(&body)))?;
+            Ok(body)
+        })
+    }
+
+    fn status<'a>(
+        &'a self,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let body = self.inner.status().await?;
+            self.fixture.record_status(&body)?;
+            Ok(body)
+        })
+    }
+}
+
+/// Replays the interactions of a fixture, without a network.
+pub(crate) struct ReplayReplicaV2Transport {
+    fixture: Arc<ReplicaFixture>,
+}
+
+impl ReplayReplicaV2Transport {
+    pub fn new(fixture: Arc<ReplicaFixture>) -> Self {
+        Self { fixture }
+    }
+}
+
+impl ReplicaV2Transport for ReplayReplicaV2Transport {
+    fn read_state<'a>(
+        &'a self,
+        _effective_canister_id: Principal,
+        envelope: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let request = request(&envelope)?;
+            body(self.fixture.replay(&request)?)
+        })
+    }
+
+    fn call<'a>(
+        &'a self,
+        _effective_canister_id: Principal,
+        envelope: Vec<u8>,
+        _request_id: RequestId,
+    ) -> Pin<Box<dyn Future<Output = Result<(), AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let request = request(&envelope)?;
+            self.fixture.replay(&request)?;
+            Ok(())
+        })
+    }
+
+    fn query<'a>(
+        &'a self,
+        _effective_canister_id: Principal,
+        envelope: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, AgentError>> + Send + 'a>> {
+        Box::pin(async move {
+            let request = request(&envelope)?;
+            body(self.fixture.replay(&request)?)
+        })
+    }
+
+    fn status<'a>(
+        &'a self,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, AgentError>> + Send + 'a>> {
+        Box::pin(async move { self.fixture.replay_status() })
+    }
+}
+
+fn message_error<E: ToString>(err: E) -> AgentError {
+    AgentError::MessageError(err.to_string())
+}
+
+fn get<'a>(map: &'a BTreeMap<Value, Value>, key: &str) -> Option<&'a Value> {
+    map.get(&Value::Text(key.to_string()))
+}
+
+/// The fields of the content of an envelope that requests are matched on.
+fn request(envelope: &[u8]) -> Result<Request, AgentError> {
+    let envelope: Value = serde_cbor::from_slice(envelope).map_err(message_error)?;
+    let content = match &envelope {
+        Value::Map(envelope) => match get(envelope, "content") {
+            Some(Value::Map(content)) => content,
+            _ => return Err(message_error("The envelope has no content.")),
+        },
+        _ => return Err(message_error("The envelope is not a map.")),
+    };
+    let text = |key| match get(content, key) {
+        Some(Value::Text(text)) => Some(text.clone()),
+        _ => None,
+    };
+    let bytes = |key| match get(content, key) {
+        Some(Value::Bytes(bytes)) => Some(bytes.clone()),
+        _ => None,
+    };
+    let paths = match get(content, "paths") {
+        Some(Value::Array(paths)) => Some(
+            paths
+                .iter()
+                .map(|path| match path {
+                    Value::Array(labels) => labels
+                        .iter()
+                        .map(|label| match label {
+                            Value::Bytes(label) => hex::encode(label),
+                            _ => String::new(),
+                        })
+                        .collect(),
+                    _ => vec![],
+                })
+                .collect(),
+        ),
+        _ => None,
+    };
+    Ok(Request {
+        request_type: text("request_type").unwrap_or_default(),
+        canister_id: bytes("canister_id").map(|id| match Principal::try_from(&id) {
+            Ok(principal) => principal.to_text(),
+            Err(_) => hex::encode(&id),
+        }),
+        method_name: text("method_name"),
+        arg: bytes("arg").map(hex::encode),
+        paths,
+    })
+}
+
+fn describe(request: &Request) -> String {
+    match (&request.method_name, &request.canister_id) {
+        (Some(method_name), Some(canister_id)) => format!(
+            "the {} of {} on {} with argument 0x{}",
+            request.request_type,
+            method_name,
+            canister_id,
+            request.arg.as_deref().unwrap_or_default()
+        ),
+        _ => match request.paths.as_ref().and_then(|paths| paths.first()) {
+            Some(path) if path.len() >= 2 && path[0] == hex::encode("request_status") => format!(
+                "the status of the update call 0x{}. Only update calls that expire at the time \
+                 saved in the fixture have the request id they were recorded with",
+                path[1]
+            ),
+            _ => format!("the {} {:?}", request.request_type, request.paths),
+        },
+    }
+}
+
+fn body(response: Response) -> Result<Vec<u8>, AgentError> {
+    match response {
+        Response::Body(body) => hex::decode(body).map_err(message_error),
+        _ => Err(message_error("The fixture has no body for the request.")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope(content: Vec<(&str, Value)>) -> Vec<u8> {
+        let content = content
+            .into_iter()
+            .map(|(key, value)| (Value::Text(key.to_string()), value))
+            .collect();
+        let mut envelope = BTreeMap::new();
+        envelope.insert(Value::Text("content".to_string()), Value::Map(content));
+        envelope.insert(Value::Text("sender_sig".to_string()), Value::Bytes(vec![1]));
+        serde_cbor::to_vec(&Value::Map(envelope)).unwrap()
+    }
+
+    #[test]
+    fn requests_are_matched_on_their_content() {
+        let call = |nonce: u8, expiry: i128| {
+            request(&envelope(vec![
+                ("request_type", Value::Text("call".to_string())),
+                (
+                    "canister_id",
+                    Value::Bytes(vec![0, 0, 0, 0, 0, 0, 0, 1, 1, 1]),
+                ),
+                ("method_name", Value::Text("inc".to_string())),
+                ("arg", Value::Bytes(b"DIDL\x00\x00".to_vec())),
+                ("nonce", Value::Bytes(vec![nonce])),
+                ("ingress_expiry", Value::Integer(expiry)),
+            ]))
+            .unwrap()
+        };
+        assert_eq!(call(1, 1_000), call(2, 2_000));
+        assert_eq!(call(1, 1_000).arg.as_deref(), Some("4449444c0000"));
+
+        let read_state = request(&envelope(vec![
+            ("request_type", Value::Text("read_state".to_string())),
+            (
+                "paths",
+                Value::Array(vec![Value::Array(vec![
+                    Value::Bytes(b"request_status".to_vec()),
+                    Value::Bytes(vec![7; 32]),
+                ])]),
+            ),
+        ]))
+        .unwrap();
+        assert_eq!(
+            read_state.paths,
+            Some(vec![vec![
+                hex::encode("request_status"),
+                hex::encode([7; 32])
+            ]])
+        );
+        assert!(describe(&read_state).contains(&hex::encode([7; 32])));
+    }
+}
@@ -1,6 +1,8 @@
 use crate::config::{dfx_version, dfx_version_str};
 use crate::lib::environment::{Environment, EnvironmentImpl};
 use crate::lib::logger::{create_root_logger, LoggingMode};
+use crate::lib::output::OutputFormat;
+use crate::lib::sign::replay_transport::ReplicaFixture;
 
 use clap::{AppSettings, Clap};
 use semver::Version;
@@ -31,6 +33,30 @@ pub struct CliOpts {
     #[clap(long)]
     identity: Option<String>,
 
+    /// Use the identity even if its principal is not the one pinned for the network in dfx.json.
+    #[clap(long)]
+    allow_identity_mismatch: bool,
+
+    /// The format of the results printed on stdout. In the json format, logs and progress
+    /// are printed on stderr only.
+    #[clap(long, default_value("text"), possible_values(&["text", "json"]))]
+    output: OutputFormat,
+
+    /// Appends every request to the replica and its response to a file, as lines of JSON with
+    /// the envelopes decoded and the signatures redacted.
+    #[clap(long)]
+    trace_http: Option<PathBuf>,
+
+    /// Records every request to the replica and its response to a fixture file, to be replayed
+    /// with --replay-replica.
+    #[clap(long, conflicts_with("replay-replica"))]
+    record_replica: Option<PathBuf>,
+
+    /// Answers the requests to the replica from a fixture file recorded with --record-replica,
+    /// without a network.
+    #[clap(long)]
+    replay_replica: Option<PathBuf>,
+
     #[clap(subcommand)]
     command: commands::Command,
 }
@@ -103,14 +129,25 @@ fn main() {
     let cli_opts = CliOpts::parse();
     let (progress_bar, log) = setup_logging(&cli_opts);
     let identity = cli_opts.identity;
+    let allow_identity_mismatch = cli_opts.allow_identity_mismatch;
+    let output_format = cli_opts.output;
+    let trace_http = cli_opts.trace_http;
+    let record_replica = cli_opts.record_replica;
+    let replay_replica = cli_opts.replay_replica;
     let command = cli_opts.command;
     let result = match EnvironmentImpl::new() {
         Ok(env) => {
             maybe_redirect_dfx(env.get_version()).map_or((), |_| unreachable!());
-            match EnvironmentImpl::new().map(|env| {
-                env.with_logger(log)
+            match EnvironmentImpl::new().and_then(|env| {
+                let replica_fixture = ReplicaFixture::from_options(record_replica, replay_replica)?;
+                Ok(env
+                    .with_logger(log)
                     .with_progress_bar(progress_bar)
                     .with_identity_override(identity)
+                    .with_identity_mismatch_allowed(allow_identity_mismatch)
+                    .with_output_format(output_format)
+                    .with_trace_http(trace_http)
+                    .with_replica_fixture(replica_fixture))
             }) {
                 Ok(env) => {
                     slog::trace!(
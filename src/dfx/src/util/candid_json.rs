@@ -0,0 +1,383 @@
+//! Conversion between Candid values and JSON, guided by the Candid types of a method.
+//!
+//! Records are JSON objects keyed by field name, and tuples are JSON arrays. A variant is an
+//! object with a single key, its tag, and a variant without payload can also be given as
+//! the string of its tag. An option is `null` or its value. `nat`, `int`, `nat64` and `int64`
+//! are strings in the output, so that JavaScript does not round them, and either numbers or
+//! strings in the input. Principals and services are their textual representation.
+use crate::lib::error::DfxResult;
+
+use anyhow::{anyhow, bail, Context};
+use candid::parser::typing::TypeEnv;
+use candid::parser::value::{IDLField, IDLValue, VariantValue};
+use candid::types::{Field, Label, Type};
+use candid::{IDLArgs, Int, Nat, Principal};
+use serde_json::{json, Map, Value};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Convert decoded values into a JSON array with one element per value.
+/// Values without a type, e.g. when the Candid interface is not known, are converted as they
+/// were decoded, with the hashes of the field names as keys.
+pub fn idl_args_to_json(args: &IDLArgs, env: &TypeEnv, types: &[Type]) -> DfxResult<Value> {
+    let values = args
+        .args
+        .iter()
+        .enumerate()
+        .map(|(i, value)| value_to_json(env, value, types.get(i)))
+        .collect::<DfxResult<Vec<_>>>()?;
+    Ok(Value::Array(values))
+}
+
+/// Convert a JSON array with one element per argument into Candid values of the given types.
+/// Trailing arguments of an optional type can be left out.
+pub fn idl_args_from_json(json: &str, env: &TypeEnv, types: &[Type]) -> DfxResult<IDLArgs> {
+    let json: Value = serde_json::from_str(json).map_err(|e| anyhow!("Invalid JSON: {}", e))?;
+    let values = match json {
+        Value::Array(values) => values,
+        _ => bail!("Expected a JSON array with one element per argument."),
+    };
+    if values.len() > types.len() {
+        bail!(
+            "Expected {} arguments but found {}.",
+            types.len(),
+            values.len()
+        );
+    }
+    let args = types
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| {
+            value_from_json(env, values.get(i).unwrap_or(&Value::Null), ty)
+                .with_context(|| format!("Invalid argument {}.", i + 1))
+        })
+        .collect::<DfxResult<Vec<_>>>()?;
+    Ok(IDLArgs { args })
+}
+
+fn value_to_json(env: &TypeEnv, value: &IDLValue, ty: Option<&Type>) -> DfxResult<Value> {
+    let ty = match ty {
+        Some(ty) => Some(env.trace_type(ty)?),
+        None => None,
+    };
+    let json = match value {
+        IDLValue::Null | IDLValue::None | IDLValue::Reserved => Value::Null,
+        IDLValue::Bool(b) => Value::Bool(*b),
+        IDLValue::Text(s) | IDLValue::Number(s) => Value::String(s.clone()),
+        IDLValue::Nat(n) => Value::String(n.0.to_string()),
+        IDLValue::Int(n) => Value::String(n.0.to_string()),
+        IDLValue::Nat64(n) => Value::String(n.to_string()),
+        IDLValue::Int64(n) => Value::String(n.to_string()),
+        IDLValue::Nat8(n) => Value::from(*n),
+        IDLValue::Nat16(n) => Value::from(*n),
+        IDLValue::Nat32(n) => Value::from(*n),
+        IDLValue::Int8(n) => Value::from(*n),
+        IDLValue::Int16(n) => Value::from(*n),
+        IDLValue::Int32(n) => Value::from(*n),
+        IDLValue::Float32(f) => Value::from(*f),
+        IDLValue::Float64(f) => Value::from(*f),
+        IDLValue::Principal(p) | IDLValue::Service(p) => Value::String(p.to_text()),
+        IDLValue::Func(p, method) => json!({ "principal": p.to_text(), "method": method }),
+        IDLValue::Opt(value) => {
+            let inner = match &ty {
+                Some(Type::Opt(inner)) => Some(inner.as_ref()),
+                _ => None,
+            };
+            value_to_json(env, value, inner)?
+        }
+        IDLValue::Vec(values) => {
+            let inner = match &ty {
+                Some(Type::Vec(inner)) => Some(inner.as_ref()),
+                _ => None,
+            };
+            let values = values
+                .iter()
+                .map(|value| value_to_json(env, value, inner))
+                .collect::<DfxResult<Vec<_>>>()?;
+            Value::Array(values)
+        }
+        IDLValue::Record(fields) => {
+            let field_types = match &ty {
+                Some(Type::Record(field_types)) => field_types.as_slice(),
+                _ => &[],
+            };
+            if is_tuple(fields.iter().map(|field| &field.id)) {
+                let values = fields
+                    .iter()
+                    .map(|field| value_to_json(env, &field.val, find_field(field_types, &field.id)))
+                    .collect::<DfxResult<Vec<_>>>()?;
+                Value::Array(values)
+            } else {
+                let mut object = Map::new();
+                for field in fields {
+                    object.insert(
+                        field_name(field_types, &field.id),
+                        value_to_json(env, &field.val, find_field(field_types, &field.id))?,
+                    );
+                }
+                Value::Object(object)
+            }
+        }
+        IDLValue::Variant(VariantValue(field, _)) => {
+            let field_types = match &ty {
+                Some(Type::Variant(field_types)) => field_types.as_slice(),
+                _ => &[],
+            };
+            let mut object = Map::new();
+            object.insert(
+                field_name(field_types, &field.id),
+                value_to_json(env, &field.val, find_field(field_types, &field.id))?,
+            );
+            Value::Object(object)
+        }
+    };
+    Ok(json)
+}
+
+fn value_from_json(env: &TypeEnv, json: &Value, ty: &Type) -> DfxResult<IDLValue> {
+    let ty = env.trace_type(ty)?;
+    let value = match (&ty, json) {
+        (Type::Null, Value::Null) => IDLValue::Null,
+        (Type::Reserved, _) => IDLValue::Reserved,
+        (Type::Opt(_), Value::Null) => IDLValue::None,
+        (Type::Opt(inner), json) => IDLValue::Opt(Box::new(value_from_json(env, json, inner)?)),
+        (Type::Bool, Value::Bool(b)) => IDLValue::Bool(*b),
+        (Type::Text, Value::String(s)) => IDLValue::Text(s.clone()),
+        (Type::Nat, json) => IDLValue::Nat(parse_number::<Nat>(json, &ty)?),
+        (Type::Int, json) => IDLValue::Int(parse_number::<Int>(json, &ty)?),
+        (Type::Nat8, json) => IDLValue::Nat8(parse_number(json, &ty)?),
+        (Type::Nat16, json) => IDLValue::Nat16(parse_number(json, &ty)?),
+        (Type::Nat32, json) => IDLValue::Nat32(parse_number(json, &ty)?),
+        (Type::Nat64, json) => IDLValue::Nat64(parse_number(json, &ty)?),
+        (Type::Int8, json) => IDLValue::Int8(parse_number(json, &ty)?),
+        (Type::Int16, json) => IDLValue::Int16(parse_number(json, &ty)?),
+        (Type::Int32, json) => IDLValue::Int32(parse_number(json, &ty)?),
+        (Type::Int64, json) => IDLValue::Int64(parse_number(json, &ty)?),
+        (Type::Float32, json) => IDLValue::Float32(parse_number(json, &ty)?),
+        (Type::Float64, json) => IDLValue::Float64(parse_number(json, &ty)?),
+        (Type::Principal, Value::String(s)) => IDLValue::Principal(parse_principal(s)?),
+        (Type::Service(_), Value::String(s)) => IDLValue::Service(parse_principal(s)?),
+        (Type::Func(_), Value::Object(object)) => {
+            match (object.get("principal"), object.get("method")) {
+                (Some(Value::String(principal)), Some(Value::String(method))) => {
+                    IDLValue::Func(parse_principal(principal)?, method.clone())
+                }
+                _ => bail!(
+                    "Expected a func as {{\"principal\": ..., \"method\": ...}} but found {}.",
+                    json
+                ),
+            }
+        }
+        (Type::Vec(inner), Value::Array(values)) => IDLValue::Vec(
+            values
+                .iter()
+                .map(|value| value_from_json(env, value, inner))
+                .collect::<DfxResult<Vec<_>>>()?,
+        ),
+        (Type::Record(field_types), Value::Array(values))
+            if is_tuple(field_types.iter().map(|field| &field.id)) =>
+        {
+            if values.len() > field_types.len() {
+                bail!(
+                    "Expected a tuple of {} values but found {}.",
+                    field_types.len(),
+                    json
+                );
+            }
+            let fields = field_types
+                .iter()
+                .enumerate()
+                .map(|(i, field)| {
+                    Ok(IDLField {
+                        id: field.id.clone(),
+                        val: value_from_json(env, values.get(i).unwrap_or(&Value::Null), &field.ty)
+                            .with_context(|| format!("Invalid value at index {}.", i))?,
+                    })
+                })
+                .collect::<DfxResult<Vec<_>>>()?;
+            IDLValue::Record(fields)
+        }
+        (Type::Record(field_types), Value::Object(object)) => {
+            if let Some(key) = object.keys().find(|key| {
+                !field_types
+                    .iter()
+                    .any(|field| &label_name(&field.id) == *key)
+            }) {
+                bail!("Unknown record field '{}'.", key);
+            }
+            let fields = field_types
+                .iter()
+                .map(|field| {
+                    let name = label_name(&field.id);
+                    Ok(IDLField {
+                        id: field.id.clone(),
+                        val: value_from_json(
+                            env,
+                            object.get(&name).unwrap_or(&Value::Null),
+                            &field.ty,
+                        )
+                        .with_context(|| format!("Invalid record field '{}'.", name))?,
+                    })
+                })
+                .collect::<DfxResult<Vec<_>>>()?;
+            IDLValue::Record(fields)
+        }
+        (Type::Variant(field_types), Value::String(tag)) => {
+            variant_from_json(env, field_types, tag, &Value::Null)?
+        }
+        (Type::Variant(field_types), Value::Object(object)) if object.len() == 1 => {
+            let (tag, value) = object.iter().next().unwrap();
+            variant_from_json(env, field_types, tag, value)?
+        }
+        _ => bail!("Expected a value of type {} but found {}.", ty, json),
+    };
+    Ok(value)
+}
+
+fn variant_from_json(
+    env: &TypeEnv,
+    field_types: &[Field],
+    tag: &str,
+    json: &Value,
+) -> DfxResult<IDLValue> {
+    let (index, field) = field_types
+        .iter()
+        .enumerate()
+        .find(|(_, field)| label_name(&field.id) == tag)
+        .ok_or_else(|| anyhow!("Unknown variant tag '{}'.", tag))?;
+    let val = value_from_json(env, json, &field.ty)
+        .with_context(|| format!("Invalid value of variant tag '{}'.", tag))?;
+    Ok(IDLValue::Variant(VariantValue(
+        Box::new(IDLField {
+            id: field.id.clone(),
+            val,
+        }),
+        index as u64,
+    )))
+}
+
+fn parse_number<T>(json: &Value, ty: &Type) -> DfxResult<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let text = match json {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        _ => bail!("Expected a value of type {} but found {}.", ty, json),
+    };
+    text.parse::<T>()
+        .map_err(|e| anyhow!("Invalid {} '{}': {}", ty, text, e))
+}
+
+fn parse_principal(text: &str) -> DfxResult<Principal> {
+    Principal::from_text(text).map_err(|e| anyhow!("Invalid principal '{}': {}", text, e))
+}
+
+/// Records whose fields are numbered from 0 are tuples.
+fn is_tuple<'a>(labels: impl Iterator<Item = &'a Label>) -> bool {
+    let mut count = 0;
+    for (i, label) in labels.enumerate() {
+        if label.get_id() != i as u32 {
+            return false;
+        }
+        count += 1;
+    }
+    count > 0
+}
+
+fn find_field<'a>(field_types: &'a [Field], label: &Label) -> Option<&'a Type> {
+    field_types
+        .iter()
+        .find(|field| field.id.get_id() == label.get_id())
+        .map(|field| &field.ty)
+}
+
+/// The name of a field as declared in its type, which decoded values only know by its hash.
+fn field_name(field_types: &[Field], label: &Label) -> String {
+    field_types
+        .iter()
+        .find(|field| field.id.get_id() == label.get_id())
+        .map_or_else(|| label_name(label), |field| label_name(&field.id))
+}
+
+fn label_name(label: &Label) -> String {
+    match label {
+        Label::Named(name) => name.clone(),
+        Label::Id(id) | Label::Unnamed(id) => id.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn person_type() -> Type {
+        Type::Record(vec![
+            Field {
+                id: Label::Named("name".to_string()),
+                ty: Type::Text,
+            },
+            Field {
+                id: Label::Named("age".to_string()),
+                ty: Type::Nat,
+            },
+            Field {
+                id: Label::Named("nickname".to_string()),
+                ty: Type::Opt(Box::new(Type::Text)),
+            },
+        ])
+    }
+
+    fn shape_type() -> Type {
+        Type::Variant(vec![
+            Field {
+                id: Label::Named("point".to_string()),
+                ty: Type::Null,
+            },
+            Field {
+                id: Label::Named("square".to_string()),
+                ty: Type::Nat8,
+            },
+        ])
+    }
+
+    #[test]
+    fn round_trips_records_and_variants() {
+        let env = TypeEnv::new();
+        let types = vec![person_type(), shape_type()];
+        let args = idl_args_from_json(
+            r#"[{"name": "Alice", "age": 30}, {"square": 4}]"#,
+            &env,
+            &types,
+        )
+        .unwrap();
+        assert_eq!(
+            idl_args_to_json(&args, &env, &types).unwrap(),
+            json!([{"name": "Alice", "age": "30", "nickname": null}, {"square": 4}])
+        );
+    }
+
+    #[test]
+    fn accepts_tags_of_variants_without_payload() {
+        let env = TypeEnv::new();
+        let args = idl_args_from_json(r#"["point"]"#, &env, &[shape_type()]).unwrap();
+        assert_eq!(
+            idl_args_to_json(&args, &env, &[shape_type()]).unwrap(),
+            json!([{ "point": null }])
+        );
+    }
+
+    #[test]
+    fn rejects_values_of_the_wrong_type() {
+        let env = TypeEnv::new();
+        assert!(idl_args_from_json(r#"[{"name": 1, "age": 30}]"#, &env, &[person_type()]).is_err());
+        assert!(idl_args_from_json(
+            r#"[{"name": "Bob", "age": 30, "x": 1}]"#,
+            &env,
+            &[person_type()]
+        )
+        .is_err());
+        assert!(idl_args_from_json(r#"[{"square": 300}]"#, &env, &[shape_type()]).is_err());
+    }
+}
@@ -37,6 +37,15 @@ pub fn memo_validator(memo: &str) -> Result<(), String> {
     Err("Must specify a non negative whole number.".to_string())
 }
 
+pub fn subaccount_validator(subaccount: &str) -> Result<(), String> {
+    let is_hex = subaccount.len() == 64 && subaccount.chars().all(|c| c.is_ascii_hexdigit());
+    let is_index = subaccount.len() != 64 && subaccount.parse::<u64>().is_ok();
+    if is_hex || is_index {
+        return Ok(());
+    }
+    Err("Must be a subaccount index or 64 hex digits.".to_string())
+}
+
 pub fn cycle_amount_validator(cycles: &str) -> Result<(), String> {
     if cycles.parse::<u64>().is_ok() {
         return Ok(());
@@ -1,3 +1,4 @@
+use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::{error_invalid_argument, error_invalid_data, error_unknown};
 
@@ -7,9 +8,10 @@ use candid::{parser::value::IDLValue, IDLArgs};
 use net2::TcpListenerExt;
 use net2::{unix::UnixTcpBuilderExt, TcpBuilder};
 use std::net::{IpAddr, SocketAddr};
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 pub mod assets;
+pub mod candid_json;
 pub mod clap;
 
 // The user can pass in port "0" to dfx start or dfx bootstrap i.e. "127.0.0.1:0" or "[::1]:0",
@@ -36,36 +38,57 @@ pub fn expiry_duration() -> Duration {
     Duration::from_secs(60 * 5)
 }
 
-/// Deserialize and print return values from canister method.
-pub fn print_idl_blob(
+/// The time an update call expires at. When recording or replaying the replica it is the time
+/// saved in the fixture, so that the call has the same request id in both.
+pub fn ingress_expiry(env: &dyn Environment) -> SystemTime {
+    match env.get_replica_fixture() {
+        Some(fixture) => fixture.ingress_expiry(),
+        None => SystemTime::now() + expiry_duration(),
+    }
+}
+
+/// Deserialize and format return values from canister method.
+pub fn format_idl_blob(
     blob: &[u8],
     output_type: Option<&str>,
     method_type: &Option<(TypeEnv, Function)>,
-) -> DfxResult<()> {
+) -> DfxResult<String> {
     let output_type = output_type.unwrap_or("pp");
     match output_type {
-        "raw" => {
-            let hex_string = hex::encode(blob);
-            println!("{}", hex_string);
-        }
-        "idl" | "pp" => {
-            let result = match method_type {
-                None => candid::IDLArgs::from_bytes(blob),
-                Some((env, func)) => candid::IDLArgs::from_bytes_with_types(blob, &env, &func.rets),
-            };
-            if result.is_err() {
-                let hex_string = hex::encode(blob);
-                eprintln!("Error deserializing blob 0x{}", hex_string);
-            }
-            if output_type == "idl" {
-                println!("{:?}", result?);
-            } else {
-                println!("{}", result?);
-            }
-        }
-        v => return Err(error_unknown!("Invalid output type: {}", v)),
+        "raw" => Ok(hex::encode(blob)),
+        "idl" => Ok(format!("{:?}", decode_idl_blob(blob, method_type)?)),
+        "pp" => Ok(format!("{}", decode_idl_blob(blob, method_type)?)),
+        "json" => Ok(serde_json::to_string_pretty(&json_from_idl_blob(
+            blob,
+            method_type,
+        )?)?),
+        v => Err(error_unknown!("Invalid output type: {}", v)),
     }
-    Ok(())
+}
+
+/// Deserialize return values from canister method into a JSON array, using the return types
+/// of the method for the names of record fields and variant tags.
+pub fn json_from_idl_blob(
+    blob: &[u8],
+    method_type: &Option<(TypeEnv, Function)>,
+) -> DfxResult<serde_json::Value> {
+    let args = decode_idl_blob(blob, method_type)?;
+    match method_type {
+        None => candid_json::idl_args_to_json(&args, &TypeEnv::new(), &[]),
+        Some((env, func)) => candid_json::idl_args_to_json(&args, env, &func.rets),
+    }
+}
+
+fn decode_idl_blob(blob: &[u8], method_type: &Option<(TypeEnv, Function)>) -> DfxResult<IDLArgs> {
+    let result = match method_type {
+        None => candid::IDLArgs::from_bytes(blob),
+        Some((env, func)) => candid::IDLArgs::from_bytes_with_types(blob, &env, &func.rets),
+    };
+    if result.is_err() {
+        let hex_string = hex::encode(blob);
+        eprintln!("Error deserializing blob 0x{}", hex_string);
+    }
+    Ok(result?)
 }
 
 /// Parse IDL file into TypeEnv. This is a best effort function: it will succeed if
@@ -168,6 +191,21 @@ pub fn blob_from_arguments(
             .map_err(|e| error_invalid_data!("Unable to serialize Candid values: {}", e))?;
             Ok(typed_args)
         }
+        "json" => {
+            let (env, func) = method_type.as_ref().ok_or_else(|| {
+                error_invalid_argument!(
+                    "JSON arguments need the Candid interface of the method, which is unknown."
+                )
+            })?;
+            let args = match arguments {
+                Some(arguments) => candid_json::idl_args_from_json(arguments, env, &func.args)
+                    .map_err(|e| error_invalid_argument!("Invalid JSON arguments: {:#}", e))?,
+                None if func.args.is_empty() => IDLArgs::new(&[]),
+                None => return Err(error_invalid_data!("Expected arguments but found none.")),
+            };
+            args.to_bytes_with_types(env, &func.args)
+                .map_err(|e| error_invalid_data!("Unable to serialize Candid values: {}", e))
+        }
         v => Err(error_unknown!("Invalid type: {}", v)),
     }
 }